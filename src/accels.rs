@@ -0,0 +1,242 @@
+//! Keyboard shortcuts of the app's actions
+
+use std::collections::HashMap;
+
+use gettextrs::gettext;
+use gtk::gio::Settings;
+use gtk::prelude::*;
+
+use crate::data::KeybindingMode;
+
+/// Actions and their default shortcuts.
+/// `library.project-add` is left out because `win.project-add` runs it from anywhere in the
+/// window, while its own shortcuts would only work with the library focused.
+pub const ACCELS: &[(&str, &[&str])] = &[
+    ("win.file-new", &["<Ctrl>N"]),
+    ("win.folder-new", &["<Shift><Ctrl>N"]),
+    ("win.project-add", &["<Ctrl><Shift>O"]),
+    ("win.file-save", &["<Ctrl>S"]),
+    ("win.file-rename-selected", &["F2"]),
+    ("win.file-close", &["<Ctrl>W"]),
+    ("win.journal-today", &["<Ctrl><Shift>J"]),
+    ("win.journal-previous", &[]),
+    ("win.journal-next", &[]),
+    ("editor.format-bold", &["<Ctrl>B"]),
+    ("editor.format-italic", &["<Ctrl>I"]),
+    ("editor.format-strikethrough", &[]),
+    ("editor.format-highlight", &[]),
+    ("editor.format-code", &[]),
+    ("editor.format-blockquote", &[]),
+    ("editor.format-link", &["<Ctrl>K"]),
+    ("editor.format-task", &["<Ctrl>Return"]),
+    ("editor.format-footnote", &["<Ctrl><Alt>F"]),
+    ("editor.table-reformat", &["<Ctrl><Shift>T"]),
+    ("editor.table-insert-row", &[]),
+    ("editor.table-remove-row", &[]),
+    ("editor.table-insert-column", &[]),
+    ("editor.table-remove-column", &[]),
+    ("editor.format-document", &["<Ctrl><Alt>L"]),
+    ("editor.reflow-paragraph", &["<Alt>Q"]),
+    ("editor.unwrap-paragraph", &["<Alt><Shift>Q"]),
+    ("editor.format-h1", &["<Ctrl>1"]),
+    ("editor.format-h2", &["<Ctrl>2"]),
+    ("editor.format-h3", &["<Ctrl>3"]),
+    ("editor.format-h4", &["<Ctrl>4"]),
+    ("editor.format-h5", &["<Ctrl>5"]),
+    ("editor.format-h6", &["<Ctrl>6"]),
+    ("editor.move-lines-up", &["<Alt>Up"]),
+    ("editor.move-lines-down", &["<Alt>Down"]),
+    ("editor.move-section-up", &["<Alt><Shift>Up"]),
+    ("editor.move-section-down", &["<Alt><Shift>Down"]),
+    ("editor.promote-section", &["<Alt><Shift>Left"]),
+    ("editor.demote-section", &["<Alt><Shift>Right"]),
+    ("editor.fold-toggle", &["<Ctrl><Alt>bracketleft"]),
+    ("editor.unfold-all", &["<Ctrl><Alt>bracketright"]),
+    ("editor.fold-level-1", &[]),
+    ("editor.fold-level-2", &[]),
+    ("editor.fold-level-3", &[]),
+    ("editor.fold-level-4", &[]),
+    ("editor.fold-level-5", &[]),
+    ("editor.fold-level-6", &[]),
+    ("editor.zoom-in", &["<Ctrl>plus"]),
+    ("editor.zoom-out", &["<Ctrl>minus"]),
+    ("editor.zoom-reset", &["<Ctrl>0"]),
+    ("editor.show-search", &["<Ctrl>F"]),
+    ("editor.show-search-replace", &["<Ctrl>R"]),
+    ("editor.hide-search", &["Escape"]),
+    // Not rebindable, it lets Shift+Return search backwards from the search entries
+    ("editor.shiftreturn", &["<Shift>Return"]),
+    ("win.library-refresh", &["F5"]),
+    ("library.sort-type::AlphanumericAsc", &[]),
+    ("library.sort-type::AlphanumericDesc", &[]),
+    ("library.sort-type::ModifiedDesc", &[]),
+    ("library.sort-type::ModifiedAsc", &[]),
+    ("library.sort-type::AccessedDesc", &[]),
+    ("library.sort-type::AccessedAsc", &[]),
+    ("win.check-links", &[]),
+    ("win.lint-document", &[]),
+    ("win.sprint-start", &[]),
+    ("win.sprint-stop", &[]),
+    ("win.toggle-sidebar", &["F9"]),
+    ("win.fullscreen", &["F11"]),
+    ("win.focus-mode", &["F8"]),
+    ("win.typewriter-mode", &["<Control><Alt>T"]),
+    ("win.show-help-overlay", &["<Control>question"]),
+    ("win.preferences", &["<ctrl>comma"]),
+    // Debugging tools, not rebindable
+    ("win.eat-inspector", &["<ctrl><Shift>I"]),
+    ("win.eat-adaptive-preview", &["<ctrl><Shift>M"]),
+];
+
+/// Name of a shortcut in preferences. Actions without one can't be rebound.
+pub fn title(action: &str) -> Option<String> {
+    let title = match action {
+        "win.file-new" => gettext("New Document"),
+        "win.folder-new" => gettext("New Folder"),
+        "win.project-add" => gettext("Add Project"),
+        "win.file-save" => gettext("Save Document"),
+        "win.file-rename-selected" => gettext("Rename"),
+        "win.file-close" => gettext("Close Document"),
+        "win.journal-today" => gettext("Open Today's Note"),
+        "win.journal-previous" => gettext("Previous Daily Note"),
+        "win.journal-next" => gettext("Next Daily Note"),
+        "editor.format-bold" => gettext("Bold"),
+        "editor.format-italic" => gettext("Italic"),
+        "editor.format-strikethrough" => gettext("Strikethrough"),
+        "editor.format-highlight" => gettext("Highlight"),
+        "editor.format-code" => gettext("Code"),
+        "editor.format-blockquote" => gettext("Quote"),
+        "editor.format-link" => gettext("Insert Link"),
+        "editor.format-task" => gettext("Toggle Task"),
+        "editor.format-footnote" => gettext("Insert Footnote"),
+        "editor.table-reformat" => gettext("Reformat Table"),
+        "editor.table-insert-row" => gettext("Insert Table Row"),
+        "editor.table-remove-row" => gettext("Remove Table Row"),
+        "editor.table-insert-column" => gettext("Insert Table Column"),
+        "editor.table-remove-column" => gettext("Remove Table Column"),
+        "editor.format-document" => gettext("Format Document"),
+        "editor.reflow-paragraph" => gettext("Reflow Paragraph"),
+        "editor.unwrap-paragraph" => gettext("Unwrap Paragraph"),
+        "editor.format-h1" => gettext("Heading 1"),
+        "editor.format-h2" => gettext("Heading 2"),
+        "editor.format-h3" => gettext("Heading 3"),
+        "editor.format-h4" => gettext("Heading 4"),
+        "editor.format-h5" => gettext("Heading 5"),
+        "editor.format-h6" => gettext("Heading 6"),
+        "editor.move-lines-up" => gettext("Move Lines Up"),
+        "editor.move-lines-down" => gettext("Move Lines Down"),
+        "editor.move-section-up" => gettext("Move Section Up"),
+        "editor.move-section-down" => gettext("Move Section Down"),
+        "editor.promote-section" => gettext("Promote Section"),
+        "editor.demote-section" => gettext("Demote Section"),
+        "editor.fold-toggle" => gettext("Fold Section"),
+        "editor.unfold-all" => gettext("Unfold All"),
+        "editor.fold-level-1" => gettext("Fold to Level 1"),
+        "editor.fold-level-2" => gettext("Fold to Level 2"),
+        "editor.fold-level-3" => gettext("Fold to Level 3"),
+        "editor.fold-level-4" => gettext("Fold to Level 4"),
+        "editor.fold-level-5" => gettext("Fold to Level 5"),
+        "editor.fold-level-6" => gettext("Fold to Level 6"),
+        "editor.zoom-in" => gettext("Zoom In"),
+        "editor.zoom-out" => gettext("Zoom Out"),
+        "editor.zoom-reset" => gettext("Reset Zoom"),
+        "editor.show-search" => gettext("Search Text"),
+        "editor.show-search-replace" => gettext("Search and Replace Text"),
+        "editor.hide-search" => gettext("Hide Search"),
+        "win.library-refresh" => gettext("Manual Refresh"),
+        "library.sort-type::AlphanumericAsc" => gettext("Sort A-Z"),
+        "library.sort-type::AlphanumericDesc" => gettext("Sort Z-A"),
+        "library.sort-type::ModifiedDesc" => gettext("Sort by Last Modified"),
+        "library.sort-type::ModifiedAsc" => gettext("Sort by First Modified"),
+        "library.sort-type::AccessedDesc" => gettext("Sort by Last Accessed"),
+        "library.sort-type::AccessedAsc" => gettext("Sort by First Accessed"),
+        "win.check-links" => gettext("Check Links"),
+        "win.lint-document" => gettext("Lint Document"),
+        "win.sprint-start" => gettext("Start Writing Sprint"),
+        "win.sprint-stop" => gettext("Stop Writing Sprint"),
+        "win.toggle-sidebar" => gettext("Toggle Sidebar"),
+        "win.fullscreen" => gettext("Fullscreen"),
+        "win.focus-mode" => gettext("Focus Mode"),
+        "win.typewriter-mode" => gettext("Typewriter Mode"),
+        "win.show-help-overlay" => gettext("Show Shortcuts"),
+        "win.preferences" => gettext("Preferences"),
+        _ => return None,
+    };
+    Some(title)
+}
+
+/// Shortcuts the user picked instead of the defaults, separated by spaces.
+/// An empty shortcut turns the action's off.
+pub fn custom_accels(settings: &Settings) -> HashMap<String, String> {
+    settings.value("custom-accels").get().unwrap_or_default()
+}
+
+/// Shortcuts of an action, leaving out the defaults that the keybinding mode uses for editing
+pub fn accels_for(
+    action: &str,
+    mode: KeybindingMode,
+    custom: &HashMap<String, String>,
+) -> Vec<String> {
+    if let Some(accels) = custom.get(action) {
+        return accels
+            .split_whitespace()
+            .filter(|accel| gtk::accelerator_parse(accel).is_some())
+            .map(|accel| accel.to_owned())
+            .collect();
+    }
+    let reserved: Vec<_> = mode
+        .reserved_accels()
+        .iter()
+        .filter_map(|accel| gtk::accelerator_parse(accel))
+        .collect();
+    ACCELS
+        .iter()
+        .find(|(name, _)| *name == action)
+        .map(|(_, accels)| *accels)
+        .unwrap_or_default()
+        .iter()
+        .filter(|accel| {
+            gtk::accelerator_parse(accel).is_none_or(|parsed| !reserved.contains(&parsed))
+        })
+        .map(|accel| accel.to_string())
+        .collect()
+}
+
+/// Another action that the shortcut already triggers
+pub fn conflict(settings: &Settings, action: &str, accel: &str) -> Option<&'static str> {
+    let parsed = gtk::accelerator_parse(accel)?;
+    let mode = keybinding_mode(settings);
+    let custom = custom_accels(settings);
+    ACCELS
+        .iter()
+        .map(|(other, _)| *other)
+        .filter(|other| *other != action)
+        .find(|other| {
+            accels_for(other, mode, &custom)
+                .iter()
+                .any(|accel| gtk::accelerator_parse(accel) == Some(parsed))
+        })
+}
+
+/// Set the shortcuts of all actions from the keybinding mode and the user's own shortcuts
+pub fn apply(app: &impl IsA<gtk::Application>, settings: &Settings) {
+    let mode = keybinding_mode(settings);
+    let custom = custom_accels(settings);
+    for (action, _) in ACCELS {
+        let accels = accels_for(action, mode, &custom);
+        let accels: Vec<&str> = accels.iter().map(String::as_str).collect();
+        app.set_accels_for_action(action, &accels);
+    }
+}
+
+/// Turn off the shortcuts of all actions, until they're set again with [`apply`]
+pub fn clear(app: &impl IsA<gtk::Application>) {
+    for (action, _) in ACCELS {
+        app.set_accels_for_action(action, &[]);
+    }
+}
+
+/// Keybinding mode picked in preferences
+pub fn keybinding_mode(settings: &Settings) -> KeybindingMode {
+    KeybindingMode::try_from(settings.string("editor-keybindings").as_str()).unwrap_or_default()
+}
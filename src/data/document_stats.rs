@@ -4,4 +4,6 @@ pub struct DocumentStats {
     pub num_chars: i32,
     pub num_spaces: i32,
     pub num_words: i32,
+    pub num_tasks: i32,
+    pub num_tasks_done: i32,
 }
@@ -0,0 +1,41 @@
+use std::fmt::Display;
+
+/// How much text around the cursor stays bright in typewriter mode
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum FocusScope {
+    #[default]
+    Line,
+    Sentence,
+    Paragraph,
+}
+
+impl FocusScope {
+    pub const ALL: [FocusScope; 3] = [
+        FocusScope::Line,
+        FocusScope::Sentence,
+        FocusScope::Paragraph,
+    ];
+}
+
+impl Display for FocusScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FocusScope::Line => write!(f, "Line"),
+            FocusScope::Sentence => write!(f, "Sentence"),
+            FocusScope::Paragraph => write!(f, "Paragraph"),
+        }
+    }
+}
+
+impl TryFrom<&str> for FocusScope {
+    type Error = ();
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "Line" => Ok(Self::Line),
+            "Sentence" => Ok(Self::Sentence),
+            "Paragraph" => Ok(Self::Paragraph),
+            _ => Err(()),
+        }
+    }
+}
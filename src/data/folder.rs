@@ -90,6 +90,8 @@ use crate::error::ScratchmarkError;
 use crate::util::file_actions;
 
 use super::Document;
+use super::Template;
+use super::template;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FolderType {
@@ -182,6 +184,15 @@ impl Folder {
         *self.imp().kind.get().unwrap()
     }
 
+    /// Root folder of the project this folder is in
+    pub fn project_path(&self) -> PathBuf {
+        let path = self.path();
+        path.ancestors()
+            .nth(self.depth() as usize)
+            .unwrap_or(&path)
+            .to_path_buf()
+    }
+
     pub fn is_root(&self) -> bool {
         match self.kind() {
             FolderType::Subfolder => false,
@@ -298,6 +309,20 @@ impl Folder {
         Ok(())
     }
 
+    /// New document filled in from the template
+    pub fn create_document_from_template<P: AsRef<Path>>(
+        &self,
+        name: P,
+        template: &Template,
+    ) -> Result<(), ScratchmarkError> {
+        let path = file_actions::incremented_path(self.path().join(name));
+        let project_name = template::project_name(&self.project_path());
+        let contents = template.expand(&path, &project_name)?;
+        file_actions::create_document_with_contents(&path, &contents)?;
+        self.emit_by_name::<()>("document-created", &[&path]);
+        Ok(())
+    }
+
     pub fn create_document_untitled(&self) -> Result<(), ScratchmarkError> {
         self.create_document("Untitled.md")
     }
@@ -0,0 +1,158 @@
+use std::fmt;
+use std::path::Path;
+use std::path::PathBuf;
+
+use gtk::glib;
+
+/// Journal notes are kept in this folder inside the journal project
+pub const JOURNAL_FOLDER: &str = "journal";
+
+/// Day of a journal note. Notes live at `journal/YYYY/MM/YYYY-MM-DD.md`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct JournalDate {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl JournalDate {
+    pub fn today() -> Option<Self> {
+        let now = glib::DateTime::now_local().ok()?;
+        Some(Self {
+            year: now.year(),
+            month: now.month() as u32,
+            day: now.day_of_month() as u32,
+        })
+    }
+
+    /// Parse `YYYY-MM-DD`
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut parts = text.split('-');
+        let (year, month, day) = (parts.next()?, parts.next()?, parts.next()?);
+        if parts.next().is_some() || year.len() != 4 || month.len() != 2 || day.len() != 2 {
+            return None;
+        }
+        let date = Self {
+            year: year.parse().ok()?,
+            month: month.parse().ok()?,
+            day: day.parse().ok()?,
+        };
+        let valid = (1..=12).contains(&date.month)
+            && (1..=days_in_month(date.year, date.month)).contains(&date.day);
+        valid.then_some(date)
+    }
+
+    /// The date of the note at `path`, if it's a journal note of the project at `root`
+    pub fn of_note(path: &Path, root: &Path) -> Option<Self> {
+        let date = Self::parse(&path.file_stem()?.to_string_lossy())?;
+        (date.note_path(root) == path).then_some(date)
+    }
+
+    pub fn note_path(&self, root: &Path) -> PathBuf {
+        root.join(JOURNAL_FOLDER)
+            .join(format!("{:04}", self.year))
+            .join(format!("{:02}", self.month))
+            .join(format!("{self}.md"))
+    }
+
+    /// The closest earlier day of `dates`, which are sorted
+    pub fn previous_of(&self, dates: &[JournalDate]) -> Option<Self> {
+        let index = dates.partition_point(|date| date < self);
+        index.checked_sub(1).map(|index| dates[index])
+    }
+
+    /// The closest later day of `dates`, which are sorted
+    pub fn next_of(&self, dates: &[JournalDate]) -> Option<Self> {
+        let index = dates.partition_point(|date| date <= self);
+        dates.get(index).copied()
+    }
+}
+
+/// Days that have a note in the journal of the project at `root`, sorted
+pub fn note_dates(root: &Path) -> Vec<JournalDate> {
+    fn entries(path: PathBuf) -> impl Iterator<Item = PathBuf> {
+        std::fs::read_dir(path)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path())
+    }
+
+    let mut dates: Vec<JournalDate> = entries(root.join(JOURNAL_FOLDER))
+        .flat_map(entries)
+        .flat_map(entries)
+        .filter_map(|path| JournalDate::of_note(&path, root))
+        .collect();
+    dates.sort();
+    dates
+}
+
+impl fmt::Display for JournalDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(text: &str) -> JournalDate {
+        JournalDate::parse(text).unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            JournalDate::parse("2024-02-29"),
+            Some(JournalDate {
+                year: 2024,
+                month: 2,
+                day: 29
+            })
+        );
+        assert_eq!(JournalDate::parse("2023-02-29"), None);
+        assert_eq!(JournalDate::parse("2024-13-01"), None);
+        assert_eq!(JournalDate::parse("2024-1-01"), None);
+        assert_eq!(JournalDate::parse("notes"), None);
+    }
+
+    #[test]
+    fn test_next_and_previous() {
+        let dates = [date("2024-02-28"), date("2024-03-02"), date("2025-01-01")];
+        assert_eq!(
+            date("2024-03-02").previous_of(&dates),
+            Some(date("2024-02-28"))
+        );
+        assert_eq!(
+            date("2024-03-01").previous_of(&dates),
+            Some(date("2024-02-28"))
+        );
+        assert_eq!(date("2024-02-28").previous_of(&dates), None);
+        assert_eq!(date("2024-03-02").next_of(&dates), Some(date("2025-01-01")));
+        assert_eq!(date("2024-02-29").next_of(&dates), Some(date("2024-03-02")));
+        assert_eq!(date("2025-01-01").next_of(&dates), None);
+        assert_eq!(date("2024-01-01").next_of(&[]), None);
+    }
+
+    #[test]
+    fn test_note_path() {
+        let root = Path::new("/library");
+        let path = date("2024-05-07").note_path(root);
+        assert_eq!(path, Path::new("/library/journal/2024/05/2024-05-07.md"));
+        assert_eq!(JournalDate::of_note(&path, root), Some(date("2024-05-07")));
+        assert_eq!(
+            JournalDate::of_note(Path::new("/library/2024-05-07.md"), root),
+            None
+        );
+    }
+}
@@ -0,0 +1,74 @@
+use std::fmt::Display;
+
+/// How keys are handled in the editor
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum KeybindingMode {
+    #[default]
+    Default,
+    Vim,
+    Emacs,
+}
+
+impl KeybindingMode {
+    pub const ALL: [KeybindingMode; 3] = [
+        KeybindingMode::Default,
+        KeybindingMode::Vim,
+        KeybindingMode::Emacs,
+    ];
+
+    /// App shortcuts that the mode needs for editing. They are turned off while it's used.
+    pub fn reserved_accels(&self) -> &'static [&'static str] {
+        match self {
+            KeybindingMode::Default => &[],
+            // Escape leaves insert mode, the others scroll and redo
+            KeybindingMode::Vim => &["Escape", "<Ctrl>B", "<Ctrl>F", "<Ctrl>R"],
+            KeybindingMode::Emacs => &[
+                "<Ctrl>A",
+                "<Ctrl>B",
+                "<Ctrl>D",
+                "<Ctrl>E",
+                "<Ctrl>F",
+                "<Ctrl>G",
+                "<Ctrl>K",
+                "<Ctrl>N",
+                "<Ctrl>P",
+                "<Ctrl>R",
+                "<Ctrl>S",
+                "<Ctrl>V",
+                "<Ctrl>W",
+                "<Ctrl>X",
+                "<Ctrl>Y",
+                "<Ctrl>space",
+                "<Ctrl>slash",
+                "<Alt>B",
+                "<Alt>D",
+                "<Alt>F",
+                "<Alt>V",
+                "<Alt>W",
+            ],
+        }
+    }
+}
+
+impl Display for KeybindingMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KeybindingMode::Default => write!(f, "Default"),
+            KeybindingMode::Vim => write!(f, "Vim"),
+            KeybindingMode::Emacs => write!(f, "Emacs"),
+        }
+    }
+}
+
+impl TryFrom<&str> for KeybindingMode {
+    type Error = ();
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "Default" => Ok(Self::Default),
+            "Vim" => Ok(Self::Vim),
+            "Emacs" => Ok(Self::Emacs),
+            _ => Err(()),
+        }
+    }
+}
@@ -0,0 +1,130 @@
+//! The text around the cursor that stays bright in typewriter mode.
+//! Paragraphs are Markdown blocks, and sentences don't cross them.
+
+use std::ops::Range;
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use super::formatter;
+use crate::util::regex as md_regex;
+
+/// End of a sentence with the quotes, brackets and emphasis that close it.
+/// Group 1 is the sentence end, the whitespace after it is left out.
+static SENTENCE_END: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"([.!?]+["'”’)\]*_]*)(?:\s+|$)"#).unwrap());
+
+/// Lines of the block at the line. Blocks end at blank lines, so `lines` only has to go from
+/// one blank line to the next.
+pub fn paragraph_lines(lines: &[&str], line: usize) -> Range<usize> {
+    formatter::block_at(lines, line).unwrap_or(line..line + 1)
+}
+
+/// Where a line is, in or out of fenced code
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FenceState {
+    /// Lines of the code block with its fences
+    Code(Range<usize>),
+    /// Lines between the code blocks around the line
+    Prose(Range<usize>),
+}
+
+/// Whether the line is in a fenced code block. `fence_lines` are the sorted numbers of the
+/// lines with a fence, which open and close blocks in turn.
+pub fn fence_state(fence_lines: &[usize], line: usize, line_count: usize) -> FenceState {
+    let before = fence_lines.partition_point(|fence| *fence < line);
+    let block_end = |i: usize| fence_lines.get(i).map_or(line_count, |fence| fence + 1);
+    if before % 2 == 1 {
+        // Inside a block, or on its closing fence
+        FenceState::Code(fence_lines[before - 1]..block_end(before))
+    } else if fence_lines.get(before) == Some(&line) {
+        FenceState::Code(line..block_end(before + 1))
+    } else {
+        let start = before.checked_sub(1).map_or(0, |i| fence_lines[i] + 1);
+        let end = fence_lines.get(before).copied().unwrap_or(line_count);
+        FenceState::Prose(start..end)
+    }
+}
+
+/// Byte range of the sentence at the offset of a paragraph.
+/// A list marker belongs to the first sentence of the item.
+pub fn sentence_at(text: &str, offset: usize) -> Range<usize> {
+    let marker_end = md_regex::LIST_ITEM_OPENING
+        .find(text)
+        .map_or(0, |marker| marker.end());
+    let mut start = 0;
+    for caps in SENTENCE_END.captures_iter(&text[marker_end..]) {
+        let end = marker_end + caps.get(1).unwrap().end();
+        if offset <= end {
+            return start..end;
+        }
+        start = marker_end + caps.get(0).unwrap().end();
+    }
+    start..text.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sentence(text: &str, offset: usize) -> &str {
+        &text[sentence_at(text, offset)]
+    }
+
+    #[test]
+    fn test_paragraph_lines() {
+        let lines = [
+            "# Heading",
+            "First line",
+            "second line.",
+            "- Item",
+            "- Item two",
+        ];
+        assert_eq!(paragraph_lines(&lines, 0), 0..1);
+        assert_eq!(paragraph_lines(&lines, 1), 1..3);
+        assert_eq!(paragraph_lines(&lines, 2), 1..3);
+        assert_eq!(paragraph_lines(&lines, 3), 3..4);
+        assert_eq!(paragraph_lines(&lines, 4), 4..5);
+        assert_eq!(paragraph_lines(&[""], 0), 0..1);
+    }
+
+    #[test]
+    fn test_fence_state() {
+        // Code on lines 2 to 5, and an unclosed block from line 8
+        let fences = [2, 5, 8];
+        assert_eq!(fence_state(&fences, 0, 10), FenceState::Prose(0..2));
+        assert_eq!(fence_state(&fences, 2, 10), FenceState::Code(2..6));
+        assert_eq!(fence_state(&fences, 3, 10), FenceState::Code(2..6));
+        assert_eq!(fence_state(&fences, 5, 10), FenceState::Code(2..6));
+        assert_eq!(fence_state(&fences, 6, 10), FenceState::Prose(6..8));
+        assert_eq!(fence_state(&fences, 9, 10), FenceState::Code(8..10));
+        assert_eq!(fence_state(&[], 3, 10), FenceState::Prose(0..10));
+    }
+
+    #[test]
+    fn test_sentence_at() {
+        let text = "One two. Three\nfour? Five";
+        assert_eq!(sentence(text, 0), "One two.");
+        assert_eq!(sentence(text, 8), "One two.");
+        assert_eq!(sentence(text, 9), "Three\nfour?");
+        assert_eq!(sentence(text, 16), "Three\nfour?");
+        assert_eq!(sentence(text, 22), "Five");
+        assert_eq!(sentence(text, text.len()), "Five");
+    }
+
+    #[test]
+    fn test_sentence_at_closing_marks() {
+        let text = "She said \"hi.\" Then *left.* Done";
+        assert_eq!(sentence(text, 2), "She said \"hi.\"");
+        assert_eq!(sentence(text, 17), "Then *left.*");
+        assert_eq!(sentence(text, 30), "Done");
+    }
+
+    #[test]
+    fn test_sentence_at_list_item() {
+        let text = "1. First one. Second";
+        assert_eq!(sentence(text, 0), "1. First one.");
+        assert_eq!(sentence(text, 16), "Second");
+        assert_eq!(sentence("Version 1.5 is out.", 10), "Version 1.5 is out.");
+    }
+}
@@ -0,0 +1,253 @@
+//! Finds footnote references and definitions in markdown text.
+//! Footnotes in fenced code blocks and code spans are skipped.
+
+use std::ops::Range;
+
+use crate::util::regex;
+
+/// A footnote reference or definition label.
+/// Offsets are in chars, like TextIter offsets. The range covers `[^label]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Footnote {
+    pub label: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// All footnote definitions, in document order
+pub fn definitions(text: &str) -> Vec<Footnote> {
+    let code = code_ranges(text);
+    let mut offsets = CharOffsets::new(text);
+    regex::FOOTNOTE_DEFINITION
+        .captures_iter(text)
+        .filter(|caps| !in_ranges(&code, caps.get(1).unwrap().start()))
+        .map(|caps| {
+            let label = caps.get(1).unwrap();
+            Footnote {
+                label: label.as_str().to_owned(),
+                // Include the brackets, but not the indent or the colon
+                start: offsets.at(label.start() - 2),
+                end: offsets.at(label.end() + 1),
+            }
+        })
+        .collect()
+}
+
+/// All footnote references, in document order. Definition labels are not included.
+pub fn references(text: &str) -> Vec<Footnote> {
+    let code = code_ranges(text);
+    let definition_starts: Vec<usize> = regex::FOOTNOTE_DEFINITION
+        .captures_iter(text)
+        .map(|caps| caps.get(1).unwrap().start() - 2)
+        .collect();
+    let mut offsets = CharOffsets::new(text);
+    regex::FOOTNOTE_REFERENCE
+        .captures_iter(text)
+        .filter(|caps| {
+            let start = caps.get(0).unwrap().start();
+            !definition_starts.contains(&start) && !in_ranges(&code, start)
+        })
+        .map(|caps| {
+            let m = caps.get(0).unwrap();
+            Footnote {
+                label: caps[1].to_owned(),
+                start: offsets.at(m.start()),
+                end: offsets.at(m.end()),
+            }
+        })
+        .collect()
+}
+
+/// References that have no matching definition
+pub fn undefined_references(text: &str) -> Vec<Footnote> {
+    let definitions = definitions(text);
+    references(text)
+        .into_iter()
+        .filter(|r| !definitions.iter().any(|d| d.label == r.label))
+        .collect()
+}
+
+/// The number after the highest numeric label in use
+pub fn next_number(text: &str) -> u32 {
+    definitions(text)
+        .iter()
+        .chain(references(text).iter())
+        .filter_map(|footnote| footnote.label.parse::<u32>().ok())
+        .max()
+        .map(|n| n + 1)
+        .unwrap_or(1)
+}
+
+/// Byte ranges of fenced code blocks, fences included, and of code spans
+fn code_ranges(text: &str) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut in_fence = false;
+    let mut line_start = 0;
+    for line in text.split('\n') {
+        let line_end = line_start + line.len();
+        if regex::CODE_FENCE.is_match(line) {
+            in_fence = !in_fence;
+            ranges.push(line_start..line_end);
+        } else if in_fence {
+            ranges.push(line_start..line_end);
+        } else {
+            ranges.extend(
+                code_spans(line)
+                    .into_iter()
+                    .map(|span| line_start + span.start..line_start + span.end),
+            );
+        }
+        line_start = line_end + 1;
+    }
+    ranges
+}
+
+/// Byte ranges of the code spans on a line, backticks included. A span ends at the next run
+/// of as many backticks as it started with.
+fn code_spans(line: &str) -> Vec<Range<usize>> {
+    let runs: Vec<Range<usize>> =
+        line.match_indices('`')
+            .fold(Vec::new(), |mut runs: Vec<Range<usize>>, (i, _)| {
+                match runs.last_mut() {
+                    Some(run) if run.end == i => run.end += 1,
+                    _ => runs.push(i..i + 1),
+                }
+                runs
+            });
+    let mut spans = Vec::new();
+    let mut i = 0;
+    while i < runs.len() {
+        let open = &runs[i];
+        match runs[i + 1..]
+            .iter()
+            .position(|close| close.len() == open.len())
+        {
+            Some(close) => {
+                spans.push(open.start..runs[i + 1 + close].end);
+                i += close + 2;
+            }
+            None => i += 1,
+        }
+    }
+    spans
+}
+
+fn in_ranges(ranges: &[Range<usize>], byte: usize) -> bool {
+    ranges.iter().any(|range| range.contains(&byte))
+}
+
+/// Converts ascending byte indices to char offsets without recounting from the start
+struct CharOffsets<'a> {
+    text: &'a str,
+    byte: usize,
+    char: usize,
+}
+
+impl<'a> CharOffsets<'a> {
+    fn new(text: &'a str) -> Self {
+        Self {
+            text,
+            byte: 0,
+            char: 0,
+        }
+    }
+
+    fn at(&mut self, byte: usize) -> usize {
+        if byte < self.byte {
+            self.byte = 0;
+            self.char = 0;
+        }
+        self.char += self.text[self.byte..byte].chars().count();
+        self.byte = byte;
+        self.char
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_definitions() {
+        let text = "foo[^1]\n\n[^1]: bar\n  [^note]: baz";
+        assert_eq!(
+            definitions(text),
+            vec![
+                Footnote {
+                    label: "1".into(),
+                    start: 9,
+                    end: 13
+                },
+                Footnote {
+                    label: "note".into(),
+                    start: 21,
+                    end: 28
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_references() {
+        let text = "foo[^1] and[^a]\n\n[^1]: bar[^2]";
+        assert_eq!(
+            references(text),
+            vec![
+                Footnote {
+                    label: "1".into(),
+                    start: 3,
+                    end: 7
+                },
+                Footnote {
+                    label: "a".into(),
+                    start: 11,
+                    end: 15
+                },
+                Footnote {
+                    label: "2".into(),
+                    start: 26,
+                    end: 30
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_references_char_offsets() {
+        let text = "äö[^1] ü[^2]";
+        let refs = references(text);
+        assert_eq!(refs[0].start, 2);
+        assert_eq!(refs[0].end, 6);
+        assert_eq!(refs[1].start, 8);
+    }
+
+    #[test]
+    fn test_undefined_references() {
+        let text = "foo[^1] bar[^2] baz[^1]\n\n[^1]: qux";
+        let undefined = undefined_references(text);
+        assert_eq!(undefined.len(), 1);
+        assert_eq!(undefined[0].label, "2");
+        assert!(undefined_references("[^1]\n[^1]: foo").is_empty());
+    }
+
+    #[test]
+    fn test_skip_code() {
+        let text = "a[^1] `b[^2]` ``c`[^3]``\n```\n[^4]\n[^5]: d\n```\n[^6]: e";
+        let labels = |footnotes: Vec<Footnote>| -> Vec<String> {
+            footnotes
+                .into_iter()
+                .map(|footnote| footnote.label)
+                .collect()
+        };
+        assert_eq!(labels(references(text)), vec!["1"]);
+        assert_eq!(labels(definitions(text)), vec!["6"]);
+    }
+
+    #[test]
+    fn test_next_number() {
+        assert_eq!(next_number(""), 1);
+        assert_eq!(next_number("foo[^1]"), 2);
+        assert_eq!(next_number("foo[^note]"), 1);
+        assert_eq!(next_number("foo[^2][^a]\n\n[^5]: bar"), 6);
+    }
+}
@@ -0,0 +1,937 @@
+//! Rewrites a document in one Markdown style: heading, bullet and emphasis markers, blank lines,
+//! table padding and optionally line wrapping. Only markup changes, never the content.
+//! Front matter, code and HTML blocks are kept as they are.
+
+use std::ops::Range;
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use super::table;
+use super::table::Table;
+use crate::util::regex as md_regex;
+
+/// Blockquote markers at the start of a line
+static QUOTE_PREFIX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^(?: {0,3}> ?)+").unwrap());
+
+/// Bullet list item with some text. Group 1 is everything before the marker.
+static BULLET_ITEM: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^((?: {0,3}> ?)*[ \t]*)[-*+][ \t]+\S").unwrap());
+
+/// Start of an HTML block, or of an autolink on its own line
+static HTML_BLOCK_OPENING: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^ {0,3}<(?:[A-Za-z]|/[A-Za-z]|!|\?)").unwrap());
+
+/// Ordered list marker, which starts a list if it's at the start of a line
+static ORDERED_MARKER: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\d{1,9}[.)]$").unwrap());
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatOptions {
+    /// Marker of bullet list items: `-`, `*` or `+`
+    pub bullet: char,
+    /// Delimiter of emphasis: `*` or `_`. Strong emphasis is left as it is.
+    pub emphasis: char,
+    /// Rewrap paragraphs at this column
+    pub wrap_column: Option<usize>,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            bullet: '-',
+            emphasis: '*',
+            wrap_column: None,
+        }
+    }
+}
+
+/// Output lines. Blank lines are only written between blocks, so runs of them collapse.
+#[derive(Default)]
+struct Output {
+    lines: Vec<String>,
+    blank_pending: bool,
+}
+
+impl Output {
+    fn push(&mut self, line: impl Into<String>) {
+        if self.blank_pending && !self.lines.is_empty() {
+            self.lines.push(String::new());
+        }
+        self.blank_pending = false;
+        self.lines.push(line.into());
+    }
+
+    fn blank(&mut self) {
+        self.blank_pending = true;
+    }
+
+    fn finish(self) -> String {
+        if self.lines.is_empty() {
+            return String::new();
+        }
+        self.lines.join("\n") + "\n"
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Block {
+    Blank,
+    /// Front matter, code and HTML, which are never changed
+    Verbatim,
+    AtxHeading,
+    /// A heading line and its underline
+    SetextHeading,
+    /// Thematic breaks and underlines of multi-line setext headings
+    Break,
+    Table,
+    /// Paragraphs with the markers of their list item or blockquote. Paragraphs that could be
+    /// code or tables can't be wrapped.
+    Paragraph {
+        wrappable: bool,
+    },
+}
+
+/// Split the lines into blocks, with the range of lines of each
+fn blocks(lines: &[&str]) -> Vec<(Block, Range<usize>)> {
+    let mut blocks = Vec::new();
+    let mut i = 0;
+
+    if lines.first().is_some_and(|line| line.trim_end() == "---")
+        && let Some(end) = lines[1..]
+            .iter()
+            .position(|line| matches!(line.trim_end(), "---" | "..."))
+    {
+        blocks.push((Block::Verbatim, 0..end + 2));
+        i = end + 2;
+    }
+
+    // Content indent of the last list item. Deeper indented lines after a blank line are code.
+    let mut list_indent: Option<usize> = None;
+    let mut after_paragraph = false;
+    while i < lines.len() {
+        let line = lines[i];
+        let (block, end) = if line.trim().is_empty() {
+            (Block::Blank, i + 1)
+        } else if let Some(end) = fenced_code_end(lines, i) {
+            (Block::Verbatim, end)
+        } else if !after_paragraph && indent_width(line) >= list_indent.unwrap_or(0) + 4 {
+            let code_indent = list_indent.unwrap_or(0) + 4;
+            (Block::Verbatim, indented_code_end(lines, i, code_indent))
+        } else if HTML_BLOCK_OPENING.is_match(line) {
+            let end = lines[i..]
+                .iter()
+                .position(|line| line.trim().is_empty())
+                .map_or(lines.len(), |n| i + n);
+            (Block::Verbatim, end)
+        } else if atx_heading(line).is_some() {
+            list_indent = None;
+            (Block::AtxHeading, i + 1)
+        } else if !after_paragraph
+            && is_plain_line(line)
+            && lines
+                .get(i + 1)
+                .is_some_and(|underline| setext_level(underline).is_some())
+        {
+            list_indent = None;
+            (Block::SetextHeading, i + 2)
+        } else if md_regex::THEMATIC_BREAK.is_match(line)
+            || md_regex::SETEXT_H_UNDERLINE.is_match(line)
+        {
+            list_indent = None;
+            (Block::Break, i + 1)
+        } else if let Some(end) = table_end(lines, i) {
+            (Block::Table, end)
+        } else {
+            let mut end = i + 1;
+            while end < lines.len() && is_continuation(lines[end], line) {
+                end += 1;
+            }
+
+            let quote_len = quote_prefix(line).len();
+            if let Some(marker) = md_regex::LIST_ITEM_OPENING.find(&line[quote_len..]) {
+                list_indent = Some(marker.as_str().chars().count());
+            } else if !after_paragraph && quote_len == 0 && indent_width(line) == 0 {
+                list_indent = None;
+            }
+            // Code in blockquotes and tables without a delimiter row
+            let wrappable = indent_width(&line[quote_len..]) < 4 + list_indent.unwrap_or(0)
+                && !lines[i..end].iter().any(|line| table::is_table_row(line));
+            (Block::Paragraph { wrappable }, end)
+        };
+        after_paragraph = matches!(block, Block::Paragraph { .. });
+        blocks.push((block, i..end));
+        i = end;
+    }
+    blocks
+}
+
+/// The formatted document, ending with a single newline
+pub fn format(text: &str, options: &FormatOptions) -> String {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut out = Output::default();
+    let mut lists = Vec::new();
+    for (block, range) in blocks(&lines) {
+        let block_lines = &lines[range.clone()];
+        if !matches!(block, Block::Blank | Block::Paragraph { .. }) {
+            lists.clear();
+        }
+        match block {
+            Block::Blank => out.blank(),
+            Block::Verbatim => {
+                for line in block_lines {
+                    out.push(*line);
+                }
+            }
+            Block::AtxHeading => {
+                let (level, title) = atx_heading(block_lines[0]).unwrap();
+                push_heading(&mut out, level, title, options);
+            }
+            Block::SetextHeading => {
+                let level = setext_level(block_lines[1]).unwrap();
+                push_heading(&mut out, level, block_lines[0].trim(), options);
+            }
+            Block::Break => out.push(block_lines[0].trim_end()),
+            Block::Table => {
+                let table = Table::parse(block_lines).unwrap();
+                for line in table.format().lines() {
+                    out.push(line);
+                }
+            }
+            Block::Paragraph { wrappable } => {
+                let paragraph: Vec<String> = block_lines
+                    .iter()
+                    .enumerate()
+                    .map(|(n, line)| {
+                        let line = if n == 0 {
+                            replace_bullet(line, options.bullet, &mut lists)
+                        } else {
+                            (*line).to_owned()
+                        };
+                        let line = convert_emphasis(&line, options.emphasis);
+                        trim_line_end(&line, n + 1 < block_lines.len())
+                    })
+                    .collect();
+                if options.wrap_column.is_some() && wrappable {
+                    let paragraph: Vec<&str> = paragraph.iter().map(String::as_str).collect();
+                    for line in reflow(&paragraph, options.wrap_column) {
+                        out.push(line);
+                    }
+                } else {
+                    for line in paragraph {
+                        out.push(line);
+                    }
+                }
+            }
+        }
+    }
+    out.finish()
+}
+
+/// Lines of the block at the line, like a paragraph, list item, heading or table
+pub fn block_at(lines: &[&str], line: usize) -> Option<Range<usize>> {
+    blocks(lines)
+        .into_iter()
+        .map(|(_, range)| range)
+        .find(|range| range.contains(&line))
+}
+
+/// Headings with their lines and level. Setext headings take two lines.
+pub fn headings(lines: &[&str]) -> Vec<(Range<usize>, usize)> {
+    blocks(lines)
+        .into_iter()
+        .filter_map(|(block, range)| match block {
+            Block::AtxHeading => Some((range.clone(), atx_heading(lines[range.start])?.0)),
+            Block::SetextHeading => Some((range.clone(), setext_level(lines[range.start + 1])?)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Reflow the paragraphs on the lines from `first` to `last`, see [`reflow`].
+/// Returns the lines to replace, which cover whole paragraphs, and their new text.
+/// None if there's no paragraph that can be wrapped on those lines.
+pub fn reflow_lines(
+    lines: &[&str],
+    first: usize,
+    last: usize,
+    column: Option<usize>,
+) -> Option<(Range<usize>, Vec<String>)> {
+    let blocks: Vec<(Block, Range<usize>)> = blocks(lines)
+        .into_iter()
+        .filter(|(_, range)| range.start <= last && range.end > first)
+        .collect();
+    if !blocks
+        .iter()
+        .any(|(block, _)| *block == Block::Paragraph { wrappable: true })
+    {
+        return None;
+    }
+
+    let start = blocks.first()?.1.start;
+    let end = blocks.last()?.1.end;
+    let mut new = Vec::new();
+    for (block, range) in blocks {
+        if block == (Block::Paragraph { wrappable: true }) {
+            new.extend(reflow(&lines[range], column));
+        } else {
+            new.extend(lines[range].iter().map(|line| (*line).to_owned()));
+        }
+    }
+    Some((start..end, new))
+}
+
+/// Re-wraps one paragraph at `column`, or joins its lines if there's no column.
+/// The blockquote and list markers of the first line are kept, and the other lines are indented
+/// to line up with its text. Hard line breaks stay where they are.
+pub fn reflow(lines: &[&str], column: Option<usize>) -> Vec<String> {
+    let Some(first) = lines.first() else {
+        return Vec::new();
+    };
+    let quote = quote_prefix(first);
+    let rest = &first[quote.len()..];
+    let marker_len = md_regex::LIST_ITEM_OPENING
+        .find(rest)
+        .map(|marker| marker.end())
+        .unwrap_or(rest.len() - rest.trim_start().len());
+    let first_prefix = &first[..quote.len() + marker_len];
+    let prefix = format!("{quote}{}", " ".repeat(rest[..marker_len].chars().count()));
+
+    let mut out = Vec::new();
+    let mut current = first_prefix.to_owned();
+    let mut line_empty = true;
+    for (n, line) in lines.iter().enumerate() {
+        let text = if n == 0 {
+            &first[first_prefix.len()..]
+        } else {
+            line[quote_prefix(line).len()..].trim_start()
+        };
+        let is_last = n + 1 == lines.len();
+        let hard_break = !is_last && (text.ends_with("  ") || text.ends_with('\\'));
+        for word in words(text.trim_end()) {
+            let width = current.chars().count() + 1 + word.chars().count();
+            if line_empty {
+                current.push_str(word);
+                line_empty = false;
+            } else if column.is_some_and(|column| width > column) && !starts_block(word) {
+                out.push(std::mem::replace(&mut current, prefix.clone() + word));
+            } else {
+                current.push(' ');
+                current.push_str(word);
+            }
+        }
+        if hard_break {
+            if text.ends_with("  ") {
+                current.push_str("  ");
+            }
+            out.push(std::mem::replace(&mut current, prefix.clone()));
+            line_empty = true;
+        }
+    }
+    if !line_empty || out.is_empty() {
+        out.push(current.trim_end().to_owned());
+    }
+    out
+}
+
+/// Words of a line. Code spans are never split, so the spaces inside them stay the same.
+fn words(text: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    let mut start = None;
+    let mut open_ticks = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((pos, c)) = chars.next() {
+        if c == '`' {
+            let mut ticks = 1;
+            while chars.next_if(|(_, c)| *c == '`').is_some() {
+                ticks += 1;
+            }
+            if open_ticks == 0 {
+                open_ticks = ticks;
+            } else if open_ticks == ticks {
+                open_ticks = 0;
+            }
+            start.get_or_insert(pos);
+        } else if c.is_whitespace() && open_ticks == 0 {
+            if let Some(start) = start.take() {
+                words.push(&text[start..pos]);
+            }
+        } else {
+            start.get_or_insert(pos);
+        }
+    }
+    if let Some(start) = start {
+        words.push(&text[start..]);
+    }
+    words
+}
+
+/// Would the word start a heading, list, blockquote or code block at the start of a line?
+fn starts_block(word: &str) -> bool {
+    let all = |c: char| word.chars().all(|w| w == c);
+    (all('#') && word.len() <= 6)
+        || word.starts_with('>')
+        || matches!(word, "-" | "+" | "*")
+        || all('=')
+        || all('-')
+        || all('*')
+        || all('_')
+        || ORDERED_MARKER.is_match(word)
+        || word.starts_with("```")
+        || word.starts_with("~~~")
+}
+
+fn quote_prefix(line: &str) -> &str {
+    QUOTE_PREFIX.find(line).map(|m| m.as_str()).unwrap_or("")
+}
+
+fn quote_depth(line: &str) -> usize {
+    quote_prefix(line).matches('>').count()
+}
+
+/// Leading whitespace, with tabs to the next multiple of four
+fn indent_width(line: &str) -> usize {
+    let mut width = 0;
+    for c in line.chars() {
+        match c {
+            ' ' => width += 1,
+            '\t' => width += 4 - width % 4,
+            _ => break,
+        }
+    }
+    width
+}
+
+/// A line that can only be paragraph text
+fn is_plain_line(line: &str) -> bool {
+    indent_width(line) < 4
+        && quote_prefix(line).is_empty()
+        && !md_regex::LIST_ITEM_OPENING.is_match(line)
+        && !table::is_table_row(line)
+}
+
+/// Level of a setext underline, which is only a heading underline without other markup
+fn setext_level(line: &str) -> Option<usize> {
+    if !md_regex::SETEXT_H_UNDERLINE.is_match(line) {
+        return None;
+    }
+    match line.trim().chars().next() {
+        Some('=') => Some(1),
+        Some('-') => Some(2),
+        _ => None,
+    }
+}
+
+/// Does the line continue the paragraph that starts with `first`?
+fn is_continuation(line: &str, first: &str) -> bool {
+    let quote = quote_prefix(line);
+    let rest = &line[quote.len()..];
+    !rest.trim().is_empty()
+        && quote_depth(line) == quote_depth(first)
+        && !md_regex::CODE_FENCE.is_match(rest)
+        && !md_regex::LIST_ITEM_OPENING.is_match(rest)
+        && !md_regex::THEMATIC_BREAK.is_match(rest)
+        && !md_regex::SETEXT_H_UNDERLINE.is_match(rest)
+        && !HTML_BLOCK_OPENING.is_match(rest)
+        && atx_heading(rest).is_none()
+        && (!table::is_table_row(rest) || table::is_table_row(first))
+}
+
+/// Index after the table, if one with a delimiter row starts at `start`
+fn table_end(lines: &[&str], start: usize) -> Option<usize> {
+    if !table::is_table_row(lines[start])
+        || !lines
+            .get(start + 1)
+            .is_some_and(|line| md_regex::TABLE_DELIMITER_ROW.is_match(line))
+    {
+        return None;
+    }
+    let end = lines[start..]
+        .iter()
+        .position(|line| !table::is_table_row(line))
+        .map_or(lines.len(), |n| start + n);
+    Table::parse(&lines[start..end]).map(|_| end)
+}
+
+/// Index after the closing fence, if a fenced code block starts at `start`.
+/// Unclosed blocks run to the end of the document.
+fn fenced_code_end(lines: &[&str], start: usize) -> Option<usize> {
+    let opening = md_regex::CODE_FENCE.find(lines[start])?;
+    let trimmed = lines[start].trim_start();
+    let fence_char = opening.as_str().chars().last()?;
+    let fence_len = trimmed.chars().take_while(|c| *c == fence_char).count();
+    let closing = lines[start + 1..].iter().position(|line| {
+        let line = line.trim();
+        line.chars().take_while(|c| *c == fence_char).count() >= fence_len
+            && line.chars().all(|c| c == fence_char)
+    });
+    Some(closing.map_or(lines.len(), |n| start + n + 2))
+}
+
+/// Index after an indented code block. Blank lines inside the block are part of it.
+fn indented_code_end(lines: &[&str], start: usize, code_indent: usize) -> usize {
+    let mut end = start + 1;
+    let mut n = end;
+    while n < lines.len() {
+        if lines[n].trim().is_empty() {
+            n += 1;
+        } else if indent_width(lines[n]) >= code_indent {
+            n += 1;
+            end = n;
+        } else {
+            break;
+        }
+    }
+    end
+}
+
+/// Heading level and title, if the line is an ATX heading
+fn atx_heading(line: &str) -> Option<(usize, &str)> {
+    let trimmed = line.trim_start_matches(' ');
+    if line.len() - trimmed.len() > 3 {
+        return None;
+    }
+    let level = trimmed.chars().take_while(|c| *c == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let rest = &trimmed[level..];
+    if !rest.is_empty() && !rest.starts_with([' ', '\t']) {
+        return None;
+    }
+    let title = rest.trim();
+    let without_closing = title.trim_end_matches('#');
+    if without_closing.is_empty() {
+        Some((level, ""))
+    } else if without_closing.ends_with([' ', '\t']) {
+        Some((level, without_closing.trim_end()))
+    } else {
+        Some((level, title))
+    }
+}
+
+fn push_heading(out: &mut Output, level: usize, title: &str, options: &FormatOptions) {
+    let mut heading = "#".repeat(level);
+    if !title.is_empty() {
+        heading.push(' ');
+        heading.push_str(&convert_emphasis(title, options.emphasis));
+        // Hashes at the end of a setext title would be read as the closing sequence
+        let without_hashes = heading.trim_end_matches('#');
+        if heading.ends_with('#') && without_hashes.ends_with([' ', '\t']) {
+            heading.insert(without_hashes.len(), '\\');
+        }
+    }
+    out.blank();
+    out.push(heading);
+    out.blank();
+}
+
+/// A bullet list that later items could belong to
+struct OpenList {
+    /// Blockquote markers and indent before the bullets
+    prefix: String,
+    /// Bullet in the document
+    bullet: char,
+    /// Bullet it's written with
+    new_bullet: char,
+}
+
+/// Swaps the bullet of a list item for `bullet`. Bullets only change for a whole list, and a list
+/// right after another one gets a different bullet, so that they stay two lists.
+fn replace_bullet(line: &str, bullet: char, lists: &mut Vec<OpenList>) -> String {
+    let mut line = line.to_owned();
+    if md_regex::THEMATIC_BREAK.is_match(&line) {
+        return line;
+    }
+    let Some(caps) = BULLET_ITEM.captures(&line) else {
+        let quote = quote_prefix(&line);
+        let rest = &line[quote.len()..];
+        if let Some(marker) = md_regex::LIST_ITEM_OPENING.find(rest) {
+            // An ordered list ends the bullet lists at its level and below
+            let indent = &rest[..marker.as_str().len() - marker.as_str().trim_start().len()];
+            let prefix = format!("{quote}{indent}");
+            lists.retain(|list| list.prefix.len() < prefix.len());
+        } else if quote.is_empty() && indent_width(&line) == 0 {
+            lists.clear();
+        }
+        return line;
+    };
+    let at = caps[1].len();
+    let prefix = &caps[1];
+    let old = line[at..].chars().next().unwrap();
+    lists.retain(|list| list.prefix.len() <= prefix.len());
+    let new_bullet = match lists.iter().position(|list| list.prefix == prefix) {
+        Some(i) if lists[i].bullet == old => lists[i].new_bullet,
+        open => {
+            let previous = open.map(|i| lists.remove(i).new_bullet);
+            let new_bullet = [bullet, old, '-', '*']
+                .into_iter()
+                .find(|c| Some(*c) != previous)
+                .unwrap();
+            lists.push(OpenList {
+                prefix: prefix.to_owned(),
+                bullet: old,
+                new_bullet,
+            });
+            new_bullet
+        }
+    };
+    line.replace_range(at..at + 1, &new_bullet.to_string());
+    line
+}
+
+/// Trailing whitespace is dropped, unless it's a hard line break
+fn trim_line_end(line: &str, keep_hard_break: bool) -> String {
+    let trimmed = line.trim_end();
+    if keep_hard_break && line.ends_with("  ") {
+        format!("{trimmed}  ")
+    } else {
+        trimmed.to_owned()
+    }
+}
+
+/// Swaps the delimiters of single emphasis for `target`. Only pairs that are emphasis with both
+/// delimiters are changed, so `_` inside words stays. Code, link destinations, HTML and bare
+/// URLs are skipped.
+fn convert_emphasis(line: &str, target: char) -> String {
+    let source = match target {
+        '*' => '_',
+        '_' => '*',
+        _ => return line.to_owned(),
+    };
+    let mut chars: Vec<char> = line.chars().collect();
+    let mut openers: Vec<usize> = Vec::new();
+    let mut pairs: Vec<usize> = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let prev = if i == 0 { ' ' } else { chars[i - 1] };
+        if c == '\\' {
+            i += 2;
+        } else if c == '`' {
+            let ticks = run_length(&chars, i, '`');
+            i = closing_ticks(&chars, i + ticks, ticks).unwrap_or(i + ticks);
+        } else if c == '<'
+            && chars
+                .get(i + 1)
+                .is_some_and(|c| c.is_ascii_alphabetic() || *c == '/' || *c == '!')
+        {
+            i = match chars[i..].iter().position(|c| *c == '>') {
+                Some(n) => i + n + 1,
+                None => i + 1,
+            };
+        } else if c == ']' && chars.get(i + 1) == Some(&'(') {
+            i = closing_paren(&chars, i + 1).map_or(i + 2, |n| n + 1);
+        } else if prev.is_whitespace() && starts_url(&chars[i..]) {
+            while i < chars.len() && !chars[i].is_whitespace() {
+                i += 1;
+            }
+        } else if c == source {
+            let length = run_length(&chars, i, source);
+            let next = chars.get(i + length).copied().unwrap_or(' ');
+            if length == 1 && prev != target && next != target {
+                let (source_open, source_close) = flanking(source, prev, next);
+                let (target_open, target_close) = flanking(target, prev, next);
+                if source_close && target_close && !openers.is_empty() {
+                    pairs.push(openers.pop().unwrap());
+                    pairs.push(i);
+                } else if source_open && target_open {
+                    openers.push(i);
+                } else if source_open || source_close {
+                    openers.clear();
+                }
+            } else if length != 2 {
+                openers.clear();
+            }
+            i += length;
+        } else {
+            i += 1;
+        }
+    }
+    for i in pairs {
+        chars[i] = target;
+    }
+    chars.into_iter().collect()
+}
+
+/// Whether a single delimiter between `prev` and `next` can open and close emphasis
+fn flanking(delimiter: char, prev: char, next: char) -> (bool, bool) {
+    let left = !next.is_whitespace()
+        && (!is_punctuation(next) || prev.is_whitespace() || is_punctuation(prev));
+    let right = !prev.is_whitespace()
+        && (!is_punctuation(prev) || next.is_whitespace() || is_punctuation(next));
+    if delimiter == '_' {
+        (
+            left && (!right || is_punctuation(prev)),
+            right && (!left || is_punctuation(next)),
+        )
+    } else {
+        (left, right)
+    }
+}
+
+fn is_punctuation(c: char) -> bool {
+    !c.is_alphanumeric() && !c.is_whitespace()
+}
+
+fn run_length(chars: &[char], start: usize, c: char) -> usize {
+    chars[start..].iter().take_while(|x| **x == c).count()
+}
+
+/// Index after the backtick run that closes a code span
+fn closing_ticks(chars: &[char], start: usize, ticks: usize) -> Option<usize> {
+    let mut i = start;
+    while i < chars.len() {
+        if chars[i] == '`' {
+            let length = run_length(chars, i, '`');
+            if length == ticks {
+                return Some(i + length);
+            }
+            i += length;
+        } else {
+            i += 1;
+        }
+    }
+    None
+}
+
+/// Index of the paren that closes the one at `open`
+fn closing_paren(chars: &[char], open: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in chars.iter().enumerate().skip(open) {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => (),
+        }
+    }
+    None
+}
+
+fn starts_url(chars: &[char]) -> bool {
+    ["http://", "https://", "www."].iter().any(|prefix| {
+        prefix.len() <= chars.len() && prefix.chars().zip(chars).all(|(a, b)| a == *b)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wrap(column: usize) -> FormatOptions {
+        FormatOptions {
+            wrap_column: Some(column),
+            ..FormatOptions::default()
+        }
+    }
+
+    /// The text without markup or whitespace, which formatting must keep
+    fn content(text: &str) -> String {
+        text.chars()
+            .filter(|c| !c.is_whitespace() && !"*_-+#=|:>".contains(*c))
+            .collect()
+    }
+
+    #[test]
+    fn test_headings() {
+        let options = FormatOptions::default();
+        assert_eq!(
+            format("##   Title ##\nText\n###### C#\n#NotHeading", &options),
+            "## Title\n\nText\n\n###### C#\n\n#NotHeading\n"
+        );
+        assert_eq!(
+            format("Title\n=====\n\nSub\n---\nText", &options),
+            "# Title\n\n## Sub\n\nText\n"
+        );
+        // Hashes at the end of the title aren't a closing sequence
+        assert_eq!(
+            format("Ask about #\n===\n\nIssue ##\n---\n\nC#\n===", &options),
+            "# Ask about \\#\n\n## Issue \\##\n\n# C#\n"
+        );
+        // Only single line setext headings are converted
+        assert_eq!(format("One\nTwo\n---\n", &options), "One\nTwo\n---\n");
+    }
+
+    #[test]
+    fn test_blank_lines_and_whitespace() {
+        let options = FormatOptions::default();
+        assert_eq!(
+            format("\n\nOne  \n\n\n\nTwo \t\nThree  \nFour  \n\n", &options),
+            "One\n\nTwo\nThree  \nFour\n"
+        );
+        assert_eq!(format("", &options), "");
+        assert_eq!(format("\n\n", &options), "");
+    }
+
+    #[test]
+    fn test_lists() {
+        let options = FormatOptions::default();
+        assert_eq!(
+            format("* one\n* two\n  + nested\n\n* * *\n1. first", &options),
+            "- one\n- two\n  - nested\n\n* * *\n1. first\n"
+        );
+        // A different bullet starts another list, which must stay separate
+        assert_eq!(
+            format("* one\n+ two\n  * nested\n* three", &options),
+            "- one\n+ two\n  - nested\n- three\n"
+        );
+        assert_eq!(format("- a\n* b\n", &options), "- a\n* b\n");
+        assert_eq!(
+            format("* a\n\n  text\n\n* b\n\nPara\n\n+ c\n", &options),
+            "- a\n\n  text\n\n- b\n\nPara\n\n- c\n"
+        );
+        let options = FormatOptions {
+            bullet: '*',
+            ..FormatOptions::default()
+        };
+        assert_eq!(
+            format("- one\n> - quoted\n\n---", &options),
+            "* one\n> * quoted\n\n---\n"
+        );
+    }
+
+    #[test]
+    fn test_emphasis() {
+        let options = FormatOptions::default();
+        assert_eq!(
+            format(
+                "_one_ *two* __strong__ snake_case `_code_` [_a_](a_b_.md) <i a=\"_x_\">",
+                &options
+            ),
+            "*one* *two* __strong__ snake_case `_code_` [*a*](a_b_.md) <i a=\"_x_\">\n"
+        );
+        assert_eq!(
+            format("https://example.com/_x_/ _y_", &options),
+            "https://example.com/_x_/ *y*\n"
+        );
+        let options = FormatOptions {
+            emphasis: '_',
+            ..FormatOptions::default()
+        };
+        assert_eq!(
+            format("*one* in*side*word **two** *a **b** c* \\*x*", &options),
+            "_one_ in*side*word **two** _a **b** c_ \\*x*\n"
+        );
+        assert_eq!(format("# *Title*", &options), "# _Title_\n");
+    }
+
+    #[test]
+    fn test_tables() {
+        let options = FormatOptions::default();
+        assert_eq!(
+            format("Text\n|a|b|\n|-|:-:|\n|long cell|x|\nAfter", &options),
+            "Text\n| a         |  b  |\n| --------- | :-: |\n| long cell |  x  |\nAfter\n"
+        );
+    }
+
+    #[test]
+    fn test_code_kept() {
+        let options = wrap(10);
+        let text = "---\ntitle:  x\n---\n\n```rust\nlet  a = *b*;\n\n\n# not heading  \n```\n\n    indented  *code*\n\n\n    more\n\n<div>\n  _html_\n</div>\n";
+        assert_eq!(format(text, &options), text);
+        // Unclosed fences run to the end
+        let text = "~~~\n* a\n\n\n";
+        assert_eq!(format(text, &options), "~~~\n* a\n\n\n");
+    }
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(
+            format("one two three four five six seven", &wrap(14)),
+            "one two three\nfour five six\nseven\n"
+        );
+        assert_eq!(
+            format(
+                "- one two three four\n  five\n> quoted text here",
+                &wrap(10)
+            ),
+            "- one two\n  three\n  four\n  five\n> quoted\n> text\n> here\n"
+        );
+        // Words that would start a block stay on the line before
+        assert_eq!(
+            format("aaaa bbbb - cc # dd", &wrap(9)),
+            "aaaa bbbb -\ncc # dd\n"
+        );
+        // Code spans are never split
+        assert_eq!(format("a `b  c` d", &wrap(3)), "a\n`b  c`\nd\n");
+        // Hard breaks stay
+        assert_eq!(
+            format("one two  \nthree\\\nfour five", &wrap(80)),
+            "one two  \nthree\\\nfour five\n"
+        );
+    }
+
+    #[test]
+    fn test_reflow_unwrap() {
+        assert_eq!(
+            reflow(&["> one", "> two", ">three"], None),
+            vec!["> one two three"]
+        );
+        assert_eq!(
+            reflow(&["1. one", "   two  ", "   three"], None),
+            vec!["1. one two  ", "   three"]
+        );
+        assert_eq!(
+            reflow(&["  indented", "  text"], Some(8)),
+            vec!["  indented", "  text"]
+        );
+    }
+
+    #[test]
+    fn test_reflow_lines() {
+        let lines = [
+            "Intro",
+            "",
+            "one two",
+            "three four",
+            "",
+            "```",
+            "code  code",
+            "```",
+            "> a b",
+            "> c",
+        ];
+        // A line is expanded to its paragraph
+        assert_eq!(
+            reflow_lines(&lines, 3, 3, Some(7)),
+            Some((2..4, vec!["one two".into(), "three".into(), "four".into()]))
+        );
+        assert_eq!(reflow_lines(&lines, 6, 6, None), None);
+        // Code between paragraphs stays
+        assert_eq!(
+            reflow_lines(&lines, 3, 9, None),
+            Some((
+                2..10,
+                [
+                    "one two three four",
+                    "",
+                    "```",
+                    "code  code",
+                    "```",
+                    "> a b c"
+                ]
+                .map(String::from)
+                .to_vec()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_content_kept() {
+        let text = "Title\n===\n\n\n*   item _one_\n+ item __two__\n    continued  \n    line\n\n\
+            > quote *with* `code  span` and a [link](https://x.org/a_b_c)\n\
+            > second line\n\n|h|i|\n|-|-|\n|1|2|\n\n```\nfn  x() {}\n```\n\
+            Paragraph with a very long line of text that will certainly be wrapped somewhere.\n";
+        for options in [FormatOptions::default(), wrap(20)] {
+            let formatted = format(text, &options);
+            assert_eq!(content(&formatted), content(text));
+            assert_eq!(format(&formatted, &options), formatted);
+            assert!(formatted.contains("```\nfn  x() {}\n```\n"));
+            assert!(formatted.contains("`code  span`"));
+        }
+    }
+}
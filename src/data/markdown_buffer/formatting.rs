@@ -1,7 +1,18 @@
+use std::ops::RangeInclusive;
+
 use adw::prelude::*;
 
 use gtk::TextIter;
 
+use super::footnotes;
+use super::formatter;
+use super::formatter::FormatOptions;
+use super::link_rewrite::LinkEdit;
+use super::sections;
+use super::sections::LinesEdit;
+use super::table;
+use super::table::Alignment;
+use super::table::Table;
 use crate::util::regex;
 
 pub fn format_bold(buffer: &impl TextBufferExt) {
@@ -331,6 +342,649 @@ pub fn format_code(buffer: &impl TextBufferExt) {
     buffer.end_user_action();
 }
 
+/// Toggles task items on the cursor line or every selected line.
+/// If none of the lines are task items, they are turned into unchecked ones instead.
+pub fn format_task(buffer: &impl TextBufferExt) {
+    let (selection_start, selection_end) = buffer.selection_bounds().unwrap_or_else(|| {
+        let iter = buffer.iter_at_mark(&buffer.get_insert());
+        (iter, iter)
+    });
+    let first_line = selection_start.line();
+    let last_line = selection_end.line();
+
+    let lines: Vec<String> = (first_line..=last_line)
+        .map(|line| line_text(buffer, line))
+        .collect();
+    let task_states: Vec<&str> = lines
+        .iter()
+        .filter_map(|line| regex::TASK_ITEM.captures(line))
+        .map(|caps| caps.get(2).unwrap().as_str())
+        .collect();
+
+    buffer.begin_user_action();
+    if task_states.is_empty() {
+        for (i, line) in lines.iter().enumerate() {
+            if line.trim().is_empty() && first_line != last_line {
+                continue;
+            }
+            let ln = first_line + i as i32;
+            let (offset, marker) = match regex::LIST_ITEM_OPENING.find(line) {
+                Some(m) => (char_len(m.as_str()), "[ ] "),
+                None => (
+                    char_len(&line[..line.len() - line.trim_start().len()]),
+                    "- [ ] ",
+                ),
+            };
+            let mut iter = buffer.iter_at_line_offset(ln, offset).unwrap();
+            buffer.insert(&mut iter, marker);
+        }
+    } else {
+        let all_done = task_states.iter().all(|state| *state != " ");
+        let new_state = if all_done { " " } else { "x" };
+        for ln in first_line..=last_line {
+            set_task_state(buffer, ln, new_state);
+        }
+    }
+    buffer.end_user_action();
+}
+
+/// Toggles the task item if the iter is on its checkbox. Returns true if it was toggled.
+pub fn toggle_task_at(buffer: &impl TextBufferExt, iter: &TextIter) -> bool {
+    let ln = iter.line();
+    let line = line_text(buffer, ln);
+    let Some(caps) = regex::TASK_ITEM.captures(&line) else {
+        return false;
+    };
+    let box_start = char_len(caps.get(1).unwrap().as_str());
+    let box_end = box_start + 3;
+    let column = iter.line_offset();
+    if column < box_start || column > box_end {
+        return false;
+    }
+    let new_state = if caps.get(2).unwrap().as_str() == " " {
+        "x"
+    } else {
+        " "
+    };
+    buffer.begin_user_action();
+    set_task_state(buffer, ln, new_state);
+    buffer.end_user_action();
+    true
+}
+
+/// Replaces the state char of a task item. Does nothing if the line isn't one.
+fn set_task_state(buffer: &impl TextBufferExt, line: i32, state: &str) {
+    let text = line_text(buffer, line);
+    let Some(caps) = regex::TASK_ITEM.captures(&text) else {
+        return;
+    };
+    let old_state = caps.get(2).unwrap();
+    if old_state.as_str() == state {
+        return;
+    }
+    let offset = char_len(&text[..old_state.start()]);
+    let mut start = buffer.iter_at_line_offset(line, offset).unwrap();
+    let mut end = buffer.iter_at_line_offset(line, offset + 1).unwrap();
+    buffer.delete(&mut start, &mut end);
+    let mut iter = buffer.iter_at_line_offset(line, offset).unwrap();
+    buffer.insert(&mut iter, state);
+}
+
+/// Insert a link, or an image link, at the iter and place the cursor after it.
+pub fn insert_link(
+    buffer: &impl TextBufferExt,
+    iter: &TextIter,
+    title: &str,
+    destination: &str,
+    is_image: bool,
+) {
+    let prefix = if is_image { "!" } else { "" };
+    let mut iter = *iter;
+    buffer.begin_user_action();
+    buffer.insert(&mut iter, &format!("{prefix}[{title}]({destination})"));
+    buffer.place_cursor(&iter);
+    buffer.end_user_action();
+}
+
+/// Apply edits from a link rewrite as one undoable step. The cursor stays where it was.
+pub fn apply_link_edits(buffer: &impl TextBufferExt, edits: &[LinkEdit]) {
+    let cursor = buffer.create_mark(None, &buffer.iter_at_mark(&buffer.get_insert()), false);
+    buffer.begin_user_action();
+    // Back to front, so earlier offsets stay valid
+    for edit in edits.iter().rev() {
+        let mut start = buffer.iter_at_offset(edit.start as i32);
+        let mut end = buffer.iter_at_offset(edit.end as i32);
+        buffer.delete(&mut start, &mut end);
+        buffer.insert(&mut start, &edit.new);
+    }
+    buffer.end_user_action();
+    buffer.place_cursor(&buffer.iter_at_mark(&cursor));
+    buffer.delete_mark(&cursor);
+}
+
+/// Rewrite the document in one Markdown style as one undoable step.
+/// Only the part that changed is replaced, so the cursor stays near where it was.
+/// Returns false if the document was formatted already.
+pub fn format_document(buffer: &impl TextBufferExt, options: &FormatOptions) -> bool {
+    let (start, end) = buffer.bounds();
+    let text = buffer.text(&start, &end, true);
+    let formatted = formatter::format(&text, options);
+    if formatted == text.as_str() {
+        return false;
+    }
+
+    let prefix = text
+        .chars()
+        .zip(formatted.chars())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let max_suffix = char_len(&text).min(char_len(&formatted)) as usize - prefix;
+    let suffix = text
+        .chars()
+        .rev()
+        .zip(formatted.chars().rev())
+        .take(max_suffix)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let new: String = formatted
+        .chars()
+        .skip(prefix)
+        .take(char_len(&formatted) as usize - prefix - suffix)
+        .collect();
+
+    let mut start = buffer.iter_at_offset(prefix as i32);
+    let mut end = buffer.iter_at_offset(char_len(&text) - suffix as i32);
+    buffer.begin_user_action();
+    buffer.delete(&mut start, &mut end);
+    buffer.insert(&mut start, &new);
+    buffer.end_user_action();
+    true
+}
+
+/// Rewrap the paragraphs under the cursor or selection at `column`, or join their lines if
+/// there's no column. Code blocks are skipped. Returns false if there's no paragraph.
+pub fn reflow_paragraph(buffer: &impl TextBufferExt, column: Option<usize>) -> bool {
+    let cursor = buffer.iter_at_mark(&buffer.get_insert());
+    let had_selection = buffer.has_selection();
+    let (selection_start, selection_end) = buffer.selection_bounds().unwrap_or((cursor, cursor));
+    let (start, end) = buffer.bounds();
+    let text = buffer.text(&start, &end, true);
+    let lines: Vec<&str> = text.split('\n').collect();
+    let Some((range, new)) = formatter::reflow_lines(
+        &lines,
+        selection_start.line() as usize,
+        selection_end.line() as usize,
+        column,
+    ) else {
+        return false;
+    };
+
+    let mut start = buffer.iter_at_line(range.start as i32).unwrap();
+    let mut end = buffer.iter_at_line(range.end as i32 - 1).unwrap();
+    if !end.ends_line() {
+        end.forward_to_line_end();
+    }
+    let new = new.join("\n");
+    let old = buffer.text(&start, &end, true);
+    if old.as_str() == new {
+        return true;
+    }
+    // The cursor stays after the same word
+    let cursor_offset = (cursor.offset() - start.offset()).max(0) as usize;
+    let words_before = content_chars(&old.chars().take(cursor_offset).collect::<String>());
+
+    buffer.begin_user_action();
+    buffer.delete(&mut start, &mut end);
+    let first = start.offset();
+    buffer.insert(&mut start, &new);
+    if had_selection {
+        buffer.select_range(&buffer.iter_at_offset(first), &start);
+    } else {
+        let offset = first + content_offset(&new, words_before) as i32;
+        buffer.place_cursor(&buffer.iter_at_offset(offset));
+    }
+    buffer.end_user_action();
+    true
+}
+
+/// Swap the section at the cursor with its neighbor of the same level.
+/// Returns false if there's none.
+pub fn move_section(buffer: &impl TextBufferExt, up: bool) -> bool {
+    let cursor = buffer.iter_at_mark(&buffer.get_insert());
+    let (start, end) = buffer.bounds();
+    let text = buffer.text(&start, &end, true);
+    let lines: Vec<&str> = text.split('\n').collect();
+    match sections::move_section(&lines, cursor.line() as usize, up) {
+        Some(edit) => {
+            apply_lines_edit(buffer, &edit, &cursor);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Promote or demote the heading of the section at the cursor and the headings inside it.
+/// Returns false if there's no section or a heading can't go further.
+pub fn shift_section(buffer: &impl TextBufferExt, promote: bool) -> bool {
+    let cursor = buffer.iter_at_mark(&buffer.get_insert());
+    let (start, end) = buffer.bounds();
+    let text = buffer.text(&start, &end, true);
+    let lines: Vec<&str> = text.split('\n').collect();
+    match sections::shift_section(&lines, cursor.line() as usize, promote) {
+        Some(edit) => {
+            apply_lines_edit(buffer, &edit, &cursor);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Replace whole lines. The cursor stays in its column and moves with its line.
+fn apply_lines_edit(buffer: &impl TextBufferExt, edit: &LinesEdit, cursor: &TextIter) {
+    let mut start = buffer.iter_at_line(edit.range.start as i32).unwrap();
+    let mut end = buffer.iter_at_line(edit.range.end as i32 - 1).unwrap();
+    if !end.ends_line() {
+        end.forward_to_line_end();
+    }
+    let line = cursor.line() + edit.offset as i32;
+    let column = cursor.line_offset();
+
+    buffer.begin_user_action();
+    buffer.delete(&mut start, &mut end);
+    buffer.insert(&mut start, &edit.lines.join("\n"));
+    let cursor = buffer
+        .iter_at_line_offset(line, column)
+        .or_else(|| buffer.iter_at_line(line))
+        .unwrap_or(start);
+    buffer.place_cursor(&cursor);
+    buffer.end_user_action();
+}
+
+/// Chars that stay the same when a paragraph is rewrapped
+fn is_content(c: char) -> bool {
+    !c.is_whitespace() && c != '>'
+}
+
+fn content_chars(text: &str) -> usize {
+    text.chars().filter(|c| is_content(*c)).count()
+}
+
+/// Char offset after the `n`th content char
+fn content_offset(text: &str, n: usize) -> usize {
+    if n == 0 {
+        return text.chars().position(is_content).unwrap_or(0);
+    }
+    let mut seen = 0;
+    for (i, c) in text.chars().enumerate() {
+        if is_content(c) {
+            seen += 1;
+            if seen == n {
+                return i + 1;
+            }
+        }
+    }
+    text.chars().count()
+}
+
+/// Insert a footnote reference with the next free number after the cursor or selection,
+/// and append its definition to the end of the document. The cursor goes to the definition.
+pub fn format_footnote(buffer: &impl TextBufferExt) {
+    let (start, end) = buffer.bounds();
+    let label = footnotes::next_number(&buffer.text(&start, &end, true));
+
+    let mut iter = match buffer.selection_bounds() {
+        Some((_, end)) => end,
+        None => buffer.iter_at_mark(&buffer.get_insert()),
+    };
+
+    buffer.begin_user_action();
+    buffer.insert(&mut iter, &format!("[^{label}]"));
+
+    // Definitions are kept together in one block at the end
+    let (start, end) = buffer.bounds();
+    let text = buffer.text(&start, &end, true);
+    let content = text.trim_end_matches('\n');
+    let last_line = content.rsplit('\n').next().unwrap_or_default();
+    let wanted_newlines = if regex::FOOTNOTE_DEFINITION.is_match(last_line) {
+        1
+    } else {
+        2
+    };
+    let trailing_newlines = text.len() - content.len();
+    let separator = "\n".repeat(wanted_newlines.saturating_sub(trailing_newlines));
+
+    buffer.insert(&mut buffer.end_iter(), &format!("{separator}[^{label}]: "));
+    buffer.place_cursor(&buffer.end_iter());
+    buffer.end_user_action();
+}
+
+/// If the iter is on a footnote reference, find its definition, and vice versa.
+/// Definitions lead to their first reference.
+pub fn footnote_target_at(buffer: &impl TextBufferExt, iter: &TextIter) -> Option<TextIter> {
+    let (start, end) = buffer.bounds();
+    let text = buffer.text(&start, &end, true);
+    let offset = iter.offset() as usize;
+    let definitions = footnotes::definitions(&text);
+    let references = footnotes::references(&text);
+    let contains =
+        |footnote: &&footnotes::Footnote| footnote.start <= offset && offset < footnote.end;
+
+    if let Some(definition) = definitions.iter().find(contains) {
+        let reference = references.iter().find(|r| r.label == definition.label)?;
+        return Some(buffer.iter_at_offset(reference.end as i32));
+    }
+    let reference = references.iter().find(contains)?;
+    let definition = definitions.iter().find(|d| d.label == reference.label)?;
+    // Skip the colon and the space after it
+    let mut target = buffer.iter_at_offset(definition.end as i32 + 1);
+    if target.char() == ' ' {
+        target.forward_char();
+    }
+    Some(target)
+}
+
+/// Insert an empty table after the cursor line and put the cursor in the first header cell.
+pub fn format_table(buffer: &impl TextBufferExt, num_rows: usize, num_columns: usize) {
+    let table = Table::new(num_rows, num_columns);
+    let mut iter = buffer.iter_at_mark(&buffer.get_insert());
+    let mut text = table.format();
+    if !line_text(buffer, iter.line()).trim().is_empty() {
+        if !iter.ends_line() {
+            iter.forward_to_line_end();
+        }
+        text = format!("\n\n{text}");
+    }
+    let first_line = iter.line() + if text.starts_with('\n') { 2 } else { 0 };
+
+    buffer.begin_user_action();
+    buffer.insert(&mut iter, &text);
+    select_table_cell(buffer, first_line, &table, 0, 0, None);
+    buffer.end_user_action();
+}
+
+/// Re-pad the columns of the table under the cursor. Returns false if there's no table.
+pub fn format_table_reformat(buffer: &impl TextBufferExt) -> bool {
+    let Some(at) = table_at_cursor(buffer) else {
+        return false;
+    };
+    buffer.begin_user_action();
+    write_table(buffer, &at, Some(at.offset));
+    buffer.end_user_action();
+    true
+}
+
+/// Re-pad the table under the cursor if its columns are out of line, keeping the cursor in its
+/// cell. Spaces the cursor is after would be trimmed, so the table is left alone then.
+/// Returns whether the table changed.
+pub fn table_realign(buffer: &impl TextBufferExt) -> bool {
+    let cursor = buffer.iter_at_mark(&buffer.get_insert());
+    if table::in_trailing_space(
+        &line_text(buffer, cursor.line()),
+        cursor.line_offset() as usize,
+    ) {
+        return false;
+    }
+    let Some(at) = table_at_cursor(buffer) else {
+        return false;
+    };
+    if table_text(buffer, &at) == at.table.format() {
+        return false;
+    }
+    buffer.begin_user_action();
+    write_table(buffer, &at, Some(at.offset));
+    buffer.end_user_action();
+    true
+}
+
+/// Re-pad the table on the iter's line without moving the cursor, which is outside of it.
+/// Returns whether the table changed.
+pub fn table_realign_at(buffer: &impl TextBufferExt, iter: &TextIter) -> bool {
+    let Some(at) = table_at(buffer, iter) else {
+        return false;
+    };
+    if table_text(buffer, &at) == at.table.format() {
+        return false;
+    }
+    buffer.begin_user_action();
+    replace_table_text(buffer, &at);
+    buffer.end_user_action();
+    true
+}
+
+/// Lines of the table on the iter's line, without parsing it
+pub fn table_lines(buffer: &impl TextBufferExt, iter: &TextIter) -> Option<RangeInclusive<i32>> {
+    let line = iter.line();
+    if !table::is_table_row(&line_text(buffer, line)) {
+        return None;
+    }
+    let mut first_line = line;
+    while first_line > 0 && table::is_table_row(&line_text(buffer, first_line - 1)) {
+        first_line -= 1;
+    }
+    let mut last_line = line;
+    while last_line + 1 < buffer.line_count()
+        && table::is_table_row(&line_text(buffer, last_line + 1))
+    {
+        last_line += 1;
+    }
+    Some(first_line..=last_line)
+}
+
+/// Whether the cursor is on a line that can be part of a table
+pub fn cursor_in_table(buffer: &impl TextBufferExt) -> bool {
+    let cursor = buffer.iter_at_mark(&buffer.get_insert());
+    table::is_table_row(&line_text(buffer, cursor.line()))
+}
+
+/// Move to the next or previous cell, and reformat the table on the way.
+/// Moving forward from the last cell adds a row. Returns false if there's no table.
+pub fn table_move_cell(buffer: &impl TextBufferExt, backwards: bool) -> bool {
+    let Some(mut at) = table_at_cursor(buffer) else {
+        return false;
+    };
+    let last_column = at.table.num_columns() - 1;
+    if backwards {
+        if at.column > 0 {
+            at.column -= 1;
+        } else if at.row > 0 {
+            at.row -= 1;
+            at.column = last_column;
+        }
+    } else if at.column < last_column {
+        at.column += 1;
+    } else {
+        at.row += 1;
+        at.column = 0;
+        if at.row == at.table.num_rows() {
+            at.table.insert_row(at.row);
+        }
+    }
+    buffer.begin_user_action();
+    write_table(buffer, &at, None);
+    buffer.end_user_action();
+    true
+}
+
+/// Add a row below the cursor. Returns false if there's no table.
+pub fn table_insert_row(buffer: &impl TextBufferExt) -> bool {
+    let Some(mut at) = table_at_cursor(buffer) else {
+        return false;
+    };
+    at.row += 1;
+    at.table.insert_row(at.row);
+    buffer.begin_user_action();
+    write_table(buffer, &at, None);
+    buffer.end_user_action();
+    true
+}
+
+/// Remove the row under the cursor. The header row stays. Returns false if nothing was removed.
+pub fn table_remove_row(buffer: &impl TextBufferExt) -> bool {
+    let Some(mut at) = table_at_cursor(buffer) else {
+        return false;
+    };
+    if !at.table.remove_row(at.row) {
+        return false;
+    }
+    at.row = at.row.min(at.table.num_rows() - 1);
+    buffer.begin_user_action();
+    write_table(buffer, &at, None);
+    buffer.end_user_action();
+    true
+}
+
+/// Add a column right of the cursor. Returns false if there's no table.
+pub fn table_insert_column(buffer: &impl TextBufferExt) -> bool {
+    let Some(mut at) = table_at_cursor(buffer) else {
+        return false;
+    };
+    at.column += 1;
+    at.table.insert_column(at.column);
+    buffer.begin_user_action();
+    write_table(buffer, &at, None);
+    buffer.end_user_action();
+    true
+}
+
+/// Remove the column under the cursor. The last column stays. Returns false if nothing was removed.
+pub fn table_remove_column(buffer: &impl TextBufferExt) -> bool {
+    let Some(mut at) = table_at_cursor(buffer) else {
+        return false;
+    };
+    if !at.table.remove_column(at.column) {
+        return false;
+    }
+    at.column = at.column.min(at.table.num_columns() - 1);
+    buffer.begin_user_action();
+    write_table(buffer, &at, None);
+    buffer.end_user_action();
+    true
+}
+
+/// Set alignment of the column under the cursor. Returns false if there's no table.
+pub fn table_align_column(buffer: &impl TextBufferExt, alignment: Alignment) -> bool {
+    let Some(mut at) = table_at_cursor(buffer) else {
+        return false;
+    };
+    at.table.set_alignment(at.column, alignment);
+    buffer.begin_user_action();
+    write_table(buffer, &at, Some(at.offset));
+    buffer.end_user_action();
+    true
+}
+
+/// Table under the cursor, and which cell the cursor is in.
+/// Tables found at another iter use that as the cursor.
+struct TableAtCursor {
+    first_line: i32,
+    last_line: i32,
+    table: Table,
+    row: usize,
+    column: usize,
+    /// Cursor position within the cell contents
+    offset: usize,
+}
+
+fn table_at_cursor(buffer: &impl TextBufferExt) -> Option<TableAtCursor> {
+    table_at(buffer, &buffer.iter_at_mark(&buffer.get_insert()))
+}
+
+/// Table on the iter's line, and which cell the iter is in
+fn table_at(buffer: &impl TextBufferExt, iter: &TextIter) -> Option<TableAtCursor> {
+    let line = iter.line();
+    let (first_line, last_line) = table_lines(buffer, iter)?.into_inner();
+    let text = line_text(buffer, line);
+
+    let lines: Vec<String> = (first_line..=last_line)
+        .map(|line| line_text(buffer, line))
+        .collect();
+    let lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+    let table = Table::parse(&lines)?;
+
+    // The delimiter row counts as the header
+    let row = match line - first_line {
+        0 | 1 => 0,
+        n => n as usize - 1,
+    };
+    let (column, offset) = table::cell_at(&text, iter.line_offset() as usize);
+    Some(TableAtCursor {
+        first_line,
+        last_line,
+        column: column.min(table.num_columns() - 1),
+        table,
+        row,
+        offset,
+    })
+}
+
+/// Replace the table text if it changed, then select the target cell.
+/// With `offset`, the cursor is placed within the cell instead.
+fn write_table(buffer: &impl TextBufferExt, at: &TableAtCursor, offset: Option<usize>) {
+    replace_table_text(buffer, at);
+    select_table_cell(buffer, at.first_line, &at.table, at.row, at.column, offset);
+}
+
+/// Current text of the table's lines
+fn table_text(buffer: &impl TextBufferExt, at: &TableAtCursor) -> String {
+    let lines: Vec<String> = (at.first_line..=at.last_line)
+        .map(|line| line_text(buffer, line))
+        .collect();
+    lines.join("\n")
+}
+
+/// Replace the table text with the formatted table, if it changed
+fn replace_table_text(buffer: &impl TextBufferExt, at: &TableAtCursor) {
+    let text = at.table.format();
+    let mut start = buffer.iter_at_line(at.first_line).unwrap();
+    let mut end = buffer.iter_at_line(at.last_line).unwrap();
+    if !end.ends_line() {
+        end.forward_to_line_end();
+    }
+    if buffer.text(&start, &end, true) != text {
+        buffer.delete(&mut start, &mut end);
+        buffer.insert(&mut start, &text);
+    }
+}
+
+fn select_table_cell(
+    buffer: &impl TextBufferExt,
+    first_line: i32,
+    table: &Table,
+    row: usize,
+    column: usize,
+    offset: Option<usize>,
+) {
+    let (line, start, end) = table.cell_range(row, column);
+    let line = first_line + line as i32;
+    match offset {
+        Some(offset) => {
+            let pos = (start + offset).min(end) as i32;
+            buffer.place_cursor(&buffer.iter_at_line_offset(line, pos).unwrap());
+        }
+        None => buffer.select_range(
+            &buffer.iter_at_line_offset(line, end as i32).unwrap(),
+            &buffer.iter_at_line_offset(line, start as i32).unwrap(),
+        ),
+    }
+}
+
+/// Line contents without the line break
+fn line_text(buffer: &impl TextBufferExt, line: i32) -> String {
+    let Some(start) = buffer.iter_at_line(line) else {
+        return String::new();
+    };
+    let mut end = start;
+    if !end.ends_line() {
+        end.forward_to_line_end();
+    }
+    buffer.text(&start, &end, true).to_string()
+}
+
+/// Length in chars, which is what TextIter offsets use.
+fn char_len(text: &str) -> i32 {
+    text.chars().count() as i32
+}
+
 fn range_around_cursor(buffer: &impl TextBufferExt, distance: i32) -> Option<(TextIter, TextIter)> {
     let cursor_pos = buffer.iter_at_mark(&buffer.get_insert());
     let start_off = cursor_pos.offset() - distance;
@@ -845,6 +1499,263 @@ mod tests {
         assert_eq!(contents!(buffer), "[text]()");
     }
 
+    #[test]
+    fn test_format_task_empty() {
+        let buffer = buf!("");
+        format_task(&buffer);
+        assert_eq!(contents!(buffer), "- [ ] ");
+        format_task(&buffer);
+        assert_eq!(contents!(buffer), "- [x] ");
+        format_task(&buffer);
+        assert_eq!(contents!(buffer), "- [ ] ");
+    }
+
+    #[test]
+    fn test_format_task_from_text() {
+        let buffer = buf!("text");
+        format_task(&buffer);
+        assert_eq!(contents!(buffer), "- [ ] text");
+        let buffer = buf!("  text");
+        format_task(&buffer);
+        assert_eq!(contents!(buffer), "  - [ ] text");
+    }
+
+    #[test]
+    fn test_format_task_from_list() {
+        let buffer = buf!("- text");
+        format_task(&buffer);
+        assert_eq!(contents!(buffer), "- [ ] text");
+        let buffer = buf!("1. text");
+        format_task(&buffer);
+        assert_eq!(contents!(buffer), "1. [ ] text");
+    }
+
+    #[test]
+    fn test_format_task_toggle() {
+        let buffer = buf!("- [ ] text");
+        buffer.place_cursor(&buffer.iter_at_offset(8));
+        format_task(&buffer);
+        assert_eq!(contents!(buffer), "- [x] text");
+        format_task(&buffer);
+        assert_eq!(contents!(buffer), "- [ ] text");
+        let buffer = buf!("- [X] text");
+        format_task(&buffer);
+        assert_eq!(contents!(buffer), "- [ ] text");
+    }
+
+    #[test]
+    fn test_format_task_manylines() {
+        let buffer = buf!("one\n\ntwo");
+        select_all!(&buffer);
+        format_task(&buffer);
+        assert_eq!(contents!(buffer), "- [ ] one\n\n- [ ] two");
+
+        let buffer = buf!("- [x] one\n- [ ] two\ntext");
+        select_all!(&buffer);
+        format_task(&buffer);
+        assert_eq!(contents!(buffer), "- [x] one\n- [x] two\ntext");
+        format_task(&buffer);
+        assert_eq!(contents!(buffer), "- [ ] one\n- [ ] two\ntext");
+    }
+
+    #[test]
+    fn test_toggle_task_at() {
+        let buffer = buf!("- [ ] text\ntext");
+        assert!(!toggle_task_at(&buffer, &buffer.iter_at_offset(0)));
+        assert!(!toggle_task_at(&buffer, &buffer.iter_at_offset(8)));
+        assert!(!toggle_task_at(&buffer, &buffer.iter_at_offset(12)));
+        assert_eq!(contents!(buffer), "- [ ] text\ntext");
+        assert!(toggle_task_at(&buffer, &buffer.iter_at_offset(3)));
+        assert_eq!(contents!(buffer), "- [x] text\ntext");
+        assert!(toggle_task_at(&buffer, &buffer.iter_at_offset(5)));
+        assert_eq!(contents!(buffer), "- [ ] text\ntext");
+    }
+
+    #[test]
+    fn test_insert_link() {
+        let buffer = buf!("foo  bar");
+        insert_link(&buffer, &buffer.iter_at_offset(4), "", "img.png", true);
+        assert_eq!(contents!(buffer), "foo ![](img.png) bar");
+        assert_eq!(buffer.cursor_position(), 16);
+        insert_link(&buffer, &buffer.start_iter(), "doc", "<my doc.md>", false);
+        assert_eq!(contents!(buffer), "[doc](<my doc.md>)foo ![](img.png) bar");
+    }
+
+    #[test]
+    fn test_apply_link_edits() {
+        let buffer = buf!("[a](a.md) and [b](b.md)");
+        buffer.place_cursor(&buffer.end_iter());
+        let edits = vec![
+            LinkEdit {
+                line: 0,
+                start: 4,
+                end: 8,
+                old: "a.md".into(),
+                new: "sub/a.md".into(),
+            },
+            LinkEdit {
+                line: 0,
+                start: 18,
+                end: 22,
+                old: "b.md".into(),
+                new: "<b c.md>".into(),
+            },
+        ];
+        apply_link_edits(&buffer, &edits);
+        assert_eq!(contents!(buffer), "[a](sub/a.md) and [b](<b c.md>)");
+        assert_eq!(buffer.cursor_position(), 31);
+    }
+
+    #[test]
+    fn test_format_footnote_empty() {
+        let buffer = buf!("");
+        format_footnote(&buffer);
+        assert_eq!(contents!(buffer), "[^1]\n\n[^1]: ");
+        assert_eq!(buffer.cursor_position(), 12);
+    }
+
+    #[test]
+    fn test_format_footnote() {
+        let buffer = buf!("foo bar");
+        buffer.place_cursor(&buffer.iter_at_offset(3));
+        format_footnote(&buffer);
+        assert_eq!(contents!(buffer), "foo[^1] bar\n\n[^1]: ");
+        assert_eq!(buffer.cursor_position(), 19);
+
+        buffer.place_cursor(&buffer.iter_at_offset(11));
+        format_footnote(&buffer);
+        assert_eq!(contents!(buffer), "foo[^1] bar[^2]\n\n[^1]: \n[^2]: ");
+    }
+
+    #[test]
+    fn test_format_footnote_after_selection() {
+        let buffer = buf!("foo bar\n");
+        select_range!(&buffer, 0, 3);
+        format_footnote(&buffer);
+        assert_eq!(contents!(buffer), "foo[^1] bar\n\n[^1]: ");
+    }
+
+    #[test]
+    fn test_format_footnote_next_number() {
+        let buffer = buf!("foo[^3]\n\n[^3]: bar\n");
+        buffer.place_cursor(&buffer.iter_at_offset(0));
+        format_footnote(&buffer);
+        assert_eq!(contents!(buffer), "[^4]foo[^3]\n\n[^3]: bar\n[^4]: ");
+    }
+
+    #[test]
+    fn test_footnote_target_at() {
+        let buffer = buf!("foo[^1] bar\n\n[^1]: baz");
+        let target = footnote_target_at(&buffer, &buffer.iter_at_offset(4)).unwrap();
+        assert_eq!(target.offset(), 19);
+        let target = footnote_target_at(&buffer, &buffer.iter_at_offset(14)).unwrap();
+        assert_eq!(target.offset(), 7);
+        assert!(footnote_target_at(&buffer, &buffer.iter_at_offset(1)).is_none());
+        assert!(footnote_target_at(&buffer, &buffer.iter_at_offset(7)).is_none());
+    }
+
+    #[test]
+    fn test_footnote_target_at_undefined() {
+        let buffer = buf!("foo[^1] bar");
+        assert!(footnote_target_at(&buffer, &buffer.iter_at_offset(4)).is_none());
+    }
+
+    #[test]
+    fn test_format_table() {
+        let buffer = buf!("");
+        format_table(&buffer, 1, 2);
+        assert_eq!(
+            contents!(buffer),
+            "|     |     |\n| --- | --- |\n|     |     |"
+        );
+        assert_eq!(buffer.cursor_position(), 2);
+
+        let buffer = buf!("text");
+        buffer.place_cursor(&buffer.iter_at_offset(2));
+        format_table(&buffer, 0, 1);
+        assert_eq!(contents!(buffer), "text\n\n|     |\n| --- |");
+        assert_eq!(buffer.cursor_position(), 8);
+    }
+
+    #[test]
+    fn test_format_table_reformat() {
+        let buffer = buf!("text\n|a|bb|\n|-|-|\n|ccc|d|\n\ntext");
+        buffer.place_cursor(&buffer.iter_at_offset(21));
+        assert!(format_table_reformat(&buffer));
+        assert_eq!(
+            contents!(buffer),
+            "text\n| a   | bb  |\n| --- | --- |\n| ccc | d   |\n\ntext"
+        );
+        // Cursor stays after "cc"
+        assert_eq!(buffer.cursor_position(), 37);
+
+        buffer.place_cursor(&buffer.iter_at_offset(0));
+        assert!(!format_table_reformat(&buffer));
+    }
+
+    #[test]
+    fn test_table_move_cell() {
+        let buffer = buf!("|a|b|\n|-|-|\n|c|d|");
+        buffer.place_cursor(&buffer.iter_at_offset(1));
+        assert!(table_move_cell(&buffer, false));
+        let (start, end) = buffer.selection_bounds().unwrap();
+        assert_eq!(buffer.text(&start, &end, true), "b");
+
+        assert!(table_move_cell(&buffer, false));
+        let (start, end) = buffer.selection_bounds().unwrap();
+        assert_eq!(buffer.text(&start, &end, true), "c");
+        assert_eq!(start.line(), 2);
+
+        assert!(table_move_cell(&buffer, true));
+        let (start, end) = buffer.selection_bounds().unwrap();
+        assert_eq!(buffer.text(&start, &end, true), "b");
+    }
+
+    #[test]
+    fn test_table_move_cell_adds_row() {
+        let buffer = buf!("|a|\n|-|");
+        buffer.place_cursor(&buffer.iter_at_offset(1));
+        assert!(table_move_cell(&buffer, false));
+        assert_eq!(contents!(buffer), "| a   |\n| --- |\n|     |");
+        assert_eq!(buffer.cursor_position(), 18);
+    }
+
+    #[test]
+    fn test_table_move_cell_not_a_table() {
+        let buffer = buf!("a | b");
+        assert!(!table_move_cell(&buffer, false));
+        assert_eq!(contents!(buffer), "a | b");
+    }
+
+    #[test]
+    fn test_table_rows_and_columns() {
+        let buffer = buf!("| a   |\n| --- |");
+        buffer.place_cursor(&buffer.iter_at_offset(2));
+        assert!(table_insert_column(&buffer));
+        assert_eq!(contents!(buffer), "| a   |     |\n| --- | --- |");
+        assert!(table_insert_row(&buffer));
+        assert_eq!(
+            contents!(buffer),
+            "| a   |     |\n| --- | --- |\n|     |     |"
+        );
+        assert!(table_remove_row(&buffer));
+        assert_eq!(contents!(buffer), "| a   |     |\n| --- | --- |");
+        assert!(!table_remove_row(&buffer));
+        assert!(table_remove_column(&buffer));
+        assert!(!table_remove_column(&buffer));
+        assert_eq!(contents!(buffer), "| a   |\n| --- |");
+    }
+
+    #[test]
+    fn test_table_align_column() {
+        let buffer = buf!("| a   |\n| --- |");
+        buffer.place_cursor(&buffer.iter_at_offset(2));
+        assert!(table_align_column(&buffer, Alignment::Center));
+        assert_eq!(contents!(buffer), "|  a  |\n| :-: |");
+        assert!(table_align_column(&buffer, Alignment::Right));
+        assert_eq!(contents!(buffer), "|   a |\n| --: |");
+    }
+
     #[test]
     fn test_find_delim_range() {
         let buffer = buf!("");
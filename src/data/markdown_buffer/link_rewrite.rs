@@ -0,0 +1,258 @@
+//! Rewrites links after a document or folder is moved.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use super::links;
+use crate::util::file_actions;
+
+/// A document or folder that moved. Everything inside a moved folder moves with it.
+#[derive(Debug, Clone)]
+pub struct MovedItem {
+    pub old_path: PathBuf,
+    pub new_path: PathBuf,
+    pub is_folder: bool,
+}
+
+impl MovedItem {
+    /// Where the path is after the move, if it moved
+    pub fn map(&self, path: &Path) -> Option<PathBuf> {
+        let relative = path.strip_prefix(&self.old_path).ok()?;
+        if relative.as_os_str().is_empty() {
+            Some(self.new_path.clone())
+        } else {
+            Some(self.new_path.join(relative))
+        }
+    }
+}
+
+/// Replace the text between `start` and `end` with `new`.
+/// Offsets are in chars, like TextIter offsets.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkEdit {
+    pub line: usize,
+    pub start: usize,
+    pub end: usize,
+    pub old: String,
+    pub new: String,
+}
+
+/// Edits that keep the links of a document working after the move, in document order.
+/// `document_path` is where the document was before the move. If the document moved itself,
+/// its relative links are updated as well.
+pub fn link_edits(
+    text: &str,
+    document_path: &Path,
+    project_root: &Path,
+    moved: &MovedItem,
+) -> Vec<LinkEdit> {
+    let new_document_path = moved
+        .map(document_path)
+        .unwrap_or_else(|| document_path.to_owned());
+    let (Some(old_dir), Some(new_dir)) = (document_path.parent(), new_document_path.parent())
+    else {
+        return Vec::new();
+    };
+    let document_moved = old_dir != new_dir;
+
+    let mut edits = Vec::new();
+    for link in links::links(text) {
+        if link.is_external() {
+            continue;
+        }
+        let (file, suffix) = link.destination.split_at(
+            link.destination
+                .find(['#', '?'])
+                .unwrap_or(link.destination.len()),
+        );
+        if file.is_empty() {
+            continue;
+        }
+        let decoded = file_actions::percent_decode(file);
+        let target = file_actions::normalize_path(&old_dir.join(&decoded));
+        let new_target = match moved.map(&target) {
+            Some(new_target) => new_target,
+            None if document_moved => target,
+            None => continue,
+        };
+        let new_file = file_actions::relative_path(new_dir, &new_target);
+        // Links that were percent-encoded stay that way
+        let encoded = decoded != file;
+        let new_file = new_file.to_string_lossy();
+        let new_destination = if encoded {
+            format!("{}{suffix}", file_actions::percent_encode(&new_file))
+        } else {
+            format!("{new_file}{suffix}")
+        };
+        if new_destination == link.destination {
+            continue;
+        }
+        let new = if encoded {
+            new_destination
+        } else {
+            file_actions::markdown_link_destination(Path::new(&new_destination))
+        };
+        edits.push(LinkEdit {
+            line: link.line,
+            start: link.destination_start,
+            end: link.destination_end,
+            old: link.destination.clone(),
+            new,
+        });
+    }
+
+    for link in links::wiki_links(text) {
+        if let Some(new_target) = wiki_link_target(&link.target, project_root, moved) {
+            edits.push(LinkEdit {
+                line: link.line,
+                start: link.start,
+                end: link.end,
+                old: link.target,
+                new: new_target,
+            });
+        }
+    }
+    edits.sort_by_key(|edit| edit.start);
+    edits
+}
+
+/// The new target of a wiki link, if it changes. Targets with a slash are relative to the
+/// project root, others are just the document name.
+fn wiki_link_target(target: &str, project_root: &Path, moved: &MovedItem) -> Option<String> {
+    if target.contains('/') {
+        let resolved = file_actions::normalize_path(&project_root.join(target));
+        let new_resolved = if moved.is_folder {
+            moved.map(&resolved)?
+        } else if resolved == moved.old_path.with_extension("") {
+            moved.new_path.with_extension("")
+        } else {
+            return None;
+        };
+        let new_target = file_actions::relative_path(project_root, &new_resolved);
+        Some(new_target.to_string_lossy().into_owned())
+    } else {
+        if moved.is_folder {
+            return None;
+        }
+        let old_name = moved.old_path.file_stem()?.to_string_lossy();
+        let new_name = moved.new_path.file_stem()?.to_string_lossy();
+        (target == old_name && old_name != new_name).then(|| new_name.into_owned())
+    }
+}
+
+/// Apply edits sorted by position, which don't overlap
+pub fn apply_edits(text: &str, edits: &[LinkEdit]) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    let mut position = 0;
+    for edit in edits {
+        result.extend(chars.by_ref().take(edit.start - position));
+        chars.by_ref().take(edit.end - edit.start).for_each(drop);
+        result.push_str(&edit.new);
+        position = edit.end;
+    }
+    result.extend(chars);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rewrite(text: &str, document_path: &str, moved: &MovedItem) -> String {
+        let edits = link_edits(text, Path::new(document_path), Path::new("/p"), moved);
+        apply_edits(text, &edits)
+    }
+
+    fn moved_document(old_path: &str, new_path: &str) -> MovedItem {
+        MovedItem {
+            old_path: PathBuf::from(old_path),
+            new_path: PathBuf::from(new_path),
+            is_folder: false,
+        }
+    }
+
+    #[test]
+    fn test_links_to_moved_document() {
+        let moved = moved_document("/p/a.md", "/p/sub/b c.md");
+        assert_eq!(
+            rewrite(
+                "[a](a.md#top) [x](x.md) ![](./a.md) [web](https://a.md)",
+                "/p/doc.md",
+                &moved
+            ),
+            "[a](<sub/b c.md#top>) [x](x.md) ![](<sub/b c.md>) [web](https://a.md)"
+        );
+    }
+
+    #[test]
+    fn test_percent_encoded_links() {
+        let moved = moved_document("/p/my note.md", "/p/sub/my note.md");
+        assert_eq!(
+            rewrite(
+                "[a](my%20note.md#top) [b](<my note.md>)",
+                "/p/doc.md",
+                &moved
+            ),
+            "[a](sub/my%20note.md#top) [b](<sub/my note.md>)"
+        );
+    }
+
+    #[test]
+    fn test_links_inside_moved_document() {
+        let moved = moved_document("/p/a.md", "/p/sub/a.md");
+        assert_eq!(
+            rewrite("[b](b.md) [self](#top) [i](img/x.png)", "/p/a.md", &moved),
+            "[b](../b.md) [self](#top) [i](../img/x.png)"
+        );
+    }
+
+    #[test]
+    fn test_links_into_moved_folder() {
+        let moved = MovedItem {
+            old_path: PathBuf::from("/p/old"),
+            new_path: PathBuf::from("/p/new/nested"),
+            is_folder: true,
+        };
+        assert_eq!(
+            rewrite("[a](old/a.md) [b](../p/old/x/b.md)", "/p/doc.md", &moved),
+            "[a](new/nested/a.md) [b](new/nested/x/b.md)"
+        );
+        // Links between documents that moved together stay the same
+        assert_eq!(rewrite("[b](x/b.md)", "/p/old/a.md", &moved), "[b](x/b.md)");
+    }
+
+    #[test]
+    fn test_wiki_links() {
+        let moved = moved_document("/p/notes/Old.md", "/p/notes/Renamed.md");
+        assert_eq!(
+            rewrite(
+                "[[Old]] [[Old#top|label]] [[notes/Old]] [[Other]]",
+                "/p/doc.md",
+                &moved
+            ),
+            "[[Renamed]] [[Renamed#top|label]] [[notes/Renamed]] [[Other]]"
+        );
+        let moved = MovedItem {
+            old_path: PathBuf::from("/p/notes"),
+            new_path: PathBuf::from("/p/archive"),
+            is_folder: true,
+        };
+        assert_eq!(
+            rewrite("[[Old]] [[notes/Old]]", "/p/doc.md", &moved),
+            "[[Old]] [[archive/Old]]"
+        );
+    }
+
+    #[test]
+    fn test_apply_edits_chars() {
+        let edits = vec![LinkEdit {
+            line: 0,
+            start: 5,
+            end: 9,
+            old: "ä.md".into(),
+            new: "ö/ä.md".into(),
+        }];
+        assert_eq!(apply_edits("[äö](ä.md)", &edits), "[äö](ö/ä.md)");
+    }
+}
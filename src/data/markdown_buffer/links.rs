@@ -0,0 +1,342 @@
+//! Finds inline links and images in markdown text, and checks where they point.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::util::file_actions;
+use crate::util::regex;
+
+/// An inline link or image.
+/// Offsets are in chars, like TextIter offsets. The range covers the whole link.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Link {
+    pub is_image: bool,
+    pub title: String,
+    /// Without the angle brackets, if there were any
+    pub destination: String,
+    pub line: usize,
+    pub start: usize,
+    pub end: usize,
+    /// The destination as written, angle brackets included
+    pub destination_start: usize,
+    pub destination_end: usize,
+}
+
+/// A wiki link, like `[[Document]]` or `[[folder/Document#heading|label]]`.
+/// The range covers only the target, `folder/Document` in the latter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WikiLink {
+    pub target: String,
+    pub line: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Link {
+    pub fn is_external(&self) -> bool {
+        is_external(&self.destination)
+    }
+
+    /// The `#fragment` of the destination, if any
+    pub fn anchor(&self) -> Option<&str> {
+        self.destination
+            .split_once('#')
+            .map(|(_, anchor)| anchor)
+            .filter(|anchor| !anchor.is_empty())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkProblem {
+    MissingFile,
+    MissingAnchor,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrokenLink {
+    pub link: Link,
+    pub problem: LinkProblem,
+}
+
+/// All inline links and images, in document order. Links in fenced code blocks are skipped.
+pub fn links(text: &str) -> Vec<Link> {
+    let mut result = Vec::new();
+    for (line, line_start, line_text) in prose_lines(text) {
+        let offset = |byte: usize| line_start + line_text[..byte].chars().count();
+        for caps in regex::INLINE_LINK.captures_iter(line_text) {
+            let m = caps.get(0).unwrap();
+            let raw_destination = caps.get(3).unwrap();
+            let destination = raw_destination.as_str();
+            let destination = destination
+                .strip_prefix('<')
+                .and_then(|d| d.strip_suffix('>'))
+                .unwrap_or(destination);
+            result.push(Link {
+                is_image: &caps[1] == "!",
+                title: caps[2].to_owned(),
+                destination: destination.to_owned(),
+                line,
+                start: offset(m.start()),
+                end: offset(m.end()),
+                destination_start: offset(raw_destination.start()),
+                destination_end: offset(raw_destination.end()),
+            });
+        }
+    }
+    result
+}
+
+/// All wiki links, in document order. Links in fenced code blocks are skipped.
+pub fn wiki_links(text: &str) -> Vec<WikiLink> {
+    let mut result = Vec::new();
+    for (line, line_start, line_text) in prose_lines(text) {
+        let offset = |byte: usize| line_start + line_text[..byte].chars().count();
+        for caps in regex::WIKI_LINK.captures_iter(line_text) {
+            let target = caps.get(1).unwrap();
+            result.push(WikiLink {
+                target: target.as_str().to_owned(),
+                line,
+                start: offset(target.start()),
+                end: offset(target.end()),
+            });
+        }
+    }
+    result
+}
+
+/// Line number, char offset of the line start, and text of lines outside fenced code blocks
+fn prose_lines(text: &str) -> Vec<(usize, usize, &str)> {
+    let mut result = Vec::new();
+    let mut in_fence = false;
+    let mut line_start = 0;
+    for (line, line_text) in text.split('\n').enumerate() {
+        if regex::CODE_FENCE.is_match(line_text) {
+            in_fence = !in_fence;
+        } else if !in_fence {
+            result.push((line, line_start, line_text));
+        }
+        line_start += line_text.chars().count() + 1;
+    }
+    result
+}
+
+/// Anchors of all headings, like GitHub generates them. Repeated headings get a number suffix.
+pub fn heading_anchors(text: &str) -> Vec<String> {
+    let mut anchors: Vec<String> = Vec::new();
+    let mut in_fence = false;
+    let mut previous_line = "";
+    for line in text.split('\n') {
+        let heading = if regex::CODE_FENCE.is_match(line) {
+            in_fence = !in_fence;
+            None
+        } else if in_fence {
+            None
+        } else if let Some(m) = regex::ATX_H_OPENING.find(line) {
+            Some(line[m.end()..].trim_end().trim_end_matches('#'))
+        } else if regex::SETEXT_H_UNDERLINE.is_match(line)
+            && !previous_line.trim().is_empty()
+            && !regex::ATX_H_OPENING.is_match(previous_line)
+        {
+            Some(previous_line)
+        } else {
+            None
+        };
+        if let Some(heading) = heading {
+            let slug = slug(heading);
+            let mut anchor = slug.clone();
+            let mut n = 0;
+            while anchors.contains(&anchor) {
+                n += 1;
+                anchor = format!("{slug}-{n}");
+            }
+            anchors.push(anchor);
+        }
+        previous_line = if in_fence { "" } else { line };
+    }
+    anchors
+}
+
+fn slug(heading: &str) -> String {
+    heading
+        .trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            '-' | '_' => Some(c),
+            c if c.is_alphanumeric() => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Local links whose file doesn't exist, or whose anchor matches no heading.
+/// The document's own text is passed in, so unsaved changes are taken into account.
+pub fn broken_links(text: &str, document_path: &Path) -> Vec<BrokenLink> {
+    let Some(document_dir) = document_path.parent() else {
+        return Vec::new();
+    };
+    let mut anchors: HashMap<PathBuf, Option<Vec<String>>> = HashMap::new();
+    anchors.insert(document_path.to_owned(), Some(heading_anchors(text)));
+
+    let mut result = Vec::new();
+    for link in links(text) {
+        if link.is_external() || link.destination.is_empty() {
+            continue;
+        }
+        // Links with only an anchor point to the document itself
+        let target = match file_actions::link_target_path(document_dir, &link.destination) {
+            Some(target) if !target.exists() => {
+                result.push(BrokenLink {
+                    link,
+                    problem: LinkProblem::MissingFile,
+                });
+                continue;
+            }
+            Some(target) => target,
+            None => document_path.to_owned(),
+        };
+        let Some(anchor) = link.anchor() else {
+            continue;
+        };
+        // Anchors can only be checked in documents we can read
+        let target_anchors = anchors.entry(target).or_insert_with_key(|target| {
+            std::fs::read_to_string(target)
+                .ok()
+                .map(|text| heading_anchors(&text))
+        });
+        if let Some(target_anchors) = target_anchors
+            && !target_anchors.iter().any(|a| a == anchor)
+        {
+            result.push(BrokenLink {
+                link,
+                problem: LinkProblem::MissingAnchor,
+            });
+        }
+    }
+    result
+}
+
+/// True for destinations with a URI scheme, like `https://` or `mailto:`
+fn is_external(destination: &str) -> bool {
+    let Some((scheme, _)) = destination.split_once(':') else {
+        return false;
+    };
+    // Single letters are more likely to be drive letters
+    scheme.len() > 1
+        && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_links() {
+        let text = "# Title\n\nSee [äö](other.md) and ![](<img/a b.png>).\n";
+        assert_eq!(
+            links(text),
+            vec![
+                Link {
+                    is_image: false,
+                    title: "äö".into(),
+                    destination: "other.md".into(),
+                    line: 2,
+                    start: 13,
+                    end: 27,
+                    destination_start: 18,
+                    destination_end: 26,
+                },
+                Link {
+                    is_image: true,
+                    title: "".into(),
+                    destination: "img/a b.png".into(),
+                    line: 2,
+                    start: 32,
+                    end: 50,
+                    destination_start: 36,
+                    destination_end: 49,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_links_skip_code_fences() {
+        let text = "[a](a.md)\n```\n[b](b.md)\n```\n[c](c.md)";
+        let found: Vec<String> = links(text).into_iter().map(|l| l.destination).collect();
+        assert_eq!(found, vec!["a.md", "c.md"]);
+    }
+
+    #[test]
+    fn test_wiki_links() {
+        let text = "See [[Notes]] and [[sub/Ünï#top|label]].\n```\n[[Code]]\n```";
+        assert_eq!(
+            wiki_links(text),
+            vec![
+                WikiLink {
+                    target: "Notes".into(),
+                    line: 0,
+                    start: 6,
+                    end: 11,
+                },
+                WikiLink {
+                    target: "sub/Ünï".into(),
+                    line: 0,
+                    start: 20,
+                    end: 27,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_heading_anchors() {
+        let text = "# Hello, World!\n\n## Hello World\n\nSetext Title\n---\n\n```\n# Not a heading\n```\n### Ünïcode_and-more ##";
+        assert_eq!(
+            heading_anchors(text),
+            vec![
+                "hello-world",
+                "hello-world-1",
+                "setext-title",
+                "ünïcode_and-more"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_heading_anchors_thematic_break() {
+        assert!(heading_anchors("foo\n\n---").is_empty());
+    }
+
+    #[test]
+    fn test_broken_links_anchors() {
+        let path = Path::new("/nonexistent/doc.md");
+        let text = "# Intro\n\n[ok](#intro) [bad](#outro) [web](https://example.com/#x)";
+        let broken = broken_links(text, path);
+        assert_eq!(broken.len(), 1);
+        assert_eq!(broken[0].link.destination, "#outro");
+        assert_eq!(broken[0].problem, LinkProblem::MissingAnchor);
+    }
+
+    #[test]
+    fn test_broken_links_missing_file() {
+        let path = Path::new("/nonexistent/doc.md");
+        let broken = broken_links("![](img.png) [a](other.md#intro)", path);
+        assert_eq!(broken.len(), 2);
+        assert!(broken.iter().all(|b| b.problem == LinkProblem::MissingFile));
+    }
+
+    #[test]
+    fn test_is_external() {
+        assert!(is_external("https://example.com"));
+        assert!(is_external("mailto:foo@example.com"));
+        assert!(!is_external("foo/bar.md"));
+        assert!(!is_external("#heading"));
+        assert!(!is_external("C:/foo.png"));
+    }
+}
@@ -0,0 +1,555 @@
+//! Markdown lint rules. Rules have the ids and names of markdownlint's rules, and projects are
+//! configured with a `.markdownlint.json` file, so that a CI lint and the editor agree.
+
+use std::path::Path;
+use std::sync::LazyLock;
+
+use gettextrs::gettext;
+use regex::Regex;
+use serde_json::Value;
+
+use super::links;
+use crate::error::ScratchmarkError;
+use crate::util::regex as md_regex;
+
+/// Lint config file in the project root
+pub const CONFIG_FILE: &str = ".markdownlint.json";
+
+/// A `"key": value` pair of the config. Group 1 is the key, group 2 the start of the value.
+static CONFIG_ENTRY: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#""([A-Za-z0-9_-]+)"\s*:\s*(true|false|\{)"#).unwrap());
+
+/// Opening of an ATX heading without a space, like `#Heading`
+static ATX_MISSING_SPACE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^ {0,3}#{1,6}[^#\s]").unwrap());
+
+/// Bullet list marker. Group 1 is the marker.
+static BULLET_MARKER: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\s*([-*+])\s+").unwrap());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LintRule {
+    HeadingIncrement,
+    ListMarkerStyle,
+    TrailingSpaces,
+    HardTabs,
+    MultipleBlanks,
+    MissingSpaceAtx,
+    SingleH1,
+    FencedCodeLanguage,
+    NoAltText,
+    TrailingNewline,
+}
+
+impl LintRule {
+    pub const ALL: [LintRule; 10] = [
+        LintRule::HeadingIncrement,
+        LintRule::ListMarkerStyle,
+        LintRule::TrailingSpaces,
+        LintRule::HardTabs,
+        LintRule::MultipleBlanks,
+        LintRule::MissingSpaceAtx,
+        LintRule::SingleH1,
+        LintRule::FencedCodeLanguage,
+        LintRule::NoAltText,
+        LintRule::TrailingNewline,
+    ];
+
+    /// The markdownlint rule id
+    pub fn id(&self) -> &'static str {
+        match self {
+            LintRule::HeadingIncrement => "MD001",
+            LintRule::ListMarkerStyle => "MD004",
+            LintRule::TrailingSpaces => "MD009",
+            LintRule::HardTabs => "MD010",
+            LintRule::MultipleBlanks => "MD012",
+            LintRule::MissingSpaceAtx => "MD018",
+            LintRule::SingleH1 => "MD025",
+            LintRule::FencedCodeLanguage => "MD040",
+            LintRule::NoAltText => "MD045",
+            LintRule::TrailingNewline => "MD047",
+        }
+    }
+
+    /// The markdownlint rule alias
+    pub fn name(&self) -> &'static str {
+        match self {
+            LintRule::HeadingIncrement => "heading-increment",
+            LintRule::ListMarkerStyle => "ul-style",
+            LintRule::TrailingSpaces => "no-trailing-spaces",
+            LintRule::HardTabs => "no-hard-tabs",
+            LintRule::MultipleBlanks => "no-multiple-blanks",
+            LintRule::MissingSpaceAtx => "no-missing-space-atx",
+            LintRule::SingleH1 => "single-h1",
+            LintRule::FencedCodeLanguage => "fenced-code-language",
+            LintRule::NoAltText => "no-alt-text",
+            LintRule::TrailingNewline => "single-trailing-newline",
+        }
+    }
+
+    pub fn description(&self) -> String {
+        match self {
+            LintRule::HeadingIncrement => gettext("Heading levels should only go up by one"),
+            LintRule::ListMarkerStyle => gettext("Bullet lists should use the same marker"),
+            LintRule::TrailingSpaces => gettext("Trailing spaces"),
+            LintRule::HardTabs => gettext("Hard tabs"),
+            LintRule::MultipleBlanks => gettext("Multiple consecutive blank lines"),
+            LintRule::MissingSpaceAtx => gettext("No space after the hash of a heading"),
+            LintRule::SingleH1 => gettext("Multiple top-level headings"),
+            LintRule::FencedCodeLanguage => gettext("Code blocks should have a language"),
+            LintRule::NoAltText => gettext("Images should have alternate text"),
+            LintRule::TrailingNewline => gettext("Files should end with a single newline"),
+        }
+    }
+
+    fn from_key(key: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|rule| rule.id().eq_ignore_ascii_case(key) || rule.name() == key)
+    }
+}
+
+/// A rule violation. Offsets are in chars, like TextIter offsets. Lines start at 0.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintDiagnostic {
+    pub rule: LintRule,
+    pub line: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Which rules are enabled. Rules are enabled unless the config says otherwise.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintConfig {
+    default: bool,
+    rules: Vec<(LintRule, bool)>,
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        Self {
+            default: true,
+            rules: Vec::new(),
+        }
+    }
+}
+
+impl LintConfig {
+    /// The config of the project at `project_path`. Without a config file, all rules are enabled.
+    pub fn load(project_path: &Path) -> Self {
+        std::fs::read_to_string(project_path.join(CONFIG_FILE))
+            .map(|text| Self::parse(&text))
+            .unwrap_or_default()
+    }
+
+    /// Read the rule switches of a markdownlint config. Rule options are not used,
+    /// a rule with options is enabled. A config that isn't a JSON object enables all rules.
+    pub fn parse(text: &str) -> Self {
+        let mut config = Self::default();
+        let entries = match serde_json::from_str::<serde_json::Map<String, Value>>(text) {
+            Ok(entries) => entries,
+            Err(e) => {
+                println!("{e}");
+                return config;
+            }
+        };
+        for (key, value) in entries {
+            let enabled = value != Value::Bool(false);
+            if key == "default" {
+                config.default = enabled;
+            } else if let Some(rule) = LintRule::from_key(&key) {
+                config.rules.retain(|(r, _)| *r != rule);
+                config.rules.push((rule, enabled));
+            }
+        }
+        config
+    }
+
+    pub fn is_enabled(&self, rule: LintRule) -> bool {
+        self.rules
+            .iter()
+            .find(|(r, _)| *r == rule)
+            .map(|(_, enabled)| *enabled)
+            .unwrap_or(self.default)
+    }
+
+    /// Switch a rule in the config file of the project, keeping the rest of the file
+    pub fn save_rule(
+        project_path: &Path,
+        rule: LintRule,
+        enabled: bool,
+    ) -> Result<(), ScratchmarkError> {
+        let path = project_path.join(CONFIG_FILE);
+        let text = std::fs::read_to_string(&path).unwrap_or_default();
+        std::fs::write(&path, set_rule(&text, rule, enabled)).map_err(|e| {
+            println!("{e}");
+            ScratchmarkError::FileWriteFail
+        })
+    }
+}
+
+/// The config text with the rule switched. Rule options are dropped when a rule is disabled.
+pub fn set_rule(text: &str, rule: LintRule, enabled: bool) -> String {
+    let value = if enabled { "true" } else { "false" };
+    let existing = CONFIG_ENTRY
+        .captures_iter(text)
+        .filter(|caps| brace_depth(&text[..caps.get(0).unwrap().start()]) == 1)
+        .find(|caps| LintRule::from_key(&caps[1]) == Some(rule));
+    if let Some(caps) = existing {
+        let value_match = caps.get(2).unwrap();
+        let value_end = if value_match.as_str() == "{" {
+            if enabled {
+                return text.to_owned();
+            }
+            matching_brace(text, value_match.start()).unwrap_or(value_match.end())
+        } else {
+            value_match.end()
+        };
+        return format!(
+            "{}{value}{}",
+            &text[..value_match.start()],
+            &text[value_end..]
+        );
+    }
+
+    let entry = format!("\"{}\": {value}", rule.id());
+    let Some(open) = text.find('{') else {
+        return format!("{{\n  {entry}\n}}\n");
+    };
+    let rest = &text[open + 1..];
+    let separator = if rest.trim_start().starts_with('}') {
+        ""
+    } else {
+        ","
+    };
+    format!("{}\n  {entry}{separator}{rest}", &text[..=open])
+}
+
+/// How many braces are open at the end of the text, not counting those in strings
+fn brace_depth(text: &str) -> usize {
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    for c in text.chars() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => (),
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '{' => depth += 1,
+            '}' => depth = depth.saturating_sub(1),
+            _ => (),
+        }
+    }
+    depth
+}
+
+/// Byte index after the brace that closes the one at `open`
+fn matching_brace(text: &str, open: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in text[open..].char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => (),
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(open + i + 1);
+                }
+            }
+            _ => (),
+        }
+    }
+    None
+}
+
+/// All diagnostics of the enabled rules, sorted by position
+pub fn lint(text: &str, config: &LintConfig) -> Vec<LintDiagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut push = |rule: LintRule, line: usize, start: usize, end: usize| {
+        if config.is_enabled(rule) {
+            diagnostics.push(LintDiagnostic {
+                rule,
+                line,
+                start,
+                end,
+            });
+        }
+    };
+
+    let mut in_fence = false;
+    let mut previous_blank = false;
+    let mut heading_level = None;
+    let mut seen_h1 = false;
+    let mut bullet_marker = None;
+    let mut line_start = 0;
+    let text_end = text.chars().count();
+    for (line, raw_line) in text.split('\n').enumerate() {
+        let line_text = raw_line.strip_suffix('\r').unwrap_or(raw_line);
+        let chars: Vec<char> = line_text.chars().collect();
+        let line_end = line_start + chars.len();
+        // A `\r` before the newline is part of the line break, not of the line
+        let next_line_start = line_start + raw_line.chars().count() + 1;
+
+        // Whitespace rules apply to code too
+        let content_len = line_text.trim_end_matches([' ', '\t']).chars().count();
+        let trailing = chars.len() - content_len;
+        let hard_break = trailing == 2 && content_len > 0 && line_text.ends_with("  ");
+        if trailing > 0 && !hard_break {
+            push(
+                LintRule::TrailingSpaces,
+                line,
+                line_start + content_len,
+                line_end,
+            );
+        }
+        if let Some(tab) = chars.iter().position(|&c| c == '\t') {
+            let tabs = chars[tab..].iter().take_while(|&&c| c == '\t').count();
+            push(
+                LintRule::HardTabs,
+                line,
+                line_start + tab,
+                line_start + tab + tabs,
+            );
+        }
+
+        if md_regex::CODE_FENCE.is_match(line_text) {
+            if !in_fence
+                && line_text.trim_start()[3..]
+                    .trim_matches(['`', '~', ' '])
+                    .is_empty()
+            {
+                push(LintRule::FencedCodeLanguage, line, line_start, line_end);
+            }
+            in_fence = !in_fence;
+            previous_blank = false;
+            line_start = next_line_start;
+            continue;
+        }
+        if in_fence {
+            line_start = next_line_start;
+            continue;
+        }
+
+        let blank = line_text.trim().is_empty();
+        // The last line of a file that ends with a newline is empty, but not a blank line
+        let is_last = line_end == text_end;
+        if blank && previous_blank && !is_last {
+            push(LintRule::MultipleBlanks, line, line_start, line_end);
+        }
+        previous_blank = blank;
+
+        if md_regex::ATX_H_OPENING.is_match(line_text) {
+            let level = line_text
+                .trim_start()
+                .chars()
+                .take_while(|&c| c == '#')
+                .count();
+            if heading_level.is_some_and(|previous| level > previous + 1) {
+                push(LintRule::HeadingIncrement, line, line_start, line_end);
+            }
+            heading_level = Some(level);
+            if level == 1 {
+                if seen_h1 {
+                    push(LintRule::SingleH1, line, line_start, line_end);
+                }
+                seen_h1 = true;
+            }
+        } else if ATX_MISSING_SPACE.is_match(line_text) {
+            push(LintRule::MissingSpaceAtx, line, line_start, line_end);
+        }
+
+        if let Some(caps) = BULLET_MARKER.captures(line_text)
+            && !md_regex::THEMATIC_BREAK.is_match(line_text)
+        {
+            let marker = caps.get(1).unwrap();
+            let first = *bullet_marker.get_or_insert(marker.as_str());
+            if marker.as_str() != first {
+                let start = line_start + line_text[..marker.start()].chars().count();
+                push(LintRule::ListMarkerStyle, line, start, start + 1);
+            }
+        }
+
+        line_start = next_line_start;
+    }
+
+    for link in links::links(text) {
+        if link.is_image && link.title.trim().is_empty() {
+            push(LintRule::NoAltText, link.line, link.start, link.end);
+        }
+    }
+
+    if !text.is_empty()
+        && (!text.ends_with('\n') || text.ends_with("\n\n") || text.ends_with("\n\r\n"))
+    {
+        let line = text.matches('\n').count();
+        push(LintRule::TrailingNewline, line, text_end, text_end);
+    }
+
+    diagnostics.sort_by_key(|d| (d.start, d.end));
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(text: &str) -> Vec<(LintRule, usize)> {
+        lint(text, &LintConfig::default())
+            .into_iter()
+            .map(|d| (d.rule, d.line))
+            .collect()
+    }
+
+    #[test]
+    fn test_headings() {
+        assert_eq!(
+            rules("# Title\n\n### Skipped\n\n## Fine\n\n# Second\n\n#Tag\n"),
+            vec![
+                (LintRule::HeadingIncrement, 2),
+                (LintRule::SingleH1, 6),
+                (LintRule::MissingSpaceAtx, 8),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_whitespace() {
+        assert_eq!(
+            rules("a \nhard break  \n\tb\n\n\n\nc"),
+            vec![
+                (LintRule::TrailingSpaces, 0),
+                (LintRule::HardTabs, 2),
+                (LintRule::MultipleBlanks, 4),
+                (LintRule::MultipleBlanks, 5),
+                (LintRule::TrailingNewline, 6),
+            ]
+        );
+        assert_eq!(rules("a\n\n"), vec![(LintRule::TrailingNewline, 2)]);
+        assert_eq!(rules(""), vec![]);
+    }
+
+    #[test]
+    fn test_crlf() {
+        let diagnostics = lint("a\r\nb \r\n\tc\r\n", &LintConfig::default());
+        assert_eq!(
+            diagnostics
+                .iter()
+                .map(|d| (d.rule, d.start, d.end))
+                .collect::<Vec<_>>(),
+            vec![(LintRule::TrailingSpaces, 4, 5), (LintRule::HardTabs, 7, 8)]
+        );
+        assert_eq!(rules("a\r\n\r\n"), vec![(LintRule::TrailingNewline, 2)]);
+    }
+
+    #[test]
+    fn test_lists_and_images() {
+        let text = "- a\n- b\n* c\n\n---\n\n![](x.png) ![alt](y.png)\n";
+        let diagnostics = lint(text, &LintConfig::default());
+        assert_eq!(
+            diagnostics
+                .iter()
+                .map(|d| (d.rule, d.start, d.end))
+                .collect::<Vec<_>>(),
+            vec![
+                (LintRule::ListMarkerStyle, 8, 9),
+                (LintRule::NoAltText, 18, 28)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_code_blocks() {
+        assert_eq!(
+            rules("```\n# a\n### b\n```\n\n```rust\n\tlet a = 1; \n```\n"),
+            vec![
+                (LintRule::FencedCodeLanguage, 0),
+                (LintRule::HardTabs, 6),
+                (LintRule::TrailingSpaces, 6),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_config() {
+        let config = LintConfig::parse(
+            r#"{ "default": true, "MD009": false, "no-hard-tabs": false, "MD004": { "style": "dash" } }"#,
+        );
+        assert!(!config.is_enabled(LintRule::TrailingSpaces));
+        assert!(!config.is_enabled(LintRule::HardTabs));
+        assert!(config.is_enabled(LintRule::ListMarkerStyle));
+        assert!(config.is_enabled(LintRule::SingleH1));
+        assert_eq!(rules("a \n\tb\n").len(), 2);
+        assert_eq!(lint("a \n\tb\n", &config), vec![]);
+
+        let config = LintConfig::parse(r#"{ "default": false, "MD001": true }"#);
+        assert!(config.is_enabled(LintRule::HeadingIncrement));
+        assert!(!config.is_enabled(LintRule::HardTabs));
+
+        // Keys of rule options aren't rules
+        let config = LintConfig::parse(r#"{ "MD004": { "MD009": false, "default": false } }"#);
+        assert!(config.is_enabled(LintRule::TrailingSpaces));
+        assert!(config.is_enabled(LintRule::HardTabs));
+        assert_eq!(LintConfig::parse("not json"), LintConfig::default());
+    }
+
+    #[test]
+    fn test_set_rule() {
+        assert_eq!(
+            set_rule("", LintRule::HardTabs, false),
+            "{\n  \"MD010\": false\n}\n"
+        );
+        assert_eq!(
+            set_rule("{}", LintRule::HardTabs, false),
+            "{\n  \"MD010\": false}"
+        );
+        assert_eq!(
+            set_rule("{\n  \"MD013\": false\n}", LintRule::HardTabs, false),
+            "{\n  \"MD010\": false,\n  \"MD013\": false\n}"
+        );
+        assert_eq!(
+            set_rule("{ \"no-hard-tabs\": false }", LintRule::HardTabs, true),
+            "{ \"no-hard-tabs\": true }"
+        );
+        assert_eq!(
+            set_rule(
+                "{ \"MD004\": { \"style\": \"}\" }, \"MD013\": false }",
+                LintRule::ListMarkerStyle,
+                false
+            ),
+            "{ \"MD004\": false, \"MD013\": false }"
+        );
+        assert_eq!(
+            set_rule(
+                "{ \"MD004\": { \"MD010\": false } }",
+                LintRule::HardTabs,
+                false
+            ),
+            "{\n  \"MD010\": false, \"MD004\": { \"MD010\": false } }"
+        );
+        assert_eq!(
+            set_rule(
+                "{ \"MD004\": { \"style\": \"dash\" } }",
+                LintRule::ListMarkerStyle,
+                true
+            ),
+            "{ \"MD004\": { \"style\": \"dash\" } }"
+        );
+    }
+}
@@ -0,0 +1,123 @@
+//! LaTeX math in documents. The previews are rendered by [`crate::util::math_render`].
+
+use std::ops::Range;
+
+use crate::util::regex;
+
+/// A formula in the document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Math<'a> {
+    /// Byte range, including the dollars
+    pub range: Range<usize>,
+    /// The formula without the dollars
+    pub tex: &'a str,
+    /// `$$display$$` instead of `$inline$`
+    pub display: bool,
+}
+
+/// The formula at a byte offset. Math in fenced code doesn't count.
+pub fn math_at(text: &str, offset: usize) -> Option<Math<'_>> {
+    let mut in_fence = false;
+    let mut display_start: Option<usize> = None;
+    let mut line_start = 0;
+    for line in text.split_inclusive('\n') {
+        let line_end = line_start + line.len();
+        if display_start.is_none() && regex::CODE_FENCE.is_match(line) {
+            in_fence = !in_fence;
+        }
+        if in_fence {
+            line_start = line_end;
+            continue;
+        }
+
+        let mut pos = line_start;
+        while pos < line_end {
+            let rest = &text[pos..line_end];
+            if let Some(start) = display_start {
+                let Some(close) = rest.find("$$") else {
+                    break;
+                };
+                let end = pos + close + 2;
+                if (start..end).contains(&offset) {
+                    return Some(Math {
+                        range: start..end,
+                        tex: &text[start + 2..end - 2],
+                        display: true,
+                    });
+                }
+                display_start = None;
+                pos = end;
+                continue;
+            }
+
+            let open = rest.find("$$").map(|i| pos + i).unwrap_or(line_end);
+            for found in regex::MATH_INLINE.find_iter(&text[pos..open]) {
+                let range = pos + found.start()..pos + found.end();
+                if range.contains(&offset) {
+                    return Some(Math {
+                        tex: &text[range.start + 1..range.end - 1],
+                        range,
+                        display: false,
+                    });
+                }
+            }
+            if open < line_end {
+                display_start = Some(open);
+                pos = open + 2;
+            } else {
+                pos = line_end;
+            }
+        }
+        line_start = line_end;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_math_at_inline() {
+        let text = "Euler: $e^{i\\pi} = -1$, done";
+        let math = math_at(text, 10).unwrap();
+        assert_eq!(math.tex, "e^{i\\pi} = -1");
+        assert_eq!(&text[math.range.clone()], "$e^{i\\pi} = -1$");
+        assert!(!math.display);
+        assert!(math_at(text, 2).is_none());
+        assert!(math_at(text, text.len() - 1).is_none());
+    }
+
+    #[test]
+    fn test_math_at_display() {
+        let text = "before\n$$\n\\sum_{k=1}^n k\n$$\nafter $x$";
+        let math = math_at(text, 12).unwrap();
+        assert_eq!(math.tex, "\n\\sum_{k=1}^n k\n");
+        assert!(math.display);
+        assert_eq!(math_at(text, 7).unwrap().range, 7..27);
+        assert_eq!(math_at(text, text.len() - 2).unwrap().tex, "x");
+        assert!(math_at(text, 2).is_none());
+    }
+
+    #[test]
+    fn test_math_at_one_line_display() {
+        let text = "$a$ and $$b$$ and $c$";
+        assert_eq!(math_at(text, 1).unwrap().tex, "a");
+        assert_eq!(math_at(text, 10).unwrap().tex, "b");
+        assert!(math_at(text, 10).unwrap().display);
+        assert_eq!(math_at(text, 19).unwrap().tex, "c");
+    }
+
+    #[test]
+    fn test_math_at_skips_code() {
+        let text = "```sh\necho $HOME$\n```\n$y$";
+        assert!(math_at(text, 12).is_none());
+        assert_eq!(math_at(text, text.len() - 2).unwrap().tex, "y");
+    }
+
+    #[test]
+    fn test_math_at_prices() {
+        let text = "It costs $5, or $6 with tax";
+        assert!(math_at(text, 10).is_none());
+    }
+}
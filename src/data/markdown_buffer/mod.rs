@@ -1,7 +1,20 @@
+mod focus;
+mod footnotes;
+mod formatter;
 mod formatting;
+mod link_rewrite;
+mod links;
+mod lint;
+mod math;
+mod sections;
+mod style;
+mod table;
+mod typography;
 
 mod imp {
     use std::cell::Cell;
+    use std::cell::OnceCell;
+    use std::cell::RefCell;
 
     use adw::prelude::*;
     use adw::subclass::prelude::*;
@@ -11,12 +24,37 @@ mod imp {
     use sourceview5::prelude::*;
     use sourceview5::subclass::prelude::*;
 
+    use super::StyleIssueKind;
+    use super::typography;
+    use super::typography::Quotes;
+    use crate::util::regex;
+
+    pub(super) const FOOTNOTE_UNDEFINED_TAG: &str = "footnote-undefined";
+    pub(super) const LINK_BROKEN_TAG: &str = "link-broken";
+    pub(super) const STYLE_ADVERB_TAG: &str = "style-adverb";
+    pub(super) const STYLE_PASSIVE_TAG: &str = "style-passive";
+    pub(super) const STYLE_FILLER_TAG: &str = "style-filler";
+    pub(super) const STYLE_LONG_SENTENCE_TAG: &str = "style-long-sentence";
+    pub(super) const STYLE_REPEATED_WORD_TAG: &str = "style-repeated-word";
+    pub(super) const FOLDED_TAG: &str = "folded";
+
     #[derive(Debug, Properties, Default)]
     #[properties(wrapper_type = super::MarkdownBuffer)]
     pub struct MarkdownBuffer {
         #[property(get, set)]
         pub(super) paste_in_progress: Cell<bool>,
         pub(super) formatting_action_in_progress: Cell<bool>,
+        /// An undo or redo is changing the text
+        #[property(get)]
+        history_action_in_progress: Cell<bool>,
+        /// Curly quotes, dashes and ellipses while typing
+        #[property(get, set)]
+        smart_typography: Cell<bool>,
+        quotes: OnceCell<Quotes>,
+        /// Lines with a code fence. Found once and kept until an edit could change them.
+        fence_lines: RefCell<Option<Vec<usize>>>,
+        /// Marks at the start of the folded headings
+        pub(super) folds: RefCell<Vec<gtk::TextMark>>,
     }
 
     #[glib::object_subclass]
@@ -31,6 +69,45 @@ mod imp {
         fn constructed(&self) {
             let obj = self.obj();
             obj.set_highlight_matching_brackets(false);
+
+            let footnote_undefined_tag = gtk::TextTag::builder()
+                .name(FOOTNOTE_UNDEFINED_TAG)
+                .underline(gtk::pango::Underline::Error)
+                .build();
+            obj.tag_table().add(&footnote_undefined_tag);
+
+            let link_broken_tag = gtk::TextTag::builder()
+                .name(LINK_BROKEN_TAG)
+                .underline(gtk::pango::Underline::Error)
+                .build();
+            obj.tag_table().add(&link_broken_tag);
+
+            // Long sentences get a background, so that the underlines inside stay visible
+            let long_sentence_tag = gtk::TextTag::builder()
+                .name(STYLE_LONG_SENTENCE_TAG)
+                .background_rgba(&super::style_issue_color(StyleIssueKind::LongSentence))
+                .build();
+            obj.tag_table().add(&long_sentence_tag);
+            for (name, kind) in [
+                (STYLE_ADVERB_TAG, StyleIssueKind::Adverb),
+                (STYLE_PASSIVE_TAG, StyleIssueKind::Passive),
+                (STYLE_FILLER_TAG, StyleIssueKind::Filler),
+                (STYLE_REPEATED_WORD_TAG, StyleIssueKind::RepeatedWord),
+            ] {
+                let tag = gtk::TextTag::builder()
+                    .name(name)
+                    .underline(gtk::pango::Underline::Single)
+                    .underline_rgba(&super::style_issue_color(kind))
+                    .build();
+                obj.tag_table().add(&tag);
+            }
+
+            let folded_tag = gtk::TextTag::builder()
+                .name(FOLDED_TAG)
+                .invisible(true)
+                .build();
+            obj.tag_table().add(&folded_tag);
+
             self.parent_constructed();
         }
     }
@@ -42,10 +119,12 @@ mod imp {
             let mut process_text = Some(new_text.to_owned());
             let cursor_move;
 
+            process_text = self.process_smart_typography(iter, process_text);
             (process_text, cursor_move) = self.process_auto_close_formatting(iter, process_text);
 
             if let Some(new_text) = process_text {
-                self.parent_insert_text(iter, &new_text)
+                self.parent_insert_text(iter, &new_text);
+                self.invalidate_fence_lines(iter, &new_text);
             }
 
             let obj = self.obj();
@@ -54,14 +133,124 @@ mod imp {
             obj.place_cursor(&cursor);
         }
 
+        fn delete_range(&self, start: &mut TextIter, end: &mut TextIter) {
+            let deleted = self.obj().text(start, end, true);
+            self.invalidate_fence_lines(start, &deleted);
+            self.parent_delete_range(start, end)
+        }
+
         fn paste_done(&self, clipboard: &gtk::gdk::Clipboard) {
             self.paste_in_progress.replace(false);
             self.parent_paste_done(clipboard)
         }
+
+        fn undo(&self) {
+            self.history_action_in_progress.set(true);
+            self.parent_undo();
+            self.history_action_in_progress.set(false);
+        }
+
+        fn redo(&self) {
+            self.history_action_in_progress.set(true);
+            self.parent_redo();
+            self.history_action_in_progress.set(false);
+        }
     }
     impl BufferImpl for MarkdownBuffer {}
 
     impl MarkdownBuffer {
+        /// Replaces typed quotes, dashes and dots with proper typography, outside of code.
+        /// Chars before the cursor may be deleted, `iter` is revalidated then.
+        pub(super) fn process_smart_typography(
+            &self,
+            iter: &mut TextIter,
+            process_text: Option<String>,
+        ) -> Option<String> {
+            if !self.smart_typography.get()
+                || self.paste_in_progress.get()
+                || self.formatting_action_in_progress.get()
+            {
+                return process_text;
+            }
+            let process_text = process_text?;
+
+            let obj = self.obj();
+            let mut line_start = *iter;
+            line_start.set_line_offset(0);
+            let mut line_end = *iter;
+            if !line_end.ends_line() {
+                line_end.forward_to_line_end();
+            }
+            let line_before = obj.text(&line_start, iter, false);
+            let line_after = obj.text(iter, &line_end, false);
+            let quotes = self.quotes.get_or_init(|| {
+                let locale = glib::language_names()
+                    .into_iter()
+                    .next()
+                    .unwrap_or_default();
+                Quotes::for_locale(&locale)
+            });
+
+            let Some(replacement) =
+                typography::replacement(&line_before, &line_after, &process_text, quotes)
+            else {
+                return Some(process_text);
+            };
+            if self.in_fenced_code(&line_start) {
+                return Some(process_text);
+            }
+
+            if replacement.delete > 0 {
+                let mut start = *iter;
+                start.backward_chars(replacement.delete as i32);
+                obj.delete(&mut start, iter);
+            }
+            Some(replacement.text)
+        }
+
+        /// True if the line starts inside a fenced code block
+        pub(super) fn in_fenced_code(&self, line_start: &TextIter) -> bool {
+            let line = line_start.line() as usize;
+            let num_fences = self
+                .with_fence_lines(|fences| fences.partition_point(|fence_line| *fence_line < line));
+            num_fences % 2 == 1
+        }
+
+        /// Calls `f` with the numbers of the lines that have a code fence
+        pub(super) fn with_fence_lines<R>(&self, f: impl FnOnce(&[usize]) -> R) -> R {
+            let mut fence_lines = self.fence_lines.borrow_mut();
+            let fence_lines = fence_lines.get_or_insert_with(|| {
+                let obj = self.obj();
+                let text = obj.text(&obj.start_iter(), &obj.end_iter(), true);
+                text.lines()
+                    .enumerate()
+                    .filter(|(_, line)| regex::CODE_FENCE.is_match(line))
+                    .map(|(i, _)| i)
+                    .collect()
+            });
+            f(fence_lines)
+        }
+
+        /// Forgets the fence lines if the edited text or its line could start or end a fence
+        fn invalidate_fence_lines(&self, iter: &TextIter, edited_text: &str) {
+            if self.fence_lines.borrow().is_none() {
+                return;
+            }
+            let mut line_start = *iter;
+            line_start.set_line_offset(0);
+            let mut line_end = *iter;
+            if !line_end.ends_line() {
+                line_end.forward_to_line_end();
+            }
+            let line = self.obj().text(&line_start, &line_end, false);
+            if edited_text.contains(['\n', '\r', '`', '~'])
+                || line.contains("```")
+                || line.contains("~~~")
+            {
+                self.fence_lines.replace(None);
+            }
+        }
+
         /// If you type an opening formatting char, for example an asterisk,
         /// this will place a closing one after the cursor
         pub(super) fn process_auto_close_formatting(
@@ -144,6 +333,25 @@ mod imp {
                         return (Some(process_text), 0);
                     }
                 },
+                "$" => match lookahead {
+                    Some('$') => {
+                        let lookback = self.lookback2(iter);
+                        if lookback == "$$" || !has_whitespace(&lookback) {
+                            return (None, 1);
+                        }
+                        return (Some(process_text + "$"), -1);
+                    }
+                    Some(c) if !c.is_whitespace() => {
+                        return (Some(process_text), 0);
+                    }
+                    _ => {
+                        // A lone dollar is more likely a price than math
+                        if self.lookback(iter) == Some('$') {
+                            return (Some(process_text + "$$"), -2);
+                        }
+                        return (Some(process_text), 0);
+                    }
+                },
                 "`" => match lookahead {
                     Some('`') => {
                         return (None, 1);
@@ -178,7 +386,10 @@ mod imp {
     }
 }
 
+use std::path::Path;
+
 use adw::subclass::prelude::*;
+use gtk::TextIter;
 use gtk::glib;
 use gtk::glib::Object;
 use sourceview5::LanguageManager;
@@ -188,6 +399,47 @@ use sourceview5::prelude::*;
 #[cfg(feature = "installed")]
 use crate::config::PKGDATADIR;
 use crate::data::DocumentStats;
+use crate::data::FocusScope;
+use crate::util::regex;
+
+pub use formatter::FormatOptions;
+pub use link_rewrite::LinkEdit;
+pub use link_rewrite::MovedItem;
+pub use link_rewrite::apply_edits as apply_link_edits;
+pub use link_rewrite::link_edits;
+pub use links::BrokenLink;
+pub use links::Link as MarkdownLink;
+pub use links::LinkProblem;
+pub use links::broken_links;
+pub use lint::LintConfig;
+pub use lint::LintDiagnostic;
+pub use lint::LintRule;
+pub use math::Math;
+pub use math::math_at;
+pub use sections::Section;
+pub use sections::section_at;
+pub use style::StyleIssueKind;
+pub use style::StyleSummary;
+pub use style::StyleWords;
+pub use table::Alignment as TableAlignment;
+
+/// Source mark category of lint diagnostics
+pub const LINT_MARK_CATEGORY: &str = "lint";
+/// Source mark category of headings that can be folded
+pub const FOLD_MARK_CATEGORY: &str = "fold";
+/// Source mark category of folded headings
+pub const FOLDED_MARK_CATEGORY: &str = "folded";
+
+/// Marks style issues in the editor, and their labels in the sidebar
+pub fn style_issue_color(kind: StyleIssueKind) -> gtk::gdk::RGBA {
+    match kind {
+        StyleIssueKind::Adverb => gtk::gdk::RGBA::new(0.21, 0.52, 0.89, 1.0),
+        StyleIssueKind::Passive => gtk::gdk::RGBA::new(0.18, 0.76, 0.49, 1.0),
+        StyleIssueKind::Filler => gtk::gdk::RGBA::new(1.0, 0.47, 0.0, 1.0),
+        StyleIssueKind::LongSentence => gtk::gdk::RGBA::new(0.96, 0.83, 0.18, 0.25),
+        StyleIssueKind::RepeatedWord => gtk::gdk::RGBA::new(0.57, 0.25, 0.67, 1.0),
+    }
+}
 
 glib::wrapper! {
     pub struct MarkdownBuffer(ObjectSubclass<imp::MarkdownBuffer>)
@@ -218,11 +470,23 @@ impl MarkdownBuffer {
         let num_chars = self.char_count();
         let mut num_spaces = 0;
         let mut num_words = 0;
+        let mut num_tasks = 0;
+        let mut num_tasks_done = 0;
         let mut prev_whitespace = true;
+        let mut in_fence = false;
         for i in 0..num_lines {
             let start = self.iter_at_line(i).unwrap();
             let end = self.iter_at_line(i + 1).unwrap_or_else(|| self.end_iter());
             let text = self.text(&start, &end, true);
+            // Task items in code blocks are examples, not tasks
+            if regex::CODE_FENCE.is_match(&text) {
+                in_fence = !in_fence;
+            } else if !in_fence && let Some(caps) = regex::TASK_ITEM.captures(&text) {
+                num_tasks += 1;
+                if &caps[2] != " " {
+                    num_tasks_done += 1;
+                }
+            }
             for char in text.chars() {
                 let is_whitespace = char.is_whitespace();
                 if is_whitespace {
@@ -238,6 +502,8 @@ impl MarkdownBuffer {
             num_chars,
             num_spaces,
             num_words,
+            num_tasks,
+            num_tasks_done,
         }
     }
 
@@ -288,10 +554,510 @@ impl MarkdownBuffer {
         self.imp().formatting_action_in_progress.set(false);
     }
 
+    pub fn format_task(&self) {
+        self.imp().formatting_action_in_progress.set(true);
+        formatting::format_task(self);
+        self.imp().formatting_action_in_progress.set(false);
+    }
+
+    /// Toggle the task item if the iter is on its checkbox. Returns true if it was toggled.
+    pub fn toggle_task_at(&self, iter: &TextIter) -> bool {
+        let mut line_start = *iter;
+        line_start.set_line_offset(0);
+        if self.imp().in_fenced_code(&line_start) {
+            return false;
+        }
+        self.imp().formatting_action_in_progress.set(true);
+        let toggled = formatting::toggle_task_at(self, iter);
+        self.imp().formatting_action_in_progress.set(false);
+        toggled
+    }
+
+    pub fn format_table(&self, num_rows: usize, num_columns: usize) {
+        self.imp().formatting_action_in_progress.set(true);
+        formatting::format_table(self, num_rows, num_columns);
+        self.imp().formatting_action_in_progress.set(false);
+    }
+
+    /// Re-pad the table under the cursor. Returns false if there's no table.
+    pub fn format_table_reformat(&self) -> bool {
+        self.imp().formatting_action_in_progress.set(true);
+        let found = formatting::format_table_reformat(self);
+        self.imp().formatting_action_in_progress.set(false);
+        found
+    }
+
+    /// Re-pad the table under the cursor after it was edited. Returns whether it changed.
+    pub fn table_realign(&self) -> bool {
+        self.imp().formatting_action_in_progress.set(true);
+        let changed = formatting::table_realign(self);
+        self.imp().formatting_action_in_progress.set(false);
+        changed
+    }
+
+    /// Re-pad the table on the iter's line, when the cursor has left it.
+    /// Returns whether it changed.
+    pub fn table_realign_at(&self, iter: &TextIter) -> bool {
+        self.imp().formatting_action_in_progress.set(true);
+        let changed = formatting::table_realign_at(self, iter);
+        self.imp().formatting_action_in_progress.set(false);
+        changed
+    }
+
+    pub fn cursor_in_table(&self) -> bool {
+        formatting::cursor_in_table(self)
+    }
+
+    /// Whether the cursor is in the table on the iter's line
+    pub fn cursor_in_table_at(&self, iter: &TextIter) -> bool {
+        let cursor_line = self.iter_at_mark(&self.get_insert()).line();
+        formatting::table_lines(self, iter).is_some_and(|lines| lines.contains(&cursor_line))
+    }
+
+    /// Move to the next or previous table cell. Returns false if there's no table.
+    pub fn table_move_cell(&self, backwards: bool) -> bool {
+        self.imp().formatting_action_in_progress.set(true);
+        let found = formatting::table_move_cell(self, backwards);
+        self.imp().formatting_action_in_progress.set(false);
+        found
+    }
+
+    pub fn table_insert_row(&self) {
+        self.imp().formatting_action_in_progress.set(true);
+        formatting::table_insert_row(self);
+        self.imp().formatting_action_in_progress.set(false);
+    }
+
+    pub fn table_remove_row(&self) {
+        self.imp().formatting_action_in_progress.set(true);
+        formatting::table_remove_row(self);
+        self.imp().formatting_action_in_progress.set(false);
+    }
+
+    pub fn table_insert_column(&self) {
+        self.imp().formatting_action_in_progress.set(true);
+        formatting::table_insert_column(self);
+        self.imp().formatting_action_in_progress.set(false);
+    }
+
+    pub fn table_remove_column(&self) {
+        self.imp().formatting_action_in_progress.set(true);
+        formatting::table_remove_column(self);
+        self.imp().formatting_action_in_progress.set(false);
+    }
+
+    pub fn table_align_column(&self, alignment: TableAlignment) {
+        self.imp().formatting_action_in_progress.set(true);
+        formatting::table_align_column(self, alignment);
+        self.imp().formatting_action_in_progress.set(false);
+    }
+
+    pub fn insert_link(&self, iter: &TextIter, title: &str, destination: &str, is_image: bool) {
+        self.imp().formatting_action_in_progress.set(true);
+        formatting::insert_link(self, iter, title, destination, is_image);
+        self.imp().formatting_action_in_progress.set(false);
+    }
+
+    pub fn format_footnote(&self) {
+        self.imp().formatting_action_in_progress.set(true);
+        formatting::format_footnote(self);
+        self.imp().formatting_action_in_progress.set(false);
+    }
+
+    /// Where to jump from a footnote reference or definition, if anywhere
+    pub fn footnote_target_at(&self, iter: &TextIter) -> Option<TextIter> {
+        formatting::footnote_target_at(self, iter)
+    }
+
+    /// Underline footnote references that don't have a definition
+    pub fn refresh_footnote_check(&self) {
+        let (start, end) = self.bounds();
+        self.remove_tag_by_name(imp::FOOTNOTE_UNDEFINED_TAG, &start, &end);
+        let text = self.text(&start, &end, true);
+        for footnote in footnotes::undefined_references(&text) {
+            self.apply_tag_by_name(
+                imp::FOOTNOTE_UNDEFINED_TAG,
+                &self.iter_at_offset(footnote.start as i32),
+                &self.iter_at_offset(footnote.end as i32),
+            );
+        }
+    }
+
+    /// Inline links and images outside of code blocks
+    pub fn links(&self) -> Vec<MarkdownLink> {
+        let (start, end) = self.bounds();
+        links::links(&self.text(&start, &end, true))
+    }
+
+    /// Local links that point to missing files or headings
+    pub fn broken_links(&self, document_path: &Path) -> Vec<BrokenLink> {
+        let (start, end) = self.bounds();
+        links::broken_links(&self.text(&start, &end, true), document_path)
+    }
+
+    /// Underline broken links
+    pub fn refresh_link_check(&self, document_path: &Path) {
+        let (start, end) = self.bounds();
+        self.remove_tag_by_name(imp::LINK_BROKEN_TAG, &start, &end);
+        for broken in self.broken_links(document_path) {
+            self.apply_tag_by_name(
+                imp::LINK_BROKEN_TAG,
+                &self.iter_at_offset(broken.link.start as i32),
+                &self.iter_at_offset(broken.link.end as i32),
+            );
+        }
+    }
+
+    /// Mark prose style issues, and count them
+    pub fn refresh_style_check(&self, words: &StyleWords) -> StyleSummary {
+        self.clear_style_check();
+        let (start, end) = self.bounds();
+        let issues = style::check(&self.text(&start, &end, true), words);
+        for issue in &issues {
+            let tag = match issue.kind {
+                StyleIssueKind::Adverb => imp::STYLE_ADVERB_TAG,
+                StyleIssueKind::Passive => imp::STYLE_PASSIVE_TAG,
+                StyleIssueKind::Filler => imp::STYLE_FILLER_TAG,
+                StyleIssueKind::LongSentence => imp::STYLE_LONG_SENTENCE_TAG,
+                StyleIssueKind::RepeatedWord => imp::STYLE_REPEATED_WORD_TAG,
+            };
+            self.apply_tag_by_name(
+                tag,
+                &self.iter_at_offset(issue.start as i32),
+                &self.iter_at_offset(issue.end as i32),
+            );
+        }
+        StyleSummary::of(&issues)
+    }
+
+    pub fn clear_style_check(&self) {
+        let (start, end) = self.bounds();
+        for tag in [
+            imp::STYLE_ADVERB_TAG,
+            imp::STYLE_PASSIVE_TAG,
+            imp::STYLE_FILLER_TAG,
+            imp::STYLE_LONG_SENTENCE_TAG,
+            imp::STYLE_REPEATED_WORD_TAG,
+        ] {
+            self.remove_tag_by_name(tag, &start, &end);
+        }
+    }
+
+    /// Put a gutter mark on each line with a lint diagnostic
+    pub fn refresh_lint(&self, config: &LintConfig) -> Vec<LintDiagnostic> {
+        self.clear_lint();
+        let (start, end) = self.bounds();
+        let diagnostics = lint::lint(&self.text(&start, &end, true), config);
+        for diagnostic in &diagnostics {
+            self.create_source_mark(
+                None,
+                LINT_MARK_CATEGORY,
+                &self.iter_at_offset(diagnostic.start as i32),
+            );
+        }
+        diagnostics
+    }
+
+    pub fn clear_lint(&self) {
+        let (start, end) = self.bounds();
+        self.remove_source_marks(&start, &end, Some(LINT_MARK_CATEGORY));
+    }
+
+    pub fn apply_link_edits(&self, edits: &[LinkEdit]) {
+        self.imp().formatting_action_in_progress.set(true);
+        formatting::apply_link_edits(self, edits);
+        self.imp().formatting_action_in_progress.set(false);
+    }
+
+    /// Rewrite the document in one Markdown style. Returns false if nothing changed.
+    pub fn format_document(&self, options: &FormatOptions) -> bool {
+        self.imp().formatting_action_in_progress.set(true);
+        let changed = formatting::format_document(self, options);
+        self.imp().formatting_action_in_progress.set(false);
+        changed
+    }
+
+    /// Rewrap the paragraph or selection at `column`, or unwrap it if there's no column.
+    /// Returns false if there's no paragraph.
+    pub fn reflow_paragraph(&self, column: Option<usize>) -> bool {
+        self.imp().formatting_action_in_progress.set(true);
+        let found = formatting::reflow_paragraph(self, column);
+        self.imp().formatting_action_in_progress.set(false);
+        found
+    }
+
     pub fn format_code(&self) {
         self.imp().formatting_action_in_progress.set(true);
         formatting::format_code(self);
     }
+
+    /// Swap the section at the cursor with the one of the same level before or after it.
+    /// Folded sections stay folded. Returns false if there's nothing to swap with.
+    pub fn move_section(&self, up: bool) -> bool {
+        let folded = self.folded_headings();
+        self.imp().formatting_action_in_progress.set(true);
+        let moved = formatting::move_section(self, up);
+        self.imp().formatting_action_in_progress.set(false);
+        self.fold_headings(&folded);
+        moved
+    }
+
+    /// Promote or demote the section at the cursor, with the headings inside it.
+    /// Returns false if a heading can't go further.
+    pub fn shift_section(&self, promote: bool) -> bool {
+        let folded = self.folded_lines();
+        self.imp().formatting_action_in_progress.set(true);
+        let shifted = formatting::shift_section(self, promote);
+        self.imp().formatting_action_in_progress.set(false);
+        self.set_folded_lines(&folded);
+        shifted
+    }
+
+    /// Headings and the lines under them
+    pub fn sections(&self) -> Vec<Section> {
+        let (start, end) = self.bounds();
+        sections::sections(&self.text(&start, &end, true))
+    }
+
+    /// Fold or unfold the section of the heading on the line.
+    /// Returns false if there's nothing under the heading.
+    pub fn toggle_fold(&self, line: usize) -> bool {
+        if !self
+            .sections()
+            .iter()
+            .any(|section| section.line == line && section.has_body())
+        {
+            return false;
+        }
+        let mut lines = self.folded_lines();
+        match lines.iter().position(|folded| *folded == line) {
+            Some(i) => {
+                lines.remove(i);
+            }
+            None => lines.push(line),
+        }
+        self.set_folded_lines(&lines);
+        true
+    }
+
+    /// Fold the sections of headings at the level or below it
+    pub fn fold_to_level(&self, level: usize) {
+        let lines: Vec<usize> = self
+            .sections()
+            .iter()
+            .filter(|section| section.level >= level && section.has_body())
+            .map(|section| section.line)
+            .collect();
+        self.set_folded_lines(&lines);
+    }
+
+    pub fn unfold_all(&self) {
+        self.set_folded_lines(&[]);
+    }
+
+    /// The outermost folded section that hides the line
+    pub fn folded_section_at(&self, line: usize) -> Option<Section> {
+        let folded = self.folded_lines();
+        self.sections().into_iter().find(|section| {
+            folded.contains(&section.line) && section.body <= line && line < section.end
+        })
+    }
+
+    /// Whether the iter is in a folded section
+    pub fn is_folded_at(&self, iter: &TextIter) -> bool {
+        self.tag_table()
+            .lookup(imp::FOLDED_TAG)
+            .is_some_and(|tag| iter.has_tag(&tag))
+    }
+
+    /// Unfold the sections that hide the line. Returns false if it wasn't hidden.
+    pub fn unfold_line(&self, line: usize) -> bool {
+        let sections = self.sections();
+        let folded = self.folded_lines();
+        let lines: Vec<usize> = folded
+            .iter()
+            .copied()
+            .filter(|folded_line| {
+                !sections.iter().any(|section| {
+                    section.line == *folded_line && section.body <= line && line < section.end
+                })
+            })
+            .collect();
+        if lines.len() == folded.len() {
+            return false;
+        }
+        self.set_folded_lines(&lines);
+        true
+    }
+
+    /// Text of the folded headings, to fold them again when the document is opened
+    pub fn folded_headings(&self) -> Vec<String> {
+        self.folded_lines()
+            .into_iter()
+            .filter_map(|line| self.line_text(line))
+            .collect()
+    }
+
+    /// Fold the sections of the headings with these texts
+    pub fn fold_headings(&self, headings: &[String]) {
+        let lines: Vec<usize> = self
+            .sections()
+            .iter()
+            .filter(|section| {
+                section.has_body()
+                    && self
+                        .line_text(section.line)
+                        .is_some_and(|text| headings.contains(&text))
+            })
+            .map(|section| section.line)
+            .collect();
+        self.set_folded_lines(&lines);
+    }
+
+    /// Hide the folded sections and mark the headings that can be folded.
+    /// Folds of headings that are gone are dropped. Returns false if there's nothing to fold.
+    pub fn refresh_folds(&self) -> bool {
+        let (start, end) = self.bounds();
+        self.remove_tag_by_name(imp::FOLDED_TAG, &start, &end);
+        self.remove_source_marks(&start, &end, Some(FOLD_MARK_CATEGORY));
+        self.remove_source_marks(&start, &end, Some(FOLDED_MARK_CATEGORY));
+
+        let sections = self.sections();
+        let mut folded = Vec::new();
+        let mut kept = Vec::new();
+        for mark in self.imp().folds.take() {
+            let line = self.iter_at_mark(&mark).line() as usize;
+            if !folded.contains(&line)
+                && sections
+                    .iter()
+                    .any(|section| section.line == line && section.has_body())
+            {
+                folded.push(line);
+                kept.push(mark);
+            } else {
+                self.delete_mark(&mark);
+            }
+        }
+        self.imp().folds.replace(kept);
+
+        for section in sections.iter().filter(|section| section.has_body()) {
+            let Some(heading) = self.iter_at_line(section.line as i32) else {
+                continue;
+            };
+            if !folded.contains(&section.line) {
+                self.create_source_mark(None, FOLD_MARK_CATEGORY, &heading);
+                continue;
+            }
+            self.create_source_mark(None, FOLDED_MARK_CATEGORY, &heading);
+            // Whole lines are hidden, so the newline of the heading stays
+            let body = self
+                .iter_at_line(section.body as i32)
+                .unwrap_or_else(|| self.end_iter());
+            let section_end = self
+                .iter_at_line(section.end as i32)
+                .unwrap_or_else(|| self.end_iter());
+            self.apply_tag_by_name(imp::FOLDED_TAG, &body, &section_end);
+        }
+        sections.iter().any(Section::has_body)
+    }
+
+    fn folded_lines(&self) -> Vec<usize> {
+        self.imp()
+            .folds
+            .borrow()
+            .iter()
+            .map(|mark| self.iter_at_mark(mark).line() as usize)
+            .collect()
+    }
+
+    /// Fold the sections of the headings on the lines, and unfold the others
+    fn set_folded_lines(&self, lines: &[usize]) {
+        for mark in self.imp().folds.take() {
+            self.delete_mark(&mark);
+        }
+        let marks = lines
+            .iter()
+            .filter_map(|line| self.iter_at_line(*line as i32))
+            .map(|iter| self.create_mark(None, &iter, true))
+            .collect();
+        self.imp().folds.replace(marks);
+        self.refresh_folds();
+    }
+
+    /// The text around the iter that stays bright in typewriter mode
+    pub fn focus_range(&self, iter: &TextIter, scope: FocusScope) -> (TextIter, TextIter) {
+        let line = iter.line() as usize;
+        if scope == FocusScope::Line {
+            let start = self.iter_at_line(line as i32).unwrap_or(*iter);
+            let mut end = start;
+            if !end.ends_line() {
+                end.forward_to_line_end();
+            }
+            return (start, end);
+        }
+
+        // Code blocks can have blank lines, so the fences are found first
+        let line_count = self.line_count() as usize;
+        let fence_state = self
+            .imp()
+            .with_fence_lines(|fences| focus::fence_state(fences, line, line_count));
+        let (block, in_code) = match fence_state {
+            focus::FenceState::Code(block) => (block, true),
+            focus::FenceState::Prose(bounds) => {
+                // Other blocks end at blank lines, so only the lines between them are parsed
+                let is_blank =
+                    |line: usize| self.line_text(line).is_none_or(|text| text.is_empty());
+                let mut first = line;
+                while first > bounds.start && !is_blank(line) && !is_blank(first - 1) {
+                    first -= 1;
+                }
+                let mut last = line;
+                while last + 1 < bounds.end && !is_blank(line) && !is_blank(last + 1) {
+                    last += 1;
+                }
+                let chunk: Vec<String> = (first..=last)
+                    .map(|line| self.line_text(line).unwrap_or_default())
+                    .collect();
+                let chunk: Vec<&str> = chunk.iter().map(String::as_str).collect();
+                let block = focus::paragraph_lines(&chunk, line - first);
+                (first + block.start..first + block.end, false)
+            }
+        };
+
+        let start = self.iter_at_line(block.start as i32).unwrap_or(*iter);
+        let mut end = self.iter_at_line(block.end as i32 - 1).unwrap_or(*iter);
+        if !end.ends_line() {
+            end.forward_to_line_end();
+        }
+        // Code has no sentences
+        if scope == FocusScope::Paragraph || in_code {
+            return (start, end);
+        }
+
+        let text = self.text(&start, &end, true);
+        let cursor_chars = (iter.offset() - start.offset()).max(0) as usize;
+        let cursor = text
+            .char_indices()
+            .nth(cursor_chars)
+            .map_or(text.len(), |(i, _)| i);
+        let sentence = focus::sentence_at(&text, cursor);
+        let sentence_start = start.offset() + text[..sentence.start].chars().count() as i32;
+        let sentence_end = start.offset() + text[..sentence.end].chars().count() as i32;
+        (
+            self.iter_at_offset(sentence_start),
+            self.iter_at_offset(sentence_end),
+        )
+    }
+
+    /// Text of the line without the line break
+    fn line_text(&self, line: usize) -> Option<String> {
+        let start = self.iter_at_line(line as i32)?;
+        let mut end = start;
+        if !end.ends_line() {
+            end.forward_to_line_end();
+        }
+        Some(self.text(&start, &end, true).trim_end().to_owned())
+    }
 }
 
 /*
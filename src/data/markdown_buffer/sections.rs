@@ -0,0 +1,332 @@
+//! A section is a heading and everything under it, up to the next heading of the same or a
+//! higher level. Sections can be folded, moved past each other, and promoted or demoted.
+
+use std::ops::Range;
+
+use super::formatter;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Section {
+    /// Line of the heading
+    pub line: usize,
+    /// First line under the heading. Setext headings have an underline in between.
+    pub body: usize,
+    /// Line after the section
+    pub end: usize,
+    pub level: usize,
+}
+
+impl Section {
+    /// Whether there are lines under the heading to fold
+    pub fn has_body(&self) -> bool {
+        self.body < self.end
+    }
+}
+
+/// New lines for a range of lines
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinesEdit {
+    pub range: Range<usize>,
+    pub lines: Vec<String>,
+    /// How many lines the line at the cursor moves
+    pub offset: isize,
+}
+
+/// Sections of the text in the order of their headings. Nested sections are inside the ones
+/// before them.
+pub fn sections(text: &str) -> Vec<Section> {
+    let lines: Vec<&str> = text.split('\n').collect();
+    sections_of_lines(&lines)
+}
+
+fn sections_of_lines(lines: &[&str]) -> Vec<Section> {
+    let line_count = text_line_count(lines);
+    let headings = formatter::headings(lines);
+    headings
+        .iter()
+        .enumerate()
+        .map(|(i, (range, level))| {
+            let end = headings[i + 1..]
+                .iter()
+                .find(|(_, next_level)| next_level <= level)
+                .map_or(line_count, |(next, _)| next.start);
+            Section {
+                line: range.start,
+                body: range.end,
+                end,
+                level: *level,
+            }
+        })
+        .collect()
+}
+
+/// The innermost section that the line is in
+pub fn section_at(sections: &[Section], line: usize) -> Option<Section> {
+    sections
+        .iter()
+        .rev()
+        .find(|section| section.line <= line && line < section.end)
+        .copied()
+}
+
+/// Swap the section at the line with the one of the same level before or after it.
+/// Blank lines between the sections stay where they are.
+pub fn move_section(lines: &[&str], line: usize, up: bool) -> Option<LinesEdit> {
+    let sections = sections_of_lines(lines);
+    let current = section_at(&sections, line)?;
+    let (first, second) = if up {
+        let previous = sections
+            .iter()
+            .find(|section| section.level == current.level && section.end == current.line)?;
+        (*previous, current)
+    } else {
+        let next = sections
+            .iter()
+            .find(|section| section.level == current.level && section.line == current.end)?;
+        (current, *next)
+    };
+
+    let end = second.end;
+    let (first_content, first_blanks) = split_trailing_blanks(&lines[first.line..second.line]);
+    let (second_content, second_blanks) = split_trailing_blanks(&lines[second.line..end]);
+
+    let new: Vec<String> = [second_content, first_blanks, first_content, second_blanks]
+        .concat()
+        .into_iter()
+        .map(str::to_owned)
+        .collect();
+    let offset = if up {
+        first.line as isize - second.line as isize
+    } else {
+        (second_content.len() + first_blanks.len()) as isize
+    };
+    Some(LinesEdit {
+        range: first.line..end,
+        lines: new,
+        offset,
+    })
+}
+
+/// Promote or demote the heading of the section at the line, and the headings inside it.
+/// None if a heading would go past level 1 or 6.
+pub fn shift_section(lines: &[&str], line: usize, promote: bool) -> Option<LinesEdit> {
+    let sections = sections_of_lines(lines);
+    let current = section_at(&sections, line)?;
+    let inside: Vec<&Section> = sections
+        .iter()
+        .filter(|section| current.line <= section.line && section.line < current.end)
+        .collect();
+    if (promote && current.level == 1) || (!promote && inside.iter().any(|s| s.level == 6)) {
+        return None;
+    }
+
+    let end = current.end.min(lines.len());
+    let mut new = Vec::new();
+    let mut offset = 0;
+    let mut i = current.line;
+    while i < end {
+        let Some(section) = inside.iter().find(|section| section.line == i) else {
+            new.push(lines[i].to_owned());
+            i += 1;
+            continue;
+        };
+        let level = if promote {
+            section.level - 1
+        } else {
+            section.level + 1
+        };
+        if section.body == section.line + 2 {
+            // Setext headings only go to level 2, deeper ones become ATX headings
+            if level <= 2 {
+                let marker = if level == 1 { "=" } else { "-" };
+                new.push(lines[i].to_owned());
+                new.push(lines[i + 1].replace(['=', '-'], marker));
+            } else {
+                new.push(format!("{} {}", "#".repeat(level), lines[i].trim()));
+                if i < line {
+                    offset -= 1;
+                }
+            }
+            i += 2;
+        } else {
+            new.push(shift_atx(lines[i], level));
+            i += 1;
+        }
+    }
+    Some(LinesEdit {
+        range: current.line..end,
+        lines: new,
+        offset,
+    })
+}
+
+/// ATX heading line with a new level
+fn shift_atx(line: &str, level: usize) -> String {
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    let rest = line[indent..].trim_start_matches('#');
+    format!("{}{}{}", &line[..indent], "#".repeat(level), rest)
+}
+
+/// Number of lines without the empty one after the last line break, which is not under any
+/// heading and stays last
+fn text_line_count(lines: &[&str]) -> usize {
+    match lines.last() {
+        Some(&"") => lines.len() - 1,
+        _ => lines.len(),
+    }
+}
+
+/// The lines split before the blank lines at their end
+fn split_trailing_blanks<'a>(lines: &'a [&'a str]) -> (&'a [&'a str], &'a [&'a str]) {
+    let content = lines.len()
+        - lines
+            .iter()
+            .rev()
+            .take_while(|line| line.trim().is_empty())
+            .count();
+    lines.split_at(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "\
+Intro
+
+# One
+text
+
+## One A
+text
+
+## One B
+
+# Two
+Two B
+-----
+text
+";
+
+    fn section(line: usize, body: usize, end: usize, level: usize) -> Section {
+        Section {
+            line,
+            body,
+            end,
+            level,
+        }
+    }
+
+    #[test]
+    fn test_sections() {
+        assert_eq!(
+            sections(TEXT),
+            vec![
+                section(2, 3, 10, 1),
+                section(5, 6, 8, 2),
+                section(8, 9, 10, 2),
+                section(10, 11, 14, 1),
+                section(11, 13, 14, 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sections_skip_code() {
+        let text = "# One\n```\n# Not a heading\n```\n## Two\n";
+        assert_eq!(
+            sections(text),
+            vec![section(0, 1, 5, 1), section(4, 5, 5, 2)]
+        );
+    }
+
+    #[test]
+    fn test_sections_skip_front_matter() {
+        let text = "---\ntitle: Note\n---\n# One\n";
+        assert_eq!(sections(text), vec![section(3, 4, 4, 1)]);
+        assert!(!sections(text)[0].has_body());
+    }
+
+    #[test]
+    fn test_has_body() {
+        assert!(section(0, 1, 3, 1).has_body());
+        assert!(!section(0, 1, 1, 1).has_body());
+    }
+
+    #[test]
+    fn test_section_at() {
+        let sections = sections(TEXT);
+        assert_eq!(section_at(&sections, 0), None);
+        assert_eq!(section_at(&sections, 2), Some(sections[0]));
+        assert_eq!(section_at(&sections, 3), Some(sections[0]));
+        assert_eq!(section_at(&sections, 6), Some(sections[1]));
+        assert_eq!(section_at(&sections, 9), Some(sections[2]));
+        assert_eq!(section_at(&sections, 12), Some(sections[4]));
+        assert_eq!(section_at(&sections, 20), None);
+    }
+
+    fn lines(text: &str) -> Vec<&str> {
+        text.split('\n').collect()
+    }
+
+    /// The text after the edit
+    fn apply(text: &str, edit: &LinesEdit) -> String {
+        let mut lines: Vec<String> = lines(text).into_iter().map(str::to_owned).collect();
+        lines.splice(edit.range.clone(), edit.lines.clone());
+        lines.join("\n")
+    }
+
+    #[test]
+    fn test_move_section_up() {
+        let text = "# A\na\n\n# B\nb\n";
+        let edit = move_section(&lines(text), 4, true).unwrap();
+        assert_eq!(apply(text, &edit), "# B\nb\n\n# A\na\n");
+        assert_eq!(edit.offset, -3);
+    }
+
+    #[test]
+    fn test_move_section_down() {
+        let text = "Intro\n\n## A\na\n\n### A1\n\n## B\nb\n\n# C\n";
+        let edit = move_section(&lines(text), 3, false).unwrap();
+        assert_eq!(
+            apply(text, &edit),
+            "Intro\n\n## B\nb\n\n## A\na\n\n### A1\n\n# C\n"
+        );
+        assert_eq!(edit.offset, 3);
+    }
+
+    #[test]
+    fn test_move_section_without_neighbor() {
+        let text = "# A\n## A1\n## A2\n# B\n";
+        // Nested sections only move within their parent
+        assert!(move_section(&lines(text), 1, true).is_none());
+        assert!(move_section(&lines(text), 2, false).is_none());
+        assert!(move_section(&lines(text), 3, false).is_none());
+        assert!(move_section(&lines("Intro\n# A\n"), 0, true).is_none());
+        assert!(move_section(&lines(text), 2, true).is_some());
+    }
+
+    #[test]
+    fn test_shift_section() {
+        let text = "# A\n## B\ntext\n### C\n## D\n";
+        let edit = shift_section(&lines(text), 2, false).unwrap();
+        assert_eq!(apply(text, &edit), "# A\n### B\ntext\n#### C\n## D\n");
+        let edit = shift_section(&lines(text), 2, true).unwrap();
+        assert_eq!(apply(text, &edit), "# A\n# B\ntext\n## C\n## D\n");
+        assert!(shift_section(&lines(text), 0, true).is_none());
+        assert!(shift_section(&lines("###### A\n"), 0, false).is_none());
+        assert!(shift_section(&lines("Intro\n"), 0, false).is_none());
+    }
+
+    #[test]
+    fn test_shift_section_setext() {
+        let text = "A\n===\n\nB\n---\ntext\n";
+        let edit = shift_section(&lines(text), 5, false).unwrap();
+        assert_eq!(apply(text, &edit), "A\n===\n\n### B\ntext\n");
+        assert_eq!(edit.offset, -1);
+        let edit = shift_section(&lines(text), 0, false).unwrap();
+        assert_eq!(apply(text, &edit), "A\n---\n\n### B\ntext\n");
+        let edit = shift_section(&lines(text), 5, true).unwrap();
+        assert_eq!(apply(text, &edit), "A\n===\n\nB\n===\ntext\n");
+    }
+}
@@ -0,0 +1,737 @@
+//! Prose style checks: adverbs, passive voice, filler words, long sentences and repeated words.
+//! Code, link destinations and HTML tags are not prose and are skipped.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::error::ScratchmarkError;
+use crate::util::regex;
+
+/// Sentences with more words than this are long
+pub const LONG_SENTENCE_WORDS: usize = 30;
+/// A word is repeated if it was used this many words before or less
+pub const REPEAT_WINDOW: usize = 25;
+/// Shorter words are never repeated words
+const REPEAT_MIN_CHARS: usize = 4;
+
+const SECTION_ADVERB_SUFFIXES: &str = "adverb-suffixes";
+const SECTION_ADVERBS: &str = "adverbs";
+const SECTION_NOT_ADVERBS: &str = "not-adverbs";
+const SECTION_FILLERS: &str = "fillers";
+const SECTION_PASSIVE_AUXILIARIES: &str = "passive-auxiliaries";
+const SECTION_PARTICIPLE_SUFFIXES: &str = "participle-suffixes";
+const SECTION_PARTICIPLES: &str = "participles";
+const SECTION_NOT_PARTICIPLES: &str = "not-participles";
+const SECTION_COMMON_WORDS: &str = "common-words";
+
+const SECTIONS: [&str; 9] = [
+    SECTION_ADVERB_SUFFIXES,
+    SECTION_ADVERBS,
+    SECTION_NOT_ADVERBS,
+    SECTION_FILLERS,
+    SECTION_PASSIVE_AUXILIARIES,
+    SECTION_PARTICIPLE_SUFFIXES,
+    SECTION_PARTICIPLES,
+    SECTION_NOT_PARTICIPLES,
+    SECTION_COMMON_WORDS,
+];
+
+const HEADER: &str = "\
+# Word lists for the style check. Lines starting with # are comments.
+# Each [section] has one word or phrase per line. Case doesn't matter.
+";
+
+const ENGLISH: &str = "\
+[adverb-suffixes]
+ly
+
+[adverbs]
+almost
+quite
+rather
+somewhat
+
+[not-adverbs]
+ally
+apply
+belly
+bully
+butterfly
+costly
+curly
+daily
+early
+family
+fly
+friendly
+holy
+hourly
+italy
+jelly
+july
+lily
+likely
+lively
+lonely
+lovely
+monthly
+only
+reply
+rely
+silly
+supply
+ugly
+weekly
+yearly
+
+[fillers]
+a bit
+actually
+basically
+certainly
+definitely
+in order to
+just
+kind of
+literally
+needless to say
+of course
+really
+simply
+sort of
+totally
+very
+
+[passive-auxiliaries]
+am
+are
+be
+been
+being
+is
+was
+were
+
+[participle-suffixes]
+ed
+en
+
+[participles]
+bought
+brought
+built
+caught
+done
+drawn
+found
+given
+heard
+held
+hung
+kept
+known
+led
+left
+lost
+made
+meant
+met
+paid
+put
+read
+said
+seen
+sent
+set
+shown
+sold
+spent
+struck
+taught
+thought
+told
+understood
+won
+written
+
+[not-participles]
+children
+even
+garden
+golden
+heaven
+kitchen
+often
+open
+seven
+sudden
+ten
+women
+wooden
+
+[common-words]
+about
+after
+also
+because
+been
+before
+from
+have
+into
+just
+more
+other
+some
+that
+their
+them
+then
+there
+these
+they
+this
+what
+when
+where
+which
+will
+with
+would
+your
+";
+
+/// Kinds of style issues, in the order they are listed in the sidebar
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StyleIssueKind {
+    Adverb,
+    Passive,
+    Filler,
+    LongSentence,
+    RepeatedWord,
+}
+
+/// A style issue. Offsets are in chars, like TextIter offsets.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StyleIssue {
+    pub kind: StyleIssueKind,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Number of issues of each kind
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct StyleSummary {
+    pub adverbs: usize,
+    pub passive: usize,
+    pub fillers: usize,
+    pub long_sentences: usize,
+    pub repeated_words: usize,
+}
+
+impl StyleSummary {
+    pub fn of(issues: &[StyleIssue]) -> Self {
+        let mut summary = Self::default();
+        for issue in issues {
+            let count = match issue.kind {
+                StyleIssueKind::Adverb => &mut summary.adverbs,
+                StyleIssueKind::Passive => &mut summary.passive,
+                StyleIssueKind::Filler => &mut summary.fillers,
+                StyleIssueKind::LongSentence => &mut summary.long_sentences,
+                StyleIssueKind::RepeatedWord => &mut summary.repeated_words,
+            };
+            *count += 1;
+        }
+        summary
+    }
+}
+
+/// Word lists of a language. All words are lowercase.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct StyleWords {
+    /// Words with one of these endings are adverbs, unless listed in `not_adverbs`
+    pub adverb_suffixes: Vec<String>,
+    pub adverbs: Vec<String>,
+    pub not_adverbs: Vec<String>,
+    /// Single words or phrases
+    pub fillers: Vec<String>,
+    /// Forms of "to be" that make a passive with a participle after them
+    pub passive_auxiliaries: Vec<String>,
+    /// Words with one of these endings are participles, unless listed in `not_participles`
+    pub participle_suffixes: Vec<String>,
+    pub participles: Vec<String>,
+    pub not_participles: Vec<String>,
+    /// Never count as repeated words
+    pub common_words: Vec<String>,
+}
+
+impl StyleWords {
+    /// The word lists for a language like `en_US`, from the file in `folder`.
+    /// If there's no file yet, it's created from the built-in lists, so that it can be edited.
+    pub fn load(folder: &Path, language: &str) -> Result<Self, ScratchmarkError> {
+        let path = Self::path(folder, language);
+        if let Ok(text) = std::fs::read_to_string(&path) {
+            return Ok(Self::parse(&text));
+        }
+        let text = default_text(language);
+        std::fs::write(&path, &text).map_err(|e| {
+            println!("{e}");
+            ScratchmarkError::FileCreateFail
+        })?;
+        Ok(Self::parse(&text))
+    }
+
+    /// The word list file of a language in `folder`
+    pub fn path(folder: &Path, language: &str) -> PathBuf {
+        folder.join(format!("{}.txt", language_code(language)))
+    }
+
+    /// Read the `[section]` format of the word list files. Unknown sections are ignored.
+    pub fn parse(text: &str) -> Self {
+        let mut words = Self::default();
+        let mut section: Option<&mut Vec<String>> = None;
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = words.section(name.trim());
+                continue;
+            }
+            if let Some(list) = section.as_mut() {
+                let entry = line.split_whitespace().collect::<Vec<_>>().join(" ");
+                list.push(entry.to_lowercase());
+            }
+        }
+        words
+    }
+
+    fn section(&mut self, name: &str) -> Option<&mut Vec<String>> {
+        Some(match name {
+            SECTION_ADVERB_SUFFIXES => &mut self.adverb_suffixes,
+            SECTION_ADVERBS => &mut self.adverbs,
+            SECTION_NOT_ADVERBS => &mut self.not_adverbs,
+            SECTION_FILLERS => &mut self.fillers,
+            SECTION_PASSIVE_AUXILIARIES => &mut self.passive_auxiliaries,
+            SECTION_PARTICIPLE_SUFFIXES => &mut self.participle_suffixes,
+            SECTION_PARTICIPLES => &mut self.participles,
+            SECTION_NOT_PARTICIPLES => &mut self.not_participles,
+            SECTION_COMMON_WORDS => &mut self.common_words,
+            _ => return None,
+        })
+    }
+
+    fn is_adverb(&self, word: &str) -> bool {
+        contains(&self.adverbs, word)
+            || (has_suffix(&self.adverb_suffixes, word) && !contains(&self.not_adverbs, word))
+    }
+
+    fn is_participle(&self, word: &str) -> bool {
+        contains(&self.participles, word)
+            || (has_suffix(&self.participle_suffixes, word)
+                && !contains(&self.not_participles, word))
+    }
+}
+
+/// `en_US.UTF-8` becomes `en`
+fn language_code(language: &str) -> &str {
+    language
+        .split(['_', '-', '.', '@'])
+        .next()
+        .filter(|code| !code.is_empty())
+        .unwrap_or("en")
+}
+
+/// Built-in lists for English. Other languages get empty sections to fill in.
+fn default_text(language: &str) -> String {
+    if language_code(language) == "en" {
+        return format!("{HEADER}\n{ENGLISH}");
+    }
+    let sections: Vec<String> = SECTIONS.iter().map(|s| format!("[{s}]\n")).collect();
+    format!("{HEADER}\n{}", sections.join("\n"))
+}
+
+fn contains(list: &[String], word: &str) -> bool {
+    list.iter().any(|w| w == word)
+}
+
+/// Only words that are longer than the suffix by a few chars count
+fn has_suffix(suffixes: &[String], word: &str) -> bool {
+    suffixes.iter().any(|suffix| {
+        word.ends_with(suffix.as_str()) && word.chars().count() > suffix.chars().count() + 2
+    })
+}
+
+#[derive(Debug)]
+struct Word {
+    start: usize,
+    end: usize,
+    lower: String,
+}
+
+/// All style issues, ordered by kind and then position
+pub fn check(text: &str, words: &StyleWords) -> Vec<StyleIssue> {
+    let chars = prose_chars(text);
+    let sentences = sentences(&chars);
+    let mut issues = Vec::new();
+
+    for sentence in &sentences {
+        let fillers = fillers(sentence, words);
+        let in_filler = |i: usize| {
+            fillers
+                .iter()
+                .any(|&(start, end)| (start..end).contains(&i))
+        };
+        for (i, word) in sentence.iter().enumerate() {
+            if !in_filler(i) && words.is_adverb(&word.lower) {
+                issues.push(issue(StyleIssueKind::Adverb, word, word));
+            }
+        }
+        for (start, end) in passives(sentence, words) {
+            issues.push(issue(
+                StyleIssueKind::Passive,
+                &sentence[start],
+                &sentence[end - 1],
+            ));
+        }
+        for (start, end) in fillers {
+            issues.push(issue(
+                StyleIssueKind::Filler,
+                &sentence[start],
+                &sentence[end - 1],
+            ));
+        }
+    }
+
+    for sentence in &sentences {
+        if sentence.len() > LONG_SENTENCE_WORDS {
+            issues.push(issue(
+                StyleIssueKind::LongSentence,
+                sentence.first().unwrap(),
+                sentence.last().unwrap(),
+            ));
+        }
+    }
+
+    // Repeats are counted across sentences
+    let all: Vec<&Word> = sentences.iter().flatten().collect();
+    for (i, word) in all.iter().enumerate() {
+        if word.lower.chars().count() < REPEAT_MIN_CHARS
+            || contains(&words.common_words, &word.lower)
+        {
+            continue;
+        }
+        let window = &all[i.saturating_sub(REPEAT_WINDOW)..i];
+        if window.iter().any(|w| w.lower == word.lower) {
+            issues.push(issue(StyleIssueKind::RepeatedWord, word, word));
+        }
+    }
+
+    issues
+}
+
+fn issue(kind: StyleIssueKind, first: &Word, last: &Word) -> StyleIssue {
+    StyleIssue {
+        kind,
+        start: first.start,
+        end: last.end,
+    }
+}
+
+/// Word ranges of filler words and phrases in a sentence
+fn fillers(sentence: &[Word], words: &StyleWords) -> Vec<(usize, usize)> {
+    let mut ranges = Vec::new();
+    let mut i = 0;
+    while i < sentence.len() {
+        let matched = words
+            .fillers
+            .iter()
+            .map(|filler| filler.split(' ').collect::<Vec<_>>())
+            .filter(|parts| {
+                sentence.len() - i >= parts.len()
+                    && parts
+                        .iter()
+                        .zip(&sentence[i..])
+                        .all(|(part, word)| *part == word.lower)
+            })
+            .map(|parts| parts.len())
+            .max();
+        match matched {
+            Some(len) => {
+                ranges.push((i, i + len));
+                i += len;
+            }
+            None => i += 1,
+        }
+    }
+    ranges
+}
+
+/// Word ranges of an auxiliary followed by a participle. One adverb or "not" may come between.
+fn passives(sentence: &[Word], words: &StyleWords) -> Vec<(usize, usize)> {
+    let mut ranges = Vec::new();
+    for (i, word) in sentence.iter().enumerate() {
+        if !contains(&words.passive_auxiliaries, &word.lower) {
+            continue;
+        }
+        let mut next = i + 1;
+        if sentence
+            .get(next)
+            .is_some_and(|w| w.lower == "not" || words.is_adverb(&w.lower))
+        {
+            next += 1;
+        }
+        if sentence
+            .get(next)
+            .is_some_and(|w| words.is_participle(&w.lower))
+        {
+            ranges.push((i, next + 1));
+        }
+    }
+    ranges
+}
+
+/// Split the prose into sentences of words
+fn sentences(chars: &[char]) -> Vec<Vec<Word>> {
+    let mut sentences = Vec::new();
+    let mut sentence: Vec<Word> = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if is_word_char(c) {
+            let start = i;
+            while i < chars.len()
+                && (is_word_char(chars[i])
+                    || (is_apostrophe(chars[i])
+                        && chars.get(i + 1).is_some_and(|&c| is_word_char(c))))
+            {
+                i += 1;
+            }
+            let lower: String = chars[start..i]
+                .iter()
+                .map(|&c| if c == '’' { '\'' } else { c })
+                .collect::<String>()
+                .to_lowercase();
+            sentence.push(Word {
+                start,
+                end: i,
+                lower,
+            });
+            continue;
+        }
+
+        let next = chars.get(i + 1).copied();
+        let ends_sentence = match c {
+            '.' | '!' | '?' | '…' => next.is_none_or(char::is_whitespace),
+            // Blank line
+            '\n' => next == Some('\n'),
+            BLOCK_BREAK => true,
+            _ => false,
+        };
+        if ends_sentence && !sentence.is_empty() {
+            sentences.push(std::mem::take(&mut sentence));
+        }
+        i += 1;
+    }
+    if !sentence.is_empty() {
+        sentences.push(sentence);
+    }
+    sentences
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric()
+}
+
+fn is_apostrophe(c: char) -> bool {
+    c == '\'' || c == '’'
+}
+
+/// Stands in for a line break that also ends a sentence, like after a heading
+const BLOCK_BREAK: char = '\u{2029}';
+
+/// The text with everything but prose blanked out, one char for each char of the text.
+/// Line breaks between blocks become `BLOCK_BREAK`.
+fn prose_chars(text: &str) -> Vec<char> {
+    let mut chars = Vec::with_capacity(text.len());
+    let mut in_fence = false;
+    let mut previous_was_heading = false;
+    for line in text.split_inclusive('\n') {
+        let is_fence = regex::CODE_FENCE.is_match(line);
+        if is_fence || in_fence {
+            if is_fence {
+                in_fence = !in_fence;
+            }
+            chars.extend(line.chars().map(|c| if c == '\n' { c } else { ' ' }));
+            previous_was_heading = true;
+            continue;
+        }
+
+        let is_heading = regex::ATX_H_OPENING.is_match(line)
+            || regex::SETEXT_H_UNDERLINE.is_match(line)
+            || line.trim_start().starts_with('|');
+        let starts_block = is_heading
+            || regex::LIST_ITEM_OPENING.is_match(line)
+            || line.trim_start().starts_with('>');
+        if (starts_block || previous_was_heading)
+            && let Some(last) = chars.last_mut()
+            && *last == '\n'
+        {
+            *last = BLOCK_BREAK;
+        }
+        previous_was_heading = is_heading;
+
+        chars.extend(prose_line(line));
+    }
+    chars
+}
+
+/// Blank out code spans, link destinations and HTML tags in a line
+fn prose_line(line: &str) -> Vec<char> {
+    let mut chars: Vec<char> = line.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let blank_until = match chars[i] {
+            '`' => {
+                let run = chars[i..].iter().take_while(|&&c| c == '`').count();
+                let mut end = None;
+                let mut j = i + run;
+                while j < chars.len() {
+                    let closing = chars[j..].iter().take_while(|&&c| c == '`').count();
+                    if closing == run {
+                        end = Some(j + run);
+                        break;
+                    }
+                    j += closing.max(1);
+                }
+                // An unclosed run is just backticks
+                end.unwrap_or(i + run)
+            }
+            ']' if chars.get(i + 1) == Some(&'(') => chars[i..]
+                .iter()
+                .position(|&c| c == ')')
+                .map(|p| i + p + 1)
+                .unwrap_or(i + 1),
+            '<' if chars
+                .get(i + 1)
+                .is_some_and(|&c| c.is_ascii_alphabetic() || c == '/' || c == '!') =>
+            {
+                chars[i..]
+                    .iter()
+                    .position(|&c| c == '>')
+                    .map(|p| i + p + 1)
+                    .unwrap_or(i + 1)
+            }
+            _ => i + 1,
+        };
+        if blank_until > i + 1 {
+            for c in &mut chars[i..blank_until] {
+                if *c != '\n' {
+                    *c = ' ';
+                }
+            }
+        }
+        i = blank_until;
+    }
+    chars
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROJECT_ROOT: &str = env!("CARGO_MANIFEST_DIR");
+
+    fn english() -> StyleWords {
+        StyleWords::parse(ENGLISH)
+    }
+
+    /// The text of the issues of a kind
+    fn found(text: &str, kind: StyleIssueKind) -> Vec<String> {
+        let chars: Vec<char> = text.chars().collect();
+        check(text, &english())
+            .into_iter()
+            .filter(|issue| issue.kind == kind)
+            .map(|issue| chars[issue.start..issue.end].iter().collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        let words =
+            StyleWords::parse("# comment\n[fillers]\nJust\nkind   of\n[unknown]\nx\n[adverbs]\n");
+        assert_eq!(words.fillers, vec!["just", "kind of"]);
+        assert!(words.adverbs.is_empty());
+    }
+
+    #[test]
+    fn test_adverbs_and_fillers() {
+        let text = "She quickly ran. It was really early, kind of.";
+        assert_eq!(found(text, StyleIssueKind::Adverb), vec!["quickly"]);
+        assert_eq!(
+            found(text, StyleIssueKind::Filler),
+            vec!["really", "kind of"]
+        );
+    }
+
+    #[test]
+    fn test_passive() {
+        let text = "The cake was eaten. The letter is not written. It was often late. \
+            They were quickly sold. We are happy.";
+        assert_eq!(
+            found(text, StyleIssueKind::Passive),
+            vec!["was eaten", "is not written", "were quickly sold"]
+        );
+    }
+
+    #[test]
+    fn test_long_sentences() {
+        let long = vec!["word"; LONG_SENTENCE_WORDS + 1].join(" ");
+        let text = format!("# Heading\n{long}. Short one.\n\n{long}\n\n- item");
+        let issues = check(&text, &StyleWords::default());
+        let long_sentences = issues
+            .iter()
+            .filter(|i| i.kind == StyleIssueKind::LongSentence)
+            .count();
+        assert_eq!(long_sentences, 2);
+    }
+
+    #[test]
+    fn test_repeated_words() {
+        let text = "The garden was green. The garden was big. \
+            With that, with this.";
+        assert_eq!(found(text, StyleIssueKind::RepeatedWord), vec!["garden"]);
+    }
+
+    #[test]
+    fn test_code_is_skipped() {
+        let text = "Use `really quickly` and [text](very-much.md) <span class=\"really\">\n\
+            ```\nreally quickly\n```\n";
+        let issues = check(text, &english());
+        assert_eq!(issues, vec![]);
+    }
+
+    #[test]
+    fn test_offsets_are_chars() {
+        let text = "Ünïcödé – really";
+        assert_eq!(found(text, StyleIssueKind::Filler), vec!["really"]);
+    }
+
+    #[test]
+    fn test_load_creates_file() {
+        let folder = PathBuf::from(PROJECT_ROOT).join("test").join("style");
+        std::fs::create_dir_all(&folder).unwrap();
+        let _ = std::fs::remove_file(folder.join("en.txt"));
+        let _ = std::fs::remove_file(folder.join("xx.txt"));
+
+        let words = StyleWords::load(&folder, "en_GB.UTF-8").unwrap();
+        assert_eq!(words, english());
+        assert!(folder.join("en.txt").exists());
+
+        let words = StyleWords::load(&folder, "xx").unwrap();
+        assert_eq!(words, StyleWords::default());
+        let text = std::fs::read_to_string(folder.join("xx.txt")).unwrap();
+        assert!(text.contains("[fillers]"));
+    }
+}
@@ -0,0 +1,516 @@
+//! GFM table model. Parses table lines into cells and prints them back with padded columns.
+
+use unicode_width::UnicodeWidthStr;
+
+use crate::util::regex;
+
+/// Delimiter rows need at least 3 chars for `:-:`
+const MIN_COLUMN_WIDTH: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Alignment {
+    #[default]
+    None,
+    Left,
+    Center,
+    Right,
+}
+
+impl Alignment {
+    /// Parse action parameter
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "none" => Some(Self::None),
+            "left" => Some(Self::Left),
+            "center" => Some(Self::Center),
+            "right" => Some(Self::Right),
+            _ => None,
+        }
+    }
+
+    /// Parse a delimiter row cell, like `:--`
+    fn from_delimiter(cell: &str) -> Self {
+        match (cell.starts_with(':'), cell.ends_with(':') && cell.len() > 1) {
+            (true, true) => Self::Center,
+            (true, false) => Self::Left,
+            (false, true) => Self::Right,
+            (false, false) => Self::None,
+        }
+    }
+
+    fn delimiter(&self, width: usize) -> String {
+        match self {
+            Self::None => "-".repeat(width),
+            Self::Left => format!(":{}", "-".repeat(width - 1)),
+            Self::Center => format!(":{}:", "-".repeat(width - 2)),
+            Self::Right => format!("{}:", "-".repeat(width - 1)),
+        }
+    }
+
+    fn pad(&self, cell: &str, width: usize) -> String {
+        let space = width - display_width(cell);
+        match self {
+            Self::None | Self::Left => format!("{cell}{}", " ".repeat(space)),
+            Self::Center => format!(
+                "{}{cell}{}",
+                " ".repeat(space / 2),
+                " ".repeat(space - space / 2)
+            ),
+            Self::Right => format!("{}{cell}", " ".repeat(space)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Table {
+    indent: String,
+    /// First row is the header
+    rows: Vec<Vec<String>>,
+    alignments: Vec<Alignment>,
+}
+
+impl Table {
+    /// Empty table with a header and `num_rows` body rows
+    pub fn new(num_rows: usize, num_columns: usize) -> Self {
+        let num_columns = num_columns.max(1);
+        Self {
+            indent: String::new(),
+            rows: vec![vec![String::new(); num_columns]; num_rows + 1],
+            alignments: vec![Alignment::None; num_columns],
+        }
+    }
+
+    /// Parse table lines. The second line has to be a delimiter row.
+    pub fn parse(lines: &[&str]) -> Option<Self> {
+        if lines.len() < 2
+            || !is_table_row(lines[0])
+            || !regex::TABLE_DELIMITER_ROW.is_match(lines[1])
+        {
+            return None;
+        }
+        let indent = lines[0][..lines[0].len() - lines[0].trim_start().len()].to_owned();
+
+        let mut alignments: Vec<Alignment> = split_row(lines[1])
+            .iter()
+            .map(|cell| Alignment::from_delimiter(cell))
+            .collect();
+        let mut rows: Vec<Vec<String>> = std::iter::once(lines[0])
+            .chain(lines[2..].iter().copied())
+            .map(split_row)
+            .collect();
+
+        // Don't drop any cells, even if a row has more than the header
+        let num_columns = rows.iter().map(Vec::len).max().unwrap_or(1);
+        alignments.resize(num_columns, Alignment::None);
+        for row in rows.iter_mut() {
+            row.resize(num_columns, String::new());
+        }
+
+        Some(Self {
+            indent,
+            rows,
+            alignments,
+        })
+    }
+
+    /// Number of rows, including the header
+    pub fn num_rows(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn num_columns(&self) -> usize {
+        self.alignments.len()
+    }
+
+    pub fn insert_row(&mut self, index: usize) {
+        let index = index.clamp(1, self.rows.len());
+        self.rows
+            .insert(index, vec![String::new(); self.num_columns()]);
+    }
+
+    /// The header can't be removed. Returns false if nothing was removed.
+    pub fn remove_row(&mut self, index: usize) -> bool {
+        if index == 0 || index >= self.rows.len() {
+            return false;
+        }
+        self.rows.remove(index);
+        true
+    }
+
+    pub fn insert_column(&mut self, index: usize) {
+        let index = index.min(self.num_columns());
+        self.alignments.insert(index, Alignment::None);
+        for row in self.rows.iter_mut() {
+            row.insert(index, String::new());
+        }
+    }
+
+    /// The last column can't be removed. Returns false if nothing was removed.
+    pub fn remove_column(&mut self, index: usize) -> bool {
+        if self.num_columns() <= 1 || index >= self.num_columns() {
+            return false;
+        }
+        self.alignments.remove(index);
+        for row in self.rows.iter_mut() {
+            row.remove(index);
+        }
+        true
+    }
+
+    pub fn set_alignment(&mut self, column: usize, alignment: Alignment) {
+        if let Some(a) = self.alignments.get_mut(column) {
+            *a = alignment;
+        }
+    }
+
+    /// Print the table with padded columns. No trailing newline.
+    pub fn format(&self) -> String {
+        let widths = self.column_widths();
+        let mut lines = Vec::with_capacity(self.rows.len() + 1);
+        for (i, row) in self.rows.iter().enumerate() {
+            let cells: Vec<String> = row
+                .iter()
+                .zip(&self.alignments)
+                .zip(&widths)
+                .map(|((cell, alignment), width)| alignment.pad(cell, *width))
+                .collect();
+            lines.push(format!("{}| {} |", self.indent, cells.join(" | ")));
+            if i == 0 {
+                let delimiters: Vec<String> = self
+                    .alignments
+                    .iter()
+                    .zip(&widths)
+                    .map(|(alignment, width)| alignment.delimiter(*width))
+                    .collect();
+                lines.push(format!("{}| {} |", self.indent, delimiters.join(" | ")));
+            }
+        }
+        lines.join("\n")
+    }
+
+    /// Where a cell's contents are in the formatted table.
+    /// Returns line index and the start and end char columns.
+    pub fn cell_range(&self, row: usize, column: usize) -> (usize, usize, usize) {
+        let widths = self.column_widths();
+        let row = row.min(self.rows.len() - 1);
+        let column = column.min(self.num_columns() - 1);
+        let line = if row == 0 { 0 } else { row + 1 };
+
+        // Wide chars take two columns but are one char in the buffer
+        let cell_start = char_len(&self.indent)
+            + 2
+            + (0..column)
+                .map(|col| {
+                    char_len(&self.alignments[col].pad(&self.rows[row][col], widths[col])) + 3
+                })
+                .sum::<usize>();
+        let cell = &self.rows[row][column];
+        let padding = self.alignments[column].pad(cell, widths[column]);
+        let leading = char_len(&padding) - char_len(padding.trim_start());
+        let start = cell_start + if cell.is_empty() { 0 } else { leading };
+        (line, start, start + char_len(cell))
+    }
+
+    fn column_widths(&self) -> Vec<usize> {
+        (0..self.num_columns())
+            .map(|col| {
+                self.rows
+                    .iter()
+                    .map(|row| display_width(&row[col]))
+                    .max()
+                    .unwrap_or(0)
+                    .max(MIN_COLUMN_WIDTH)
+            })
+            .collect()
+    }
+}
+
+/// Does the line have an unescaped pipe?
+pub fn is_table_row(line: &str) -> bool {
+    let mut escaped = false;
+    for c in line.chars() {
+        if c == '|' && !escaped {
+            return true;
+        }
+        escaped = c == '\\' && !escaped;
+    }
+    false
+}
+
+/// Which cell the char column is in, and how far into the cell's trimmed contents.
+/// Table lines that lead with a pipe start from cell 0 after it.
+pub fn cell_at(line: &str, column: usize) -> (usize, usize) {
+    let leads_with_pipe = line.trim_start().starts_with('|');
+    let mut cell: usize = 0;
+    let mut content_start = None;
+    let mut escaped = false;
+    let mut in_leading_space = true;
+    for (i, c) in line.chars().enumerate() {
+        if i >= column {
+            break;
+        }
+        if c == '|' && !escaped {
+            cell += 1;
+            content_start = None;
+            in_leading_space = true;
+        } else if in_leading_space && !c.is_whitespace() {
+            content_start = Some(i);
+            in_leading_space = false;
+        }
+        escaped = c == '\\' && !escaped;
+    }
+    if leads_with_pipe {
+        cell = cell.saturating_sub(1);
+    }
+    let offset = content_start.map(|start| column - start).unwrap_or(0);
+    (cell, offset)
+}
+
+/// Whether the char column is in the spaces after a cell's contents. The spaces would be
+/// trimmed away by reformatting, although the user may be about to type the next word.
+pub fn in_trailing_space(line: &str, column: usize) -> bool {
+    let chars: Vec<char> = line.chars().collect();
+    let column = column.min(chars.len());
+    let mut cell_start = 0;
+    let mut escaped = false;
+    for (i, c) in chars[..column].iter().enumerate() {
+        if *c == '|' && !escaped {
+            cell_start = i + 1;
+        }
+        escaped = *c == '\\' && !escaped;
+    }
+    let before = &chars[cell_start..column];
+    let mut after = chars[column..].iter().take_while(|c| **c != '|');
+    before.last().is_some_and(|c| c.is_whitespace())
+        && before.iter().any(|c| !c.is_whitespace())
+        && after.all(|c| c.is_whitespace())
+}
+
+/// Split a row into trimmed cells
+fn split_row(line: &str) -> Vec<String> {
+    let mut row = line.trim();
+    row = row.strip_prefix('|').unwrap_or(row);
+    if row.ends_with('|') && !row.ends_with("\\|") {
+        row = &row[..row.len() - 1];
+    }
+
+    let mut cells = vec![];
+    let mut cell = String::new();
+    let mut escaped = false;
+    for c in row.chars() {
+        if c == '|' && !escaped {
+            cells.push(cell.trim().to_owned());
+            cell.clear();
+        } else {
+            cell.push(c);
+        }
+        escaped = c == '\\' && !escaped;
+    }
+    cells.push(cell.trim().to_owned());
+    cells
+}
+
+fn char_len(text: &str) -> usize {
+    text.chars().count()
+}
+
+/// Columns the text takes in a monospace font
+fn display_width(text: &str) -> usize {
+    text.width()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parse a table from text
+    macro_rules! table {
+        ( $t:expr ) => {{
+            let lines: Vec<&str> = $t.lines().collect();
+            Table::parse(&lines).unwrap()
+        }};
+    }
+
+    #[test]
+    fn test_new() {
+        assert_eq!(
+            Table::new(2, 3).format(),
+            "|     |     |     |\n| --- | --- | --- |\n|     |     |     |\n|     |     |     |"
+        );
+        assert_eq!(Table::new(0, 1).format(), "|     |\n| --- |");
+    }
+
+    #[test]
+    fn test_parse() {
+        let table = table!("| a | b |\n|---|:-:|\n| c | d |");
+        assert_eq!(table.num_rows(), 2);
+        assert_eq!(table.num_columns(), 2);
+        assert_eq!(table.alignments, vec![Alignment::None, Alignment::Center]);
+        assert_eq!(table.rows[1], vec!["c", "d"]);
+    }
+
+    #[test]
+    fn test_parse_not_a_table() {
+        assert!(Table::parse(&["| a | b |"]).is_none());
+        assert!(Table::parse(&["| a | b |", "| c | d |"]).is_none());
+        assert!(Table::parse(&["a b", "---"]).is_none());
+    }
+
+    #[test]
+    fn test_parse_no_outer_pipes() {
+        let table = table!("a | b\n--|--\nc | d");
+        assert_eq!(table.rows, vec![vec!["a", "b"], vec!["c", "d"]]);
+    }
+
+    #[test]
+    fn test_parse_escaped_pipe() {
+        let table = table!("| a \\| b | c |\n|---|---|");
+        assert_eq!(table.rows[0], vec!["a \\| b", "c"]);
+    }
+
+    #[test]
+    fn test_parse_uneven_rows() {
+        let table = table!("| a |\n|---|\n| b | c |\n|");
+        assert_eq!(table.num_columns(), 2);
+        assert_eq!(table.rows[0], vec!["a", ""]);
+        assert_eq!(table.rows[2], vec!["", ""]);
+    }
+
+    #[test]
+    fn test_format() {
+        let table = table!("|a|bee|\n|-|-|\n|longer cell|c|");
+        assert_eq!(
+            table.format(),
+            "| a           | bee |\n| ----------- | --- |\n| longer cell | c   |"
+        );
+    }
+
+    #[test]
+    fn test_format_alignment() {
+        let table = table!("|a|b|c|d|\n|-|:-|:-:|-:|\n|aaaaa|bbbbb|ccccc|ddddd|");
+        assert_eq!(
+            table.format(),
+            "| a     | b     |   c   |     d |\n\
+             | ----- | :---- | :---: | ----: |\n\
+             | aaaaa | bbbbb | ccccc | ddddd |"
+        );
+    }
+
+    #[test]
+    fn test_format_wide_chars() {
+        let table = table!("|名前|x|\n|-|-|\n|a|🎉|");
+        assert_eq!(
+            table.format(),
+            "| 名前 | x   |\n| ---- | --- |\n| a    | 🎉  |"
+        );
+    }
+
+    #[test]
+    fn test_format_keeps_indent() {
+        let table = table!("  |a|\n  |-|");
+        assert_eq!(table.format(), "  | a   |\n  | --- |");
+    }
+
+    #[test]
+    fn test_format_roundtrip() {
+        let text = "| a   |   b |\n| :-- | --: |\n| c   |   d |";
+        assert_eq!(table!(text).format(), text);
+    }
+
+    #[test]
+    fn test_rows() {
+        let mut table = table!("|a|\n|-|\n|b|");
+        table.insert_row(0);
+        assert_eq!(table.format(), "| a   |\n| --- |\n|     |\n| b   |");
+        table.insert_row(99);
+        assert_eq!(table.num_rows(), 4);
+        assert!(!table.remove_row(0));
+        assert!(!table.remove_row(4));
+        assert!(table.remove_row(1));
+        assert_eq!(table.format(), "| a   |\n| --- |\n| b   |\n|     |");
+    }
+
+    #[test]
+    fn test_columns() {
+        let mut table = table!("|a|b|\n|-|-:|");
+        table.insert_column(1);
+        assert_eq!(table.format(), "| a   |     |   b |\n| --- | --- | --: |");
+        assert!(table.remove_column(0));
+        assert!(table.remove_column(1));
+        assert!(!table.remove_column(0));
+        assert_eq!(table.format(), "|     |\n| --- |");
+    }
+
+    #[test]
+    fn test_set_alignment() {
+        let mut table = table!("|a|\n|-|");
+        table.set_alignment(0, Alignment::Right);
+        assert_eq!(table.format(), "|   a |\n| --: |");
+        table.set_alignment(1, Alignment::Left);
+        assert_eq!(table.format(), "|   a |\n| --: |");
+    }
+
+    #[test]
+    fn test_cell_range() {
+        let table = table!("| a | bb |\n|---|---:|\n| ccccc | d |");
+        // | a     |  bb |
+        // | ----- | --: |
+        // | ccccc |   d |
+        assert_eq!(table.cell_range(0, 0), (0, 2, 3));
+        assert_eq!(table.cell_range(0, 1), (0, 11, 13));
+        assert_eq!(table.cell_range(1, 0), (2, 2, 7));
+        assert_eq!(table.cell_range(1, 1), (2, 12, 13));
+    }
+
+    #[test]
+    fn test_cell_range_wide_chars() {
+        let table = table!("|名前|x|\n|-|-|\n|a|b|");
+        // | 名前 | x   |
+        // | ---- | --- |
+        // | a    | b   |
+        assert_eq!(table.cell_range(0, 0), (0, 2, 4));
+        assert_eq!(table.cell_range(0, 1), (0, 7, 8));
+        assert_eq!(table.cell_range(1, 1), (2, 9, 10));
+    }
+
+    #[test]
+    fn test_cell_range_empty() {
+        let table = Table::new(1, 2);
+        assert_eq!(table.cell_range(0, 0), (0, 2, 2));
+        assert_eq!(table.cell_range(1, 1), (2, 8, 8));
+    }
+
+    #[test]
+    fn test_cell_at() {
+        let line = "| a   | bcd |";
+        assert_eq!(cell_at(line, 0), (0, 0));
+        assert_eq!(cell_at(line, 2), (0, 0));
+        assert_eq!(cell_at(line, 3), (0, 1));
+        assert_eq!(cell_at(line, 8), (1, 0));
+        assert_eq!(cell_at(line, 10), (1, 2));
+        assert_eq!(cell_at("a | b", 0), (0, 0));
+        assert_eq!(cell_at("a | b", 5), (1, 1));
+        assert_eq!(cell_at("| a \\| b | c", 8), (0, 6));
+    }
+
+    #[test]
+    fn test_in_trailing_space() {
+        let line = "| hello  | b |";
+        assert!(in_trailing_space(line, 8));
+        assert!(in_trailing_space(line, 9));
+        assert!(!in_trailing_space(line, 7));
+        assert!(!in_trailing_space(line, 1));
+        assert!(!in_trailing_space(line, 12));
+        assert!(!in_trailing_space("| a b |", 4));
+        assert!(in_trailing_space("| a ", 4));
+        assert!(in_trailing_space("| a \\| ", 7));
+    }
+
+    #[test]
+    fn test_is_table_row() {
+        assert!(is_table_row("| a |"));
+        assert!(is_table_row("a | b"));
+        assert!(!is_table_row("a \\| b"));
+        assert!(!is_table_row("a b"));
+    }
+}
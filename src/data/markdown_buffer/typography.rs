@@ -0,0 +1,236 @@
+//! Smart punctuation while typing: curly quotes, dashes and ellipses.
+
+use crate::util::regex;
+
+/// Opening and closing quote marks of a language
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quotes {
+    pub double: (char, char),
+    pub single: (char, char),
+}
+
+impl Quotes {
+    /// Quotes for a locale name like `de_DE.UTF-8`. Unknown languages get English quotes.
+    pub fn for_locale(locale: &str) -> Self {
+        let language = locale
+            .split(['_', '-', '.', '@'])
+            .next()
+            .unwrap_or_default();
+        let (double, single) = match language {
+            "de" | "cs" | "sk" | "sl" | "is" | "lt" => (('„', '“'), ('‚', '‘')),
+            "pl" | "ro" | "hu" | "hr" | "bg" => (('„', '”'), ('‚', '’')),
+            "fr" | "es" | "it" | "pt" | "ca" | "el" | "no" | "nb" | "nn" => {
+                (('«', '»'), ('“', '”'))
+            }
+            "ru" | "uk" | "be" => (('«', '»'), ('„', '“')),
+            "sv" | "fi" => (('”', '”'), ('’', '’')),
+            "da" => (('»', '«'), ('›', '‹')),
+            "ja" | "zh" => (('「', '」'), ('『', '』')),
+            _ => (('“', '”'), ('‘', '’')),
+        };
+        Self { double, single }
+    }
+}
+
+/// Delete `delete` chars before the cursor, then insert `text` instead of the typed text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replacement {
+    pub delete: usize,
+    pub text: String,
+}
+
+/// The replacement for typed text, if it should be replaced.
+/// `line_before` and `line_after` are the text of the line around the cursor.
+/// The caller checks for fenced code blocks, this only looks at the line.
+pub fn replacement(
+    line_before: &str,
+    line_after: &str,
+    typed: &str,
+    quotes: &Quotes,
+) -> Option<Replacement> {
+    if !matches!(typed, "\"" | "'" | "-" | ".")
+        || regex::CODE_FENCE.is_match(line_before)
+        || in_code_span(line_before)
+    {
+        return None;
+    }
+
+    match typed {
+        "-" => dash(line_before, line_after),
+        "." if line_before.ends_with("..") => Some(Replacement {
+            delete: 2,
+            text: "…".into(),
+        }),
+        "\"" | "'" if !in_markup(line_before) => {
+            let (open, close) = if typed == "\"" {
+                quotes.double
+            } else {
+                quotes.single
+            };
+            let previous = line_before
+                .trim_end_matches(['*', '_', '~', '='])
+                .chars()
+                .last();
+            let text = match previous {
+                // An apostrophe, like in "don't"
+                Some(c) if typed == "'" && c.is_alphanumeric() => '’',
+                None => open,
+                Some(c) if c.is_whitespace() || "([{<–—-/".contains(c) => open,
+                Some(c) if c == quotes.double.0 || c == quotes.single.0 => open,
+                _ => close,
+            };
+            Some(Replacement {
+                delete: 0,
+                text: text.into(),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// `--` becomes an en dash, `---` an em dash. Thematic breaks, setext headings, table delimiter
+/// rows and HTML comments keep their hyphens.
+fn dash(line_before: &str, line_after: &str) -> Option<Replacement> {
+    let line = format!("{line_before}-{line_after}");
+    // Setext underlines also cover `--` on the way to a thematic break
+    if regex::THEMATIC_BREAK.is_match(&line)
+        || regex::SETEXT_H_UNDERLINE.is_match(&line)
+        || regex::TABLE_DELIMITER_ROW.is_match(&line)
+        || line_before.contains("<!-")
+    {
+        return None;
+    }
+    if let Some((_, last_cell)) = line_before.rsplit_once('|')
+        && last_cell
+            .chars()
+            .all(|c| matches!(c, '-' | ':' | ' ' | '\t'))
+    {
+        return None;
+    }
+
+    let text = match line_before.chars().last()? {
+        '-' => "–",
+        '–' => "—",
+        _ => return None,
+    };
+    Some(Replacement {
+        delete: 1,
+        text: text.into(),
+    })
+}
+
+/// True if the end of the line is inside a code span. A span is closed by a backtick run of the
+/// same length as the one that opened it.
+fn in_code_span(line_before: &str) -> bool {
+    let mut open_run = None;
+    let mut chars = line_before.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\\' && open_run.is_none() {
+            chars.next();
+            continue;
+        }
+        if c != '`' {
+            continue;
+        }
+        let mut run = 1;
+        while chars.next_if_eq(&'`').is_some() {
+            run += 1;
+        }
+        open_run = match open_run {
+            None => Some(run),
+            Some(open) if open == run => None,
+            open => open,
+        };
+    }
+    open_run.is_some()
+}
+
+/// True inside link destinations and HTML tags, where quotes are syntax
+fn in_markup(line_before: &str) -> bool {
+    let in_destination = line_before
+        .rfind("](")
+        .is_some_and(|i| !line_before[i..].contains(')'));
+    // A `<` in prose is only a tag if a name follows
+    let in_tag = line_before.rfind('<').is_some_and(|i| {
+        let tag = &line_before[i + 1..];
+        tag.starts_with(|c: char| c.is_ascii_alphabetic() || c == '/') && !tag.contains('>')
+    });
+    in_destination || in_tag
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ENGLISH: Quotes = Quotes {
+        double: ('“', '”'),
+        single: ('‘', '’'),
+    };
+
+    /// Type the text char by char and apply the replacements
+    fn typed(text: &str) -> String {
+        let mut result = String::new();
+        for c in text.chars() {
+            let line_start = result.rfind('\n').map(|i| i + 1).unwrap_or(0);
+            let typed = c.to_string();
+            match replacement(&result[line_start..], "", &typed, &ENGLISH) {
+                Some(r) => {
+                    for _ in 0..r.delete {
+                        result.pop();
+                    }
+                    result.push_str(&r.text);
+                }
+                None => result.push(c),
+            }
+        }
+        result
+    }
+
+    #[test]
+    fn test_quotes() {
+        assert_eq!(
+            typed(r#""Hello," she said. 'It's (") *"a"*'"#),
+            "“Hello,” she said. ‘It’s (“) *“a”*’"
+        );
+    }
+
+    #[test]
+    fn test_quotes_for_locale() {
+        assert_eq!(Quotes::for_locale("de_DE.UTF-8").double, ('„', '“'));
+        assert_eq!(Quotes::for_locale("fr").double, ('«', '»'));
+        assert_eq!(Quotes::for_locale("C"), ENGLISH);
+    }
+
+    #[test]
+    fn test_dashes_and_ellipsis() {
+        assert_eq!(typed("1--2 and a---b, wait..."), "1–2 and a—b, wait…");
+    }
+
+    #[test]
+    fn test_thematic_breaks_and_tables() {
+        assert_eq!(typed("---"), "---");
+        assert_eq!(typed("  ------"), "  ------");
+        assert_eq!(typed("- item"), "- item");
+        assert_eq!(
+            typed("| a | b |\n| --- | :--: |"),
+            "| a | b |\n| --- | :--: |"
+        );
+        assert_eq!(typed("<!-- note -->"), "<!-- note -->");
+    }
+
+    #[test]
+    fn test_code() {
+        assert_eq!(typed("`a--b \"c\"` \"d\""), "`a--b \"c\"` “d”");
+        assert_eq!(typed("``a ` \"b\"``"), "``a ` \"b\"``");
+        assert_eq!(typed("```sh \"a\""), "```sh \"a\"");
+    }
+
+    #[test]
+    fn test_markup() {
+        assert_eq!(
+            typed(r#"[a](b.md "title") <a href="x">"#),
+            r#"[a](b.md "title") <a href="x">"#
+        );
+        assert_eq!(typed(r#"1 < "2""#), "1 < “2”");
+    }
+}
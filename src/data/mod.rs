@@ -1,21 +1,61 @@
 mod document;
 mod document_stats;
+mod focus_scope;
 mod folder;
+mod journal;
+mod keybindings;
 mod markdown_buffer;
 mod project;
 mod sort;
+mod sprint;
+mod template;
 
 use std::path::PathBuf;
 use std::time::SystemTime;
 
 pub use document::Document;
 pub use document_stats::DocumentStats;
+pub use focus_scope::FocusScope;
 pub use folder::Folder;
 pub use folder::FolderType;
+pub use journal::JournalDate;
+pub use journal::note_dates as journal_note_dates;
+pub use keybindings::KeybindingMode;
+pub use markdown_buffer::BrokenLink;
+pub use markdown_buffer::FOLD_MARK_CATEGORY;
+pub use markdown_buffer::FOLDED_MARK_CATEGORY;
+pub use markdown_buffer::FormatOptions;
+pub use markdown_buffer::LINT_MARK_CATEGORY;
+pub use markdown_buffer::LinkEdit;
+pub use markdown_buffer::LinkProblem;
+pub use markdown_buffer::LintConfig;
+pub use markdown_buffer::LintDiagnostic;
+pub use markdown_buffer::LintRule;
 pub use markdown_buffer::MarkdownBuffer;
+pub use markdown_buffer::MarkdownLink;
+pub use markdown_buffer::Math;
+pub use markdown_buffer::MovedItem;
+pub use markdown_buffer::Section;
+pub use markdown_buffer::StyleIssueKind;
+pub use markdown_buffer::StyleSummary;
+pub use markdown_buffer::StyleWords;
+pub use markdown_buffer::TableAlignment;
+pub use markdown_buffer::apply_link_edits;
+pub use markdown_buffer::broken_links;
+pub use markdown_buffer::link_edits;
+pub use markdown_buffer::math_at;
+pub use markdown_buffer::section_at;
+pub use markdown_buffer::style_issue_color;
 pub use project::Project;
 pub use sort::ProjectSorter;
 pub use sort::SortMethod;
+pub use sprint::SprintRecord;
+pub use sprint::append_sprint_history;
+pub use sprint::load_sprint_history;
+pub use template::Template;
+pub use template::TemplateValues;
+pub use template::fill as fill_template;
+pub use template::project_name;
 
 use gtk::glib::CollationKey;
 
@@ -0,0 +1,124 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+
+use crate::error::ScratchmarkError;
+
+/// A finished writing sprint, as kept in the sprint history
+#[derive(Debug, Clone, PartialEq)]
+pub struct SprintRecord {
+    /// Start time as seconds since the Unix epoch
+    pub started: i64,
+    /// Planned length in seconds
+    pub duration: u32,
+    /// Actual length in seconds. Shorter than planned if stopped early.
+    pub elapsed: u32,
+    /// Change in the word count. Negative if more was deleted than written.
+    pub words: i32,
+    /// Zero if there was no target
+    pub word_target: u32,
+}
+
+impl SprintRecord {
+    pub fn words_per_minute(&self) -> f64 {
+        if self.elapsed == 0 {
+            return 0.;
+        }
+        self.words as f64 * 60. / self.elapsed as f64
+    }
+
+    pub fn target_reached(&self) -> bool {
+        self.word_target > 0 && self.words >= self.word_target as i32
+    }
+
+    /// One line of the history file, tab separated
+    fn to_line(&self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}\t{}",
+            self.started, self.duration, self.elapsed, self.words, self.word_target
+        )
+    }
+
+    fn from_line(line: &str) -> Option<Self> {
+        let mut fields = line.split('\t');
+        let record = Self {
+            started: fields.next()?.parse().ok()?,
+            duration: fields.next()?.parse().ok()?,
+            elapsed: fields.next()?.parse().ok()?,
+            words: fields.next()?.parse().ok()?,
+            word_target: fields.next()?.parse().ok()?,
+        };
+        fields.next().is_none().then_some(record)
+    }
+}
+
+/// All sprints in the history file, oldest first. Lines that can't be read are skipped.
+pub fn load_sprint_history(path: &Path) -> Vec<SprintRecord> {
+    let Ok(text) = std::fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    text.lines().filter_map(SprintRecord::from_line).collect()
+}
+
+pub fn append_sprint_history(path: &Path, record: &SprintRecord) -> Result<(), ScratchmarkError> {
+    let result = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| writeln!(file, "{}", record.to_line()));
+    if let Err(e) = result {
+        println!("{e}");
+        return Err(ScratchmarkError::FileWriteFail);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    const PROJECT_ROOT: &str = env!("CARGO_MANIFEST_DIR");
+
+    fn record(words: i32, elapsed: u32) -> SprintRecord {
+        SprintRecord {
+            started: 1_700_000_000,
+            duration: 1500,
+            elapsed,
+            words,
+            word_target: 500,
+        }
+    }
+
+    #[test]
+    fn test_words_per_minute() {
+        assert_eq!(record(600, 1200).words_per_minute(), 30.);
+        assert_eq!(record(600, 0).words_per_minute(), 0.);
+        assert!(record(500, 1500).target_reached());
+        assert!(!record(499, 1500).target_reached());
+    }
+
+    #[test]
+    fn test_line_roundtrip() {
+        let record = record(-12, 1500);
+        assert_eq!(SprintRecord::from_line(&record.to_line()), Some(record));
+        assert_eq!(SprintRecord::from_line("1\t2\t3"), None);
+        assert_eq!(SprintRecord::from_line("1\t2\t3\t4\tx"), None);
+    }
+
+    #[test]
+    fn test_history_file() {
+        let dir = PathBuf::from(PROJECT_ROOT).join("test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("sprint-history.tsv");
+        let _ = std::fs::remove_file(&path);
+
+        append_sprint_history(&path, &record(100, 600)).unwrap();
+        append_sprint_history(&path, &record(200, 900)).unwrap();
+        assert_eq!(
+            load_sprint_history(&path),
+            vec![record(100, 600), record(200, 900)]
+        );
+    }
+}
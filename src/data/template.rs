@@ -0,0 +1,159 @@
+use std::path::Path;
+use std::path::PathBuf;
+
+use gettextrs::gettext;
+use gtk::glib;
+
+use crate::error::ScratchmarkError;
+use crate::util::file_actions;
+
+/// Project templates are kept in this folder inside the project
+pub const PROJECT_TEMPLATES_FOLDER: &str = ".templates";
+
+/// Skeleton for new documents. Any markdown file in a templates folder is a template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+/// Values for the `{{title}}`, `{{date}}` and `{{project}}` placeholders
+#[derive(Debug, Clone)]
+pub struct TemplateValues<'a> {
+    pub title: &'a str,
+    pub date: &'a str,
+    pub project: &'a str,
+}
+
+impl Template {
+    /// Templates of the project and of the user, sorted by name.
+    /// A project template hides a user template with the same name.
+    pub fn available(project_path: &Path) -> Vec<Template> {
+        templates_in(&[
+            project_path.join(PROJECT_TEMPLATES_FOLDER),
+            file_actions::path_user_templates(),
+        ])
+    }
+
+    /// The template as written, placeholders and all
+    pub fn read(&self) -> Result<String, ScratchmarkError> {
+        std::fs::read_to_string(&self.path).map_err(|e| {
+            println!("{e}");
+            ScratchmarkError::FileOpenFail
+        })
+    }
+
+    /// Contents of a new document at `document_path`
+    pub fn expand(
+        &self,
+        document_path: &Path,
+        project_name: &str,
+    ) -> Result<String, ScratchmarkError> {
+        let text = self.read()?;
+        let title = document_path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy())
+            .unwrap_or_default();
+        let date = glib::DateTime::now_local()
+            .and_then(|now| now.format("%Y-%m-%d"))
+            .map(|date| date.to_string())
+            .unwrap_or_default();
+        Ok(fill(
+            &text,
+            &TemplateValues {
+                title: &title,
+                date: &date,
+                project: project_name,
+            },
+        ))
+    }
+}
+
+/// Name for the `{{project}}` placeholder
+pub fn project_name(project_path: &Path) -> String {
+    if project_path == file_actions::path_builtin_library() {
+        gettext("Drafts")
+    } else {
+        project_path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+}
+
+/// Templates in the folders. Earlier folders win when names clash.
+fn templates_in(folders: &[PathBuf]) -> Vec<Template> {
+    let mut templates: Vec<Template> = Vec::new();
+    for folder in folders {
+        let Ok(entries) = folder.read_dir() else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if !path.is_file() || path.extension().is_none_or(|ext| ext != "md") {
+                continue;
+            }
+            let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+            if !templates.iter().any(|t| t.name == name) {
+                templates.push(Template { name, path });
+            }
+        }
+    }
+    templates.sort_by_key(|t| t.name.to_lowercase());
+    templates
+}
+
+/// Replace the placeholders. Unknown placeholders are left as they are.
+pub fn fill(text: &str, values: &TemplateValues) -> String {
+    text.replace("{{title}}", values.title)
+        .replace("{{date}}", values.date)
+        .replace("{{project}}", values.project)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROJECT_ROOT: &str = env!("CARGO_MANIFEST_DIR");
+
+    #[test]
+    fn test_fill() {
+        let values = TemplateValues {
+            title: "Standup",
+            date: "2024-05-01",
+            project: "Work",
+        };
+        assert_eq!(
+            fill("# {{title}}\n\n{{date}} · {{project}} · {{other}}", &values),
+            "# Standup\n\n2024-05-01 · Work · {{other}}"
+        );
+    }
+
+    #[test]
+    fn test_project_templates_win() {
+        let root = PathBuf::from(PROJECT_ROOT).join("test").join("templates");
+        let project = root.join("project");
+        let user = root.join("user");
+        std::fs::create_dir_all(&project).unwrap();
+        std::fs::create_dir_all(&user).unwrap();
+        std::fs::write(project.join("Meeting.md"), "project").unwrap();
+        std::fs::write(user.join("Meeting.md"), "user").unwrap();
+        std::fs::write(user.join("blog post.md"), "").unwrap();
+        std::fs::write(user.join("notes.txt"), "").unwrap();
+
+        let templates = templates_in(&[project.clone(), user.clone()]);
+        assert_eq!(
+            templates,
+            vec![
+                Template {
+                    name: "blog post".into(),
+                    path: user.join("blog post.md"),
+                },
+                Template {
+                    name: "Meeting".into(),
+                    path: project.join("Meeting.md"),
+                },
+            ]
+        );
+    }
+}
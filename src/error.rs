@@ -6,9 +6,11 @@ use gettextrs::gettext;
 pub enum ScratchmarkError {
     FileCreateFail,
     FileOpenFail,
+    FileWriteFail,
     FolderCreateFail,
     ItemMoveFail,
     InvalidChars,
+    Unencodable,
     FileChanged,
     InvalidPath,
     IsRootDir,
@@ -24,9 +26,15 @@ impl std::fmt::Display for ScratchmarkError {
         match self {
             FileCreateFail => write!(f, "{}", gettext("Couldn't create file")),
             FileOpenFail => write!(f, "{}", gettext("Couldn't access file")),
+            FileWriteFail => write!(f, "{}", gettext("Couldn't write file")),
             FolderCreateFail => write!(f, "{}", gettext("Couldn't create folder")),
             ItemMoveFail => write!(f, "{}", gettext("Couldn't move item")),
             InvalidChars => write!(f, "{}", gettext("File contains invalid characters")),
+            Unencodable => write!(
+                f,
+                "{}",
+                gettext("Some characters can't be saved in the file's encoding")
+            ),
             FileChanged => write!(f, "{}", gettext("File has changed on disk")),
             InvalidPath => write!(f, "{}", gettext("Invalid path")),
             IsRootDir => write!(f, "This action can't be done to a project root folder"),
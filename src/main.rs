@@ -1,3 +1,4 @@
+mod accels;
 mod config;
 mod data;
 mod error;
@@ -5,6 +6,8 @@ mod settings;
 mod util;
 mod widgets;
 
+use std::path::PathBuf;
+
 use gtk::glib;
 use gtk::glib::GString;
 use gtk::prelude::*;
@@ -13,6 +16,7 @@ use sourceview5::StyleSchemeManager;
 
 use config::PKGDATADIR;
 use util::file_actions;
+use util::language_spec;
 use widgets::Window;
 
 const APP_ID: &str = "org.scratchmark.Scratchmark";
@@ -42,7 +46,7 @@ fn main() -> glib::ExitCode {
     }
 
     let app = adw::Application::builder().application_id(APP_ID).build();
-    setup_accels(&app);
+    accels::apply(&app, &gtk::gio::Settings::new(APP_ID));
 
     app.connect_activate(|app| {
         if let Some(window) = app.windows().first() {
@@ -72,45 +76,14 @@ fn main() -> glib::ExitCode {
     app.run()
 }
 
-fn setup_accels(app: &adw::Application) {
-    app.set_accels_for_action("win.file-new", &["<Ctrl>N"]);
-    app.set_accels_for_action("win.folder-new", &["<Shift><Ctrl>N"]);
-    app.set_accels_for_action("win.project-add", &["<Ctrl><Shift>O"]);
-    app.set_accels_for_action("win.file-save", &["<Ctrl>S"]);
-    app.set_accels_for_action("win.file-rename-selected", &["F2"]);
-    app.set_accels_for_action("win.file-close", &["<Ctrl>W"]);
-    app.set_accels_for_action("editor.format-bold", &["<Ctrl>B"]);
-    app.set_accels_for_action("editor.format-italic", &["<Ctrl>I"]);
-    app.set_accels_for_action("editor.format-link", &["<Ctrl>K"]);
-    app.set_accels_for_action("editor.format-h1", &["<Ctrl>1"]);
-    app.set_accels_for_action("editor.format-h2", &["<Ctrl>2"]);
-    app.set_accels_for_action("editor.format-h3", &["<Ctrl>3"]);
-    app.set_accels_for_action("editor.format-h4", &["<Ctrl>4"]);
-    app.set_accels_for_action("editor.format-h5", &["<Ctrl>5"]);
-    app.set_accels_for_action("editor.format-h6", &["<Ctrl>6"]);
-    app.set_accels_for_action("editor.zoom-in", &["<Ctrl>plus"]);
-    app.set_accels_for_action("editor.zoom-out", &["<Ctrl>minus"]);
-    app.set_accels_for_action("editor.zoom-reset", &["<Ctrl>0"]);
-    app.set_accels_for_action("editor.show-search", &["<Ctrl>F"]);
-    app.set_accels_for_action("editor.show-search-replace", &["<Ctrl>R"]);
-    app.set_accels_for_action("editor.hide-search", &["Escape"]);
-    app.set_accels_for_action("editor.shiftreturn", &["<Shift>Return"]);
-    app.set_accels_for_action("win.library-refresh", &["F5"]);
-
-    app.set_accels_for_action("win.toggle-sidebar", &["F9"]);
-    app.set_accels_for_action("win.fullscreen", &["F11"]);
-    app.set_accels_for_action("win.focus-mode", &["F8"]);
-    app.set_accels_for_action("win.typewriter-mode", &["<Control><Alt>T"]);
-    app.set_accels_for_action("win.show-help-overlay", &["<Control>question"]);
-    app.set_accels_for_action("win.preferences", &["<ctrl>comma"]);
-
-    app.set_accels_for_action("win.eat-inspector", &["<ctrl><Shift>I"]);
-    app.set_accels_for_action("win.eat-adaptive-preview", &["<ctrl><Shift>M"]);
-}
-
 fn setup_buffer_styles() {
     StyleSchemeManager::default().append_search_path(&format!("{PKGDATADIR}/editor_schemes"));
     StyleSchemeManager::default().append_search_path(&format!("{PKGDATADIR}/document_preview"));
+    StyleSchemeManager::default().prepend_search_path(
+        file_actions::path_user_style_schemes()
+            .to_string_lossy()
+            .as_ref(),
+    );
 
     #[cfg(not(feature = "installed"))]
     {
@@ -136,6 +109,38 @@ fn setup_language_manager() {
         search_path.insert(0, lang_spec_dir.into());
     }
 
+    // Fenced code can use the installed code languages, but the markdown spec has to list them.
+    // A separate manager finds them, because the default one can't change paths after loading.
+    let languages = LanguageManager::new();
+    let dirs: Vec<&str> = search_path.iter().map(GString::as_str).collect();
+    languages.set_search_path(&dirs);
+    let spec_path = PathBuf::from(search_path[0].as_str()).join("markdown.lang");
+    match std::fs::read_to_string(&spec_path) {
+        Ok(spec) => {
+            let spec = language_spec::embed_code_languages(
+                &spec,
+                &language_spec::code_languages(&languages),
+            );
+            let generated_dir = file_actions::path_generated_language_specs();
+            match std::fs::write(generated_dir.join("markdown.lang"), spec) {
+                Ok(()) => {
+                    // The bundled spec is used if the generated one is broken
+                    let generated_dir = generated_dir.to_string_lossy();
+                    let dirs: Vec<&str> = std::iter::once(generated_dir.as_ref())
+                        .chain(search_path.iter().map(GString::as_str))
+                        .collect();
+                    if language_spec::markdown_spec_loads(&dirs) {
+                        search_path.insert(0, generated_dir.as_ref().into());
+                    } else {
+                        println!("Couldn't load generated markdown language spec");
+                    }
+                }
+                Err(e) => println!("Couldn't write markdown language spec: {e}"),
+            }
+        }
+        Err(e) => println!("Couldn't read markdown language spec: {e}"),
+    }
+
     let dirs: Vec<&str> = search_path.iter().map(GString::as_str).collect();
     lm.set_search_path(&dirs);
 }
@@ -5,6 +5,8 @@ use gtk::prelude::*;
 pub const EDITOR_FONT_SIZES: [u32; 14] = [6, 8, 9, 10, 11, 12, 13, 14, 16, 20, 24, 36, 48, 72];
 pub const EDITOR_WIDTH_LIMIT_MAX: u32 = 6000;
 pub const EDITOR_WIDTH_LIMIT_MIN: u32 = 500;
+pub const WRAP_COLUMN_MAX: u32 = 200;
+pub const WRAP_COLUMN_MIN: u32 = 20;
 
 /// Resets crazy values that could break the app
 pub fn sanity_filter(settings: &Settings) {
@@ -21,4 +23,10 @@ pub fn sanity_filter(settings: &Settings) {
     {
         settings.reset("editor-max-width");
     }
+
+    if settings.uint("editor-wrap-column") > WRAP_COLUMN_MAX
+        || settings.uint("editor-wrap-column") < WRAP_COLUMN_MIN
+    {
+        settings.reset("editor-wrap-column");
+    }
 }
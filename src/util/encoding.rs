@@ -0,0 +1,203 @@
+//! Documents that aren't UTF-8 are opened in the encoding they're detected as, and saved back
+//! in it until they're converted. Only UTF-16 has a byte order mark to go by, the other
+//! encodings are told apart by which of their byte sequences the file is valid in.
+
+use std::fmt::Display;
+
+/// Encoding of a document file
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TextEncoding {
+    #[default]
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Latin1,
+    Windows1252,
+    ShiftJis,
+}
+
+impl TextEncoding {
+    /// Name of the encoding for converting with iconv
+    pub fn charset(&self) -> &'static str {
+        match self {
+            TextEncoding::Utf8 => "UTF-8",
+            TextEncoding::Utf16Le => "UTF-16LE",
+            TextEncoding::Utf16Be => "UTF-16BE",
+            TextEncoding::Latin1 => "ISO-8859-1",
+            TextEncoding::Windows1252 => "WINDOWS-1252",
+            // Microsoft's Shift-JIS, with the NEC and IBM characters that plain Shift-JIS lacks
+            TextEncoding::ShiftJis => "CP932",
+        }
+    }
+
+    /// Byte order mark that files in the encoding start with
+    pub fn bom(&self) -> &'static [u8] {
+        match self {
+            TextEncoding::Utf16Le => &[0xFF, 0xFE],
+            TextEncoding::Utf16Be => &[0xFE, 0xFF],
+            _ => &[],
+        }
+    }
+}
+
+impl Display for TextEncoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TextEncoding::Utf8 => write!(f, "UTF-8"),
+            TextEncoding::Utf16Le => write!(f, "UTF-16 LE"),
+            TextEncoding::Utf16Be => write!(f, "UTF-16 BE"),
+            TextEncoding::Latin1 => write!(f, "Latin-1"),
+            TextEncoding::Windows1252 => write!(f, "Windows-1252"),
+            TextEncoding::ShiftJis => write!(f, "Shift-JIS"),
+        }
+    }
+}
+
+/// The encoding that the file contents are most likely in.
+/// None if they look like binary data, which every single byte encoding would accept.
+pub fn detect_encoding(bytes: &[u8]) -> Option<TextEncoding> {
+    if bytes.starts_with(TextEncoding::Utf16Le.bom()) {
+        return Some(TextEncoding::Utf16Le);
+    }
+    if bytes.starts_with(TextEncoding::Utf16Be.bom()) {
+        return Some(TextEncoding::Utf16Be);
+    }
+    if std::str::from_utf8(bytes).is_ok() {
+        return Some(TextEncoding::Utf8);
+    }
+    if bytes.iter().any(|byte| is_binary(*byte)) {
+        return None;
+    }
+    if is_shift_jis(bytes) {
+        return Some(TextEncoding::ShiftJis);
+    }
+    // Latin-1 only has control characters there, Windows-1252 has quotes and dashes
+    if bytes.iter().any(|byte| (0x80..=0x9F).contains(byte)) {
+        if bytes
+            .iter()
+            .all(|byte| !WINDOWS_1252_UNDEFINED.contains(byte))
+        {
+            return Some(TextEncoding::Windows1252);
+        }
+        return None;
+    }
+    Some(TextEncoding::Latin1)
+}
+
+/// Bytes that Windows-1252 has no characters for
+const WINDOWS_1252_UNDEFINED: [u8; 5] = [0x81, 0x8D, 0x8F, 0x90, 0x9D];
+
+/// Control characters that don't appear in text
+fn is_binary(byte: u8) -> bool {
+    (byte < 0x20 && !b"\t\n\r\x0C".contains(&byte)) || byte == 0x7F
+}
+
+/// Whether the bytes are valid Shift-JIS that looks Japanese: with kana or Japanese punctuation,
+/// or with a double byte character next to another non-ASCII one. Single accented letters and
+/// quotes of the other encodings can pass for kanji, but not for kana, and Japanese words are
+/// rarely a single kanji.
+fn is_shift_jis(bytes: &[u8]) -> bool {
+    let mut japanese = false;
+    let mut previous_non_ascii = false;
+    let mut previous_double_byte = false;
+    let mut i = 0;
+    while i < bytes.len() {
+        let double_byte = match bytes[i] {
+            0x00..=0x7F => {
+                previous_non_ascii = false;
+                previous_double_byte = false;
+                i += 1;
+                continue;
+            }
+            // Half width katakana
+            0xA1..=0xDF => false,
+            lead @ (0x81..=0x9F | 0xE0..=0xFC) => {
+                let Some(trail) = bytes.get(i + 1) else {
+                    return false;
+                };
+                if !matches!(trail, 0x40..=0x7E | 0x80..=0xFC) {
+                    return false;
+                }
+                // Punctuation, hiragana and katakana
+                japanese |= (0x81..=0x83).contains(&lead);
+                true
+            }
+            _ => return false,
+        };
+        japanese |= previous_double_byte || (double_byte && previous_non_ascii);
+        previous_non_ascii = true;
+        previous_double_byte = double_byte;
+        i += if double_byte { 2 } else { 1 };
+    }
+    japanese
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_utf() {
+        assert_eq!(detect_encoding(b"plain"), Some(TextEncoding::Utf8));
+        assert_eq!(detect_encoding("café".as_bytes()), Some(TextEncoding::Utf8));
+        assert_eq!(detect_encoding(b""), Some(TextEncoding::Utf8));
+        assert_eq!(
+            detect_encoding(b"\xFF\xFEh\x00i\x00"),
+            Some(TextEncoding::Utf16Le)
+        );
+        assert_eq!(
+            detect_encoding(b"\xFE\xFF\x00h\x00i"),
+            Some(TextEncoding::Utf16Be)
+        );
+    }
+
+    #[test]
+    fn test_detect_single_byte() {
+        assert_eq!(
+            detect_encoding(b"caf\xE9 au lait"),
+            Some(TextEncoding::Latin1)
+        );
+        assert_eq!(
+            detect_encoding(b"\x93quoted\x94 \x96 text"),
+            Some(TextEncoding::Windows1252)
+        );
+        assert_eq!(detect_encoding(b"na\xEFve\r\n"), Some(TextEncoding::Latin1));
+        assert_eq!(detect_encoding(b"Gr\xFC\xDFe"), Some(TextEncoding::Latin1));
+        assert_eq!(
+            detect_encoding(b"it\x92s a test"),
+            Some(TextEncoding::Windows1252)
+        );
+    }
+
+    #[test]
+    fn test_detect_shift_jis() {
+        // "日本語のメモ" and a half width katakana
+        let text = b"\x93\xFA\x96\x7B\x8C\xEA\x82\xCC\x83\x81\x83\x82 \xB1";
+        assert_eq!(detect_encoding(text), Some(TextEncoding::ShiftJis));
+        assert_eq!(
+            detect_encoding(b"# \x83\x81\x83\x82\n"),
+            Some(TextEncoding::ShiftJis)
+        );
+        // Short notes with a single kana or kanji pair
+        assert_eq!(
+            detect_encoding(b"# \x82\xCD\n"),
+            Some(TextEncoding::ShiftJis)
+        );
+        assert_eq!(detect_encoding(b"OK\x81\x42"), Some(TextEncoding::ShiftJis));
+        assert_eq!(
+            detect_encoding(b"\x93\x8C\x8B\x9E"),
+            Some(TextEncoding::ShiftJis)
+        );
+        // A single pair that would be a kanji is more likely an accented letter
+        assert_eq!(detect_encoding(b"gar\xE7on"), Some(TextEncoding::Latin1));
+        // A lead byte at the end isn't Shift-JIS
+        assert_eq!(detect_encoding(b"caf\xE9"), Some(TextEncoding::Latin1));
+    }
+
+    #[test]
+    fn test_detect_binary() {
+        assert_eq!(detect_encoding(b"\x89PNG\r\n\x1A\n\x00\x00"), None);
+        assert_eq!(detect_encoding(b"text\x00\xE9"), None);
+        assert_eq!(detect_encoding(b"\x81\x20"), None);
+    }
+}
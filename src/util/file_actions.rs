@@ -1,16 +1,25 @@
 use std::fs::OpenOptions;
 use std::io::Write;
+use std::path::Component;
 use std::path::Path;
 use std::path::PathBuf;
 
 use gtk::gio::Cancellable;
 use gtk::gio::File;
 use gtk::gio::prelude::*;
+use gtk::glib;
 use gtk::glib::GString;
+use gtk::glib::user_cache_dir;
 use gtk::glib::user_data_dir;
 
 use crate::APP_ID;
 use crate::error::ScratchmarkError;
+use crate::util::encoding;
+use crate::util::encoding::TextEncoding;
+
+/// Files with these extensions are linked as images
+pub const IMAGE_EXTENSIONS: [&str; 8] =
+    ["apng", "avif", "gif", "jpeg", "jpg", "png", "svg", "webp"];
 
 #[derive(Debug)]
 pub enum FilenameState {
@@ -69,6 +78,45 @@ pub fn path_builtin_library() -> PathBuf {
     }
 }
 
+/// User templates directory inside userdata. Created if it doesn't exist.
+pub fn path_user_templates() -> PathBuf {
+    created_dir(path_userdata().join("templates"))
+}
+
+/// Word lists of the style check inside userdata. Created if it doesn't exist.
+pub fn path_style_words() -> PathBuf {
+    created_dir(path_userdata().join("style"))
+}
+
+/// Imported editor color schemes inside userdata. Created if it doesn't exist.
+pub fn path_user_style_schemes() -> PathBuf {
+    created_dir(path_userdata().join("styles"))
+}
+
+/// Color schemes are checked here before they're imported. Created if it doesn't exist.
+pub fn path_style_scheme_import() -> PathBuf {
+    created_dir(user_cache_dir().join(APP_ID).join("style_import"))
+}
+
+/// History of writing sprints inside userdata
+pub fn path_sprint_history() -> PathBuf {
+    path_userdata().join("sprint-history.tsv")
+}
+
+/// Generated language specs. Created if it doesn't exist.
+pub fn path_generated_language_specs() -> PathBuf {
+    created_dir(user_cache_dir().join(APP_ID).join("language_specs"))
+}
+
+/// The folder, after creating it if it doesn't exist.
+/// Failing is only printed, using the folder will fail later.
+fn created_dir(path: PathBuf) -> PathBuf {
+    if let Err(e) = std::fs::create_dir_all(&path) {
+        println!("{e}");
+    }
+    path
+}
+
 /// Create if doesn't exist
 pub fn create_builtin_library() {
     let path = path_builtin_library();
@@ -113,6 +161,11 @@ pub fn create_folder(path: &Path) -> Result<(), ScratchmarkError> {
 }
 
 pub fn create_document(path: &Path) -> Result<(), ScratchmarkError> {
+    let stem = path.file_stem().unwrap().to_string_lossy();
+    create_document_with_contents(path, &format!("# {stem}\n\n"))
+}
+
+pub fn create_document_with_contents(path: &Path, contents: &str) -> Result<(), ScratchmarkError> {
     let mut file = match OpenOptions::new().write(true).create_new(true).open(path) {
         Ok(f) => f,
         Err(e) => {
@@ -120,8 +173,6 @@ pub fn create_document(path: &Path) -> Result<(), ScratchmarkError> {
             return Err(ScratchmarkError::FileCreateFail);
         }
     };
-    let stem = path.file_stem().unwrap().to_string_lossy();
-    let contents = format!("# {stem}\n\n");
     if let Err(e) = file.write_all(contents.as_bytes()) {
         println!("{e}");
         return Err(ScratchmarkError::FileCreateFail);
@@ -129,16 +180,40 @@ pub fn create_document(path: &Path) -> Result<(), ScratchmarkError> {
     Ok(())
 }
 
-pub fn read_file_to_string(file: &File) -> Result<GString, ScratchmarkError> {
+/// Contents of a text file and the encoding they were detected as
+pub fn read_text_file(file: &File) -> Result<(GString, TextEncoding), ScratchmarkError> {
     let slice = match FileExtManual::load_contents(file, None::<&Cancellable>) {
         Ok((slice, _)) => slice,
         Err(_) => return Err(ScratchmarkError::FileOpenFail),
     };
-    let text = match GString::from_utf8_checked(slice.to_vec()) {
+    let Some(encoding) = encoding::detect_encoding(&slice) else {
+        return Err(ScratchmarkError::InvalidChars);
+    };
+    let bytes = if encoding == TextEncoding::Utf8 {
+        slice.to_vec()
+    } else {
+        match glib::convert(&slice[encoding.bom().len()..], "UTF-8", encoding.charset()) {
+            Ok((converted, _)) => converted.to_vec(),
+            Err(_) => return Err(ScratchmarkError::InvalidChars),
+        }
+    };
+    let text = match GString::from_utf8_checked(bytes) {
         Ok(text) => text,
         Err(_) => return Err(ScratchmarkError::InvalidChars),
     };
-    Ok(text)
+    Ok((text, encoding))
+}
+
+/// Bytes of the text in the encoding, starting with its byte order mark
+pub fn encode_text(text: &str, encoding: TextEncoding) -> Result<Vec<u8>, ScratchmarkError> {
+    if encoding == TextEncoding::Utf8 {
+        return Ok(text.as_bytes().to_vec());
+    }
+    let converted = match glib::convert(text.as_bytes(), encoding.charset(), "UTF-8") {
+        Ok((converted, _)) => converted,
+        Err(_) => return Err(ScratchmarkError::Unencodable),
+    };
+    Ok([encoding.bom(), &converted[..]].concat())
 }
 
 pub fn move_folder(original_path: &Path, new_path: &Path) -> Result<(), ScratchmarkError> {
@@ -156,6 +231,98 @@ pub fn move_folder(original_path: &Path, new_path: &Path) -> Result<(), Scratchm
     Ok(())
 }
 
+/// Path of `target` relative to the `base` folder. Both should be absolute.
+pub fn relative_path(base: &Path, target: &Path) -> PathBuf {
+    let base: Vec<_> = base.components().collect();
+    let target: Vec<_> = target.components().collect();
+    let common = base.iter().zip(&target).take_while(|(a, b)| a == b).count();
+
+    let mut path = PathBuf::new();
+    for _ in common..base.len() {
+        path.push("..");
+    }
+    for component in &target[common..] {
+        path.push(component);
+    }
+    path
+}
+
+/// Resolve `.` and `..` without touching the file system, so it works for moved paths too
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+pub fn is_image_file(path: &Path) -> bool {
+    path.extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .is_some_and(|ext| IMAGE_EXTENSIONS.contains(&ext.as_str()))
+}
+
+/// Path as a markdown link destination. Wrapped in angle brackets if it has spaces or parentheses.
+pub fn markdown_link_destination(path: &Path) -> String {
+    let destination = path.to_string_lossy();
+    if destination.contains([' ', '(', ')']) {
+        format!("<{destination}>")
+    } else {
+        destination.into_owned()
+    }
+}
+
+/// The file a local link destination points to, resolved against the document's folder.
+/// Anchors are dropped. None for links within the document itself.
+pub fn link_target_path(document_dir: &Path, destination: &str) -> Option<PathBuf> {
+    let file = destination.split(['#', '?']).next().unwrap_or_default();
+    if file.is_empty() {
+        return None;
+    }
+    Some(document_dir.join(percent_decode(file)))
+}
+
+/// Decode `%20` and the like in a link destination. Invalid escapes are kept as they are.
+pub fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && let Some(hex) = text.get(i + 1..i + 3)
+            && hex.bytes().all(|b| b.is_ascii_hexdigit())
+            && let Ok(byte) = u8::from_str_radix(hex, 16)
+        {
+            decoded.push(byte);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Escape the chars that would end or break a link destination, for links that use `%20`
+/// instead of angle brackets
+pub fn percent_encode(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c.is_ascii_control() || " %<>()#?".contains(c) {
+            encoded.push_str(&format!("%{:02X}", c as u8));
+        } else {
+            encoded.push(c);
+        }
+    }
+    encoded
+}
+
 fn copy_folder_recurse(original_path: &Path, new_path: &Path) -> Result<(), std::io::Error> {
     std::fs::create_dir_all(new_path)?;
     for entry in std::fs::read_dir(original_path)? {
@@ -236,4 +403,125 @@ mod tests {
         assert_eq!(result_file, expected_file);
         assert_eq!(result_folder, expected_folder);
     }
+
+    #[test]
+    fn test_encoding_round_trip() {
+        let dir = test_root().join("encodings");
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let texts = [
+            (TextEncoding::Utf8, "# Café ☕\n\nText\n"),
+            (TextEncoding::Utf16Le, "# Café ☕\n\nText\n"),
+            (TextEncoding::Utf16Be, "# Café ☕\n\nText\n"),
+            (TextEncoding::Latin1, "# Café\n\nGrüße\n"),
+            (TextEncoding::Windows1252, "# “Café” – naïve\n"),
+            (TextEncoding::ShiftJis, "# 日本語のメモ\n\n①と髙\n"),
+        ];
+        for (encoding, text) in texts {
+            let bytes = encode_text(text, encoding).unwrap();
+            assert!(bytes.starts_with(encoding.bom()));
+            let path = dir.join(format!("{encoding}.md"));
+            std::fs::write(&path, &bytes).unwrap();
+            let (read, detected) = read_text_file(&File::for_path(&path)).unwrap();
+            assert_eq!(detected, encoding);
+            assert_eq!(read.as_str(), text);
+        }
+
+        assert_eq!(
+            encode_text("☕", TextEncoding::Latin1),
+            Err(ScratchmarkError::Unencodable)
+        );
+    }
+
+    #[test]
+    fn test_relative_path() {
+        let base = Path::new("/library/project/notes");
+        assert_eq!(
+            relative_path(base, Path::new("/library/project/notes/img.png")),
+            PathBuf::from("img.png")
+        );
+        assert_eq!(
+            relative_path(base, Path::new("/library/project/notes/assets/img.png")),
+            PathBuf::from("assets/img.png")
+        );
+        assert_eq!(
+            relative_path(base, Path::new("/library/project/other/doc.md")),
+            PathBuf::from("../other/doc.md")
+        );
+        assert_eq!(
+            relative_path(base, Path::new("/elsewhere/doc.md")),
+            PathBuf::from("../../../elsewhere/doc.md")
+        );
+    }
+
+    #[test]
+    fn test_normalize_path() {
+        assert_eq!(
+            normalize_path(Path::new("/home/user/notes/../other/./doc.md")),
+            PathBuf::from("/home/user/other/doc.md")
+        );
+        assert_eq!(
+            normalize_path(Path::new("/home/user/notes")),
+            PathBuf::from("/home/user/notes")
+        );
+    }
+
+    #[test]
+    fn test_link_target_path() {
+        let dir = Path::new("/home/user/notes");
+        assert_eq!(
+            link_target_path(dir, "img/fig.png"),
+            Some(PathBuf::from("/home/user/notes/img/fig.png"))
+        );
+        assert_eq!(
+            link_target_path(dir, "../other.md#intro"),
+            Some(PathBuf::from("/home/user/notes/../other.md"))
+        );
+        assert_eq!(
+            link_target_path(dir, "my%20note.md#intro"),
+            Some(PathBuf::from("/home/user/notes/my note.md"))
+        );
+        assert_eq!(link_target_path(dir, "#intro"), None);
+        assert_eq!(link_target_path(dir, ""), None);
+    }
+
+    #[test]
+    fn test_percent_decode() {
+        assert_eq!(percent_decode("my%20note.md"), "my note.md");
+        assert_eq!(percent_decode("%C3%A4.md"), "ä.md");
+        assert_eq!(percent_decode("100%.md"), "100%.md");
+        assert_eq!(percent_decode("%zz%2"), "%zz%2");
+        assert_eq!(percent_decode("%+1"), "%+1");
+        assert_eq!(percent_decode("ä%20b"), "ä b");
+    }
+
+    #[test]
+    fn test_percent_encode() {
+        assert_eq!(
+            percent_encode("sub/my note (1).md"),
+            "sub/my%20note%20%281%29.md"
+        );
+        assert_eq!(percent_encode("ä/100%.md"), "ä/100%25.md");
+        assert_eq!(percent_decode(&percent_encode("a b#c")), "a b#c");
+    }
+
+    #[test]
+    fn test_is_image_file() {
+        assert!(is_image_file(Path::new("assets/img.png")));
+        assert!(is_image_file(Path::new("Photo.JPG")));
+        assert!(!is_image_file(Path::new("notes.md")));
+        assert!(!is_image_file(Path::new("png")));
+    }
+
+    #[test]
+    fn test_markdown_link_destination() {
+        assert_eq!(
+            markdown_link_destination(Path::new("assets/img.png")),
+            "assets/img.png"
+        );
+        assert_eq!(
+            markdown_link_destination(Path::new("../my notes/doc (2).md")),
+            "<../my notes/doc (2).md>"
+        );
+    }
 }
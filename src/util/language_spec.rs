@@ -0,0 +1,349 @@
+//! Fenced code blocks are highlighted with the language named after the fence.
+//! Sourceview can't pick an embedded language at runtime, so the markdown spec is
+//! generated with a context for every language the language manager knows.
+
+use sourceview5::LanguageManager;
+
+/// Where the generated contexts go in `markdown.lang`
+const DEFINITIONS_MARKER: &str = "<!-- fenced-code-definitions -->";
+/// Where the references to the generated contexts go in `markdown.lang`
+const REFERENCES_MARKER: &str = "<!-- fenced-code-references -->";
+
+/// Closing fence, as long as the opening one
+const FENCE_END: &str = r"^ {0,3}\%{1@start}[ \t]*$";
+
+/// Names after the fence that pick common languages, besides their ids and names.
+/// These are claimed first, so every name belongs to one language, like `h` to C.
+const EMBEDDED_LANGUAGES: &[(&str, &[&str])] = &[
+    ("c", &["c", "h"]),
+    ("c-sharp", &["csharp", "cs", "c#"]),
+    ("cpp", &["cpp", "c++", "cxx", "cc", "hpp"]),
+    ("css", &["css"]),
+    ("dart", &["dart"]),
+    ("diff", &["diff", "patch"]),
+    ("dockerfile", &["dockerfile", "docker"]),
+    ("go", &["go", "golang"]),
+    ("haskell", &["haskell", "hs"]),
+    ("html", &["html", "htm"]),
+    ("ini", &["ini"]),
+    ("java", &["java"]),
+    ("js", &["javascript", "js", "mjs"]),
+    ("json", &["json"]),
+    ("julia", &["julia", "jl"]),
+    ("kotlin", &["kotlin", "kt"]),
+    ("latex", &["latex", "tex"]),
+    ("lua", &["lua"]),
+    ("makefile", &["makefile", "make"]),
+    ("meson", &["meson"]),
+    ("objc", &["objc", "objective-c"]),
+    ("perl", &["perl", "pl"]),
+    ("php", &["php"]),
+    ("python3", &["python", "py", "python3"]),
+    ("r", &["r"]),
+    ("ruby", &["ruby", "rb"]),
+    ("rust", &["rust", "rs"]),
+    ("scss", &["scss"]),
+    ("sh", &["sh", "bash", "shell", "zsh"]),
+    ("sql", &["sql"]),
+    ("swift", &["swift"]),
+    ("toml", &["toml"]),
+    ("typescript", &["typescript", "ts"]),
+    ("vala", &["vala"]),
+    ("xml", &["xml", "svg"]),
+    ("yaml", &["yaml", "yml"]),
+];
+
+/// A language that fenced code can be highlighted with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeLanguage {
+    /// Sourceview language id
+    pub id: String,
+    /// Names after the opening fence that pick this language
+    pub aliases: Vec<String>,
+}
+
+impl CodeLanguage {
+    /// Id of the markdown context of the language's code blocks
+    fn context_id(&self) -> String {
+        let id: String = self
+            .id
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+            .collect();
+        format!("code-block-{id}")
+    }
+}
+
+/// Languages that fenced code can use, named by their id, name and the extra names above
+pub fn code_languages(manager: &LanguageManager) -> Vec<CodeLanguage> {
+    let mut languages: Vec<(String, String)> = manager
+        .language_ids()
+        .iter()
+        .filter_map(|id| manager.language(id))
+        .filter(|language| !language.is_hidden() && language.id().as_str() != "markdown")
+        .map(|language| (language.id().to_string(), language.name().to_string()))
+        .collect();
+    languages.sort();
+    code_languages_of(&languages)
+}
+
+/// Languages with their aliases, from their extra names, ids and names.
+/// A name that another language already has is left out.
+fn code_languages_of(languages: &[(String, String)]) -> Vec<CodeLanguage> {
+    let extra_aliases = |id: &str| -> &[&str] {
+        EMBEDDED_LANGUAGES
+            .iter()
+            .find(|(embedded, _)| *embedded == id)
+            .map_or(&[], |(_, aliases)| aliases)
+    };
+    let mut taken: Vec<String> = languages
+        .iter()
+        .flat_map(|(id, _)| extra_aliases(id))
+        .map(|alias| (*alias).to_owned())
+        .collect();
+
+    let mut result = Vec::new();
+    for (id, name) in languages {
+        let mut aliases: Vec<String> = extra_aliases(id)
+            .iter()
+            .map(|alias| (*alias).to_owned())
+            .collect();
+        for alias in [id.to_lowercase(), name.to_lowercase()] {
+            if is_alias(&alias) && !taken.contains(&alias) {
+                taken.push(alias.clone());
+                aliases.push(alias);
+            }
+        }
+        if !aliases.is_empty() {
+            result.push(CodeLanguage {
+                id: id.clone(),
+                aliases,
+            });
+        }
+    }
+    result
+}
+
+/// Names with spaces or glob patterns can't follow a fence
+fn is_alias(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "+#_-".contains(c))
+}
+
+/// Whether the markdown spec that comes first on the search path can be loaded.
+/// Sourceview only parses the contexts when styles are asked for, and finds none if it fails.
+pub fn markdown_spec_loads(search_path: &[&str]) -> bool {
+    let manager = LanguageManager::new();
+    manager.set_search_path(search_path);
+    manager
+        .language("markdown")
+        .is_some_and(|language| !language.style_ids().is_empty())
+}
+
+/// Opening fence followed by one of the aliases, like `` ```rust `` or `~~~ {.rs}`
+pub fn fence_start(aliases: &[String]) -> String {
+    let aliases: Vec<String> = aliases.iter().map(|alias| regex::escape(alias)).collect();
+    format!(
+        r"^ {{0,3}}(\`{{3,}}|~{{3,}})[ \t]*\{{?\.?(?i:{})([ \t,}}][^\n]*)?$",
+        aliases.join("|")
+    )
+}
+
+/// Add a context for the code blocks of each language to the markdown spec
+pub fn embed_code_languages(spec: &str, languages: &[CodeLanguage]) -> String {
+    let mut definitions = String::new();
+    let mut references = String::new();
+    for language in languages {
+        let context_id = language.context_id();
+        definitions.push_str(&format!(
+            r#"<context id="{context_id}" class="no-spell-check">
+      <start>{start}</start>
+      <end>{FENCE_END}</end>
+      <include>
+        <context sub-pattern="0" where="start" style-ref="code" />
+        <context sub-pattern="0" where="end" style-ref="code" />
+        <context ref="{id}:{id}" />
+      </include>
+    </context>
+    "#,
+            start = fence_start(&language.aliases),
+            id = language.id,
+        ));
+        references.push_str(&format!("<context ref=\"{context_id}\" />\n        "));
+    }
+    spec.replacen(DEFINITIONS_MARKER, &definitions, 1)
+        .replacen(REFERENCES_MARKER, &references, 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use regex::Regex;
+
+    use super::*;
+
+    const SPEC: &str = include_str!("../../data/language_specs/markdown.lang");
+
+    fn rust() -> CodeLanguage {
+        CodeLanguage {
+            id: "rust".into(),
+            aliases: vec!["rust".into(), "rs".into()],
+        }
+    }
+
+    fn spec_match<'a>(spec: &'a str, context_id: &str, tag: &str) -> &'a str {
+        let context = spec
+            .split(&format!("<context id=\"{context_id}\""))
+            .nth(1)
+            .unwrap();
+        let (_, rest) = context.split_once(&format!("<{tag}>")).unwrap();
+        let (pattern, _) = rest.split_once(&format!("</{tag}>")).unwrap();
+        pattern
+    }
+
+    #[test]
+    fn test_spec_has_markers() {
+        assert!(SPEC.contains(DEFINITIONS_MARKER));
+        assert!(SPEC.contains(REFERENCES_MARKER));
+        // The generic block is the fallback, so it has to come after the generated ones
+        let references = SPEC.find(REFERENCES_MARKER).unwrap();
+        assert!(SPEC.find(r#"<context ref="code-block" />"#).unwrap() > references);
+    }
+
+    #[test]
+    fn test_spec_code_block() {
+        let start = Regex::new(spec_match(SPEC, "code-block", "start")).unwrap();
+        assert!(start.is_match("```"));
+        assert!(start.is_match("~~~~ python"));
+        assert!(start.is_match("   ```rust"));
+        assert!(!start.is_match("    ```rust"));
+        assert!(!start.is_match("``"));
+        assert_eq!(spec_match(SPEC, "code-block", "end"), FENCE_END);
+    }
+
+    #[test]
+    fn test_fence_start() {
+        let start = Regex::new(&fence_start(&rust().aliases)).unwrap();
+        assert!(start.is_match("```rust"));
+        assert!(start.is_match("```rs"));
+        assert!(start.is_match("```Rust"));
+        assert!(start.is_match("~~~ rust"));
+        assert!(start.is_match("````rust"));
+        assert!(start.is_match("```rust,ignore"));
+        assert!(start.is_match("```rust title=\"main.rs\""));
+        assert!(start.is_match("``` {.rust}"));
+        assert!(start.is_match("  ```rust  "));
+        assert!(!start.is_match("```rusty"));
+        assert!(!start.is_match("```r"));
+        assert!(!start.is_match("```"));
+        assert!(!start.is_match("    ```rust"));
+        assert!(!start.is_match("text ```rust"));
+    }
+
+    #[test]
+    fn test_fence_start_escapes_aliases() {
+        let start = Regex::new(&fence_start(&["c++".into(), "c#".into()])).unwrap();
+        assert!(start.is_match("```c++"));
+        assert!(start.is_match("```c#"));
+        assert!(!start.is_match("```c"));
+        assert!(!start.is_match("```cc"));
+    }
+
+    #[test]
+    fn test_embedded_languages() {
+        let mut seen = Vec::new();
+        for (id, aliases) in EMBEDDED_LANGUAGES {
+            assert!(!aliases.is_empty(), "{id}");
+            for alias in *aliases {
+                assert!(is_alias(alias), "{alias}");
+                assert_eq!(alias.to_lowercase(), *alias);
+                assert!(!seen.contains(alias), "{alias} is used twice");
+                seen.push(alias);
+            }
+        }
+    }
+
+    fn language(id: &str, name: &str) -> (String, String) {
+        (id.to_owned(), name.to_owned())
+    }
+
+    #[test]
+    fn test_code_languages() {
+        let languages = [
+            language("c", "C"),
+            language("cpp", "C++"),
+            language("h", "H"),
+            language("nix", "Nix"),
+            language("python3", "Python 3"),
+            language("python", "Python"),
+            language("vb", "Visual Basic"),
+            language("x", "[x]"),
+        ];
+        let aliases: Vec<(String, Vec<String>)> = code_languages_of(&languages)
+            .into_iter()
+            .map(|language| (language.id, language.aliases))
+            .collect();
+        let expected: &[(&str, &[&str])] = &[
+            ("c", &["c", "h"]),
+            ("cpp", &["cpp", "c++", "cxx", "cc", "hpp"]),
+            ("nix", &["nix"]),
+            ("python3", &["python", "py", "python3"]),
+            ("vb", &["vb"]),
+            ("x", &["x"]),
+        ];
+        assert_eq!(
+            aliases,
+            expected
+                .iter()
+                .map(|(id, aliases)| {
+                    let aliases = aliases.iter().map(|alias| (*alias).to_owned()).collect();
+                    ((*id).to_owned(), aliases)
+                })
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_is_alias() {
+        assert!(is_alias("rust"));
+        assert!(is_alias("objective-c"));
+        assert!(is_alias("c++"));
+        assert!(!is_alias(""));
+        assert!(!is_alias("visual basic"));
+        assert!(!is_alias("[ch]"));
+    }
+
+    #[test]
+    fn test_embed_code_languages() {
+        let python = CodeLanguage {
+            id: "python3".into(),
+            aliases: vec!["python3".into(), "py".into()],
+        };
+        let spec = embed_code_languages(SPEC, &[rust(), python]);
+        assert!(!spec.contains(DEFINITIONS_MARKER));
+        assert!(!spec.contains(REFERENCES_MARKER));
+        assert!(spec.contains(r#"<context ref="rust:rust" />"#));
+        assert!(spec.contains(r#"<context ref="python3:python3" />"#));
+        assert!(spec.contains(r#"<context ref="code-block-rust" />"#));
+        assert!(spec.contains(r#"<context ref="code-block-python3" />"#));
+
+        let start = Regex::new(spec_match(&spec, "code-block-python3", "start")).unwrap();
+        assert!(start.is_match("```py"));
+        assert!(!start.is_match("```rust"));
+        assert_eq!(spec_match(&spec, "code-block-rust", "end"), FENCE_END);
+
+        // Languages are tried before the generic block
+        let generated = spec.find(r#"<context ref="code-block-rust" />"#).unwrap();
+        assert!(spec.find(r#"<context ref="code-block" />"#).unwrap() > generated);
+    }
+
+    #[test]
+    fn test_context_id() {
+        let language = CodeLanguage {
+            id: "c.sharp".into(),
+            aliases: vec!["cs".into()],
+        };
+        assert_eq!(language.context_id(), "code-block-c-sharp");
+    }
+}
@@ -0,0 +1,678 @@
+//! Math previews are laid out by Typst, with its bundled math font.
+//! LaTeX formulas are written as Typst math first, so the layout engine gets to do fractions,
+//! limits, roots and matrices.
+
+use std::iter::Peekable;
+use std::str::Chars;
+use std::sync::LazyLock;
+
+use comemo::Prehashed;
+use typst::Library;
+use typst::World;
+use typst::diag::FileError;
+use typst::diag::FileResult;
+use typst::eval::Tracer;
+use typst::foundations::Bytes;
+use typst::foundations::Datetime;
+use typst::syntax::FileId;
+use typst::syntax::Source;
+use typst::text::Font;
+use typst::text::FontBook;
+use typst::visualize::Color;
+
+/// Pixels per point of the preview at scale 1
+const PIXELS_PER_PT: f32 = 1.5;
+
+static LIBRARY: LazyLock<Prehashed<Library>> = LazyLock::new(|| Prehashed::new(Library::default()));
+
+static FONTS: LazyLock<(Prehashed<FontBook>, Vec<Font>)> = LazyLock::new(|| {
+    let fonts: Vec<Font> = typst_assets::fonts()
+        .flat_map(|data| Font::iter(Bytes::from_static(data)))
+        .collect();
+    (Prehashed::new(FontBook::from_fonts(&fonts)), fonts)
+});
+
+/// A rendered formula
+pub struct MathImage {
+    pub width: u32,
+    pub height: u32,
+    /// Premultiplied RGBA
+    pub pixels: Vec<u8>,
+}
+
+/// Lay out and draw a LaTeX formula in the color, for the scale factor of the screen. `None` if Typst couldn't compile what the formula was converted to.
+pub fn render_math(tex: &str, display: bool, color: [u8; 3], scale: f32) -> Option<MathImage> {
+    let math = typst_math(tex, |name| LIBRARY.math.scope().get(name).is_some());
+    let [r, g, b] = color;
+    // Spaces inside the dollars make it a display formula
+    let math = if display {
+        format!("$ {math} $")
+    } else {
+        format!("${math}$")
+    };
+    let world = MathWorld {
+        source: Source::detached(format!(
+            "#set page(width: auto, height: auto, margin: 2pt, fill: none)\n\
+             #set text(size: 12pt, fill: rgb({r}, {g}, {b}))\n\
+             {math}"
+        )),
+    };
+    let mut tracer = Tracer::new();
+    let document = typst::compile(&world, &mut tracer);
+    // Memoized layouts of old formulas aren't needed anymore
+    comemo::evict(10);
+    let document = document.ok()?;
+    let page = document.pages.first()?;
+    let pixmap = typst_render::render(
+        &page.frame,
+        PIXELS_PER_PT * scale,
+        Color::from_u8(0, 0, 0, 0),
+    );
+    Some(MathImage {
+        width: pixmap.width(),
+        height: pixmap.height(),
+        pixels: pixmap.take(),
+    })
+}
+
+/// The one file that is compiled, with the fonts that come with Typst
+struct MathWorld {
+    source: Source,
+}
+
+impl World for MathWorld {
+    fn library(&self) -> &Prehashed<Library> {
+        &LIBRARY
+    }
+
+    fn book(&self) -> &Prehashed<FontBook> {
+        &FONTS.0
+    }
+
+    fn main(&self) -> Source {
+        self.source.clone()
+    }
+
+    fn source(&self, id: FileId) -> FileResult<Source> {
+        if id == self.source.id() {
+            Ok(self.source.clone())
+        } else {
+            Err(FileError::AccessDenied)
+        }
+    }
+
+    fn file(&self, _id: FileId) -> FileResult<Bytes> {
+        Err(FileError::AccessDenied)
+    }
+
+    fn font(&self, index: usize) -> Option<Font> {
+        FONTS.1.get(index).cloned()
+    }
+
+    fn today(&self, _offset: Option<i64>) -> Option<Datetime> {
+        None
+    }
+}
+
+/// Typst name of a LaTeX command, where they differ
+fn symbol(name: &str) -> Option<&'static str> {
+    let symbol = match name {
+        "epsilon" => "epsilon.alt",
+        "varepsilon" => "epsilon",
+        "vartheta" => "theta.alt",
+        "varkappa" => "kappa.alt",
+        "varpi" => "pi.alt",
+        "varrho" => "rho.alt",
+        "varsigma" => "sigma.alt",
+        "phi" => "phi.alt",
+        "varphi" => "phi",
+        "infty" => "infinity",
+        "partial" => "diff",
+        "nexists" => "exists.not",
+        "emptyset" | "varnothing" => "nothing",
+        "hbar" => "planck.reduce",
+        "aleph" => "alef",
+        "neg" | "lnot" => "not",
+        "ldots" | "dots" => "dots.h",
+        "cdots" => "dots.c",
+        "vdots" => "dots.v",
+        "ddots" => "dots.down",
+        "langle" => "angle.l",
+        "rangle" => "angle.r",
+        "lfloor" => "floor.l",
+        "rfloor" => "floor.r",
+        "lceil" => "ceil.l",
+        "rceil" => "ceil.r",
+        "lbrace" => "brace.l",
+        "rbrace" => "brace.r",
+        "vert" | "lvert" | "rvert" => "bar.v",
+        "Vert" | "lVert" | "rVert" | "|" => "bar.v.double",
+        "cdot" => "dot.op",
+        "pm" => "plus.minus",
+        "mp" => "minus.plus",
+        "circ" => "circle.small",
+        "cup" => "union",
+        "cap" => "sect",
+        "bigcup" => "union.big",
+        "bigcap" => "sect.big",
+        "setminus" => "without",
+        "wedge" | "land" => "and",
+        "vee" | "lor" => "or",
+        "oplus" => "plus.circle",
+        "otimes" => "times.circle",
+        "bigoplus" => "plus.circle.big",
+        "bigotimes" => "times.circle.big",
+        "le" | "leq" => "lt.eq",
+        "ge" | "geq" => "gt.eq",
+        "ne" | "neq" => "eq.not",
+        "leqslant" => "lt.eq.slant",
+        "geqslant" => "gt.eq.slant",
+        "ll" => "lt.double",
+        "gg" => "gt.double",
+        "sim" => "tilde.op",
+        "simeq" => "tilde.eq",
+        "cong" => "tilde.equiv",
+        "propto" => "prop",
+        "notin" => "in.not",
+        "ni" => "in.rev",
+        "subseteq" => "subset.eq",
+        "supseteq" => "supset.eq",
+        "mid" => "divides",
+        "nmid" => "divides.not",
+        "vdash" => "tack.r",
+        "to" | "rightarrow" => "arrow.r",
+        "leftarrow" | "gets" => "arrow.l",
+        "leftrightarrow" => "arrow.l.r",
+        "Rightarrow" => "arrow.r.double",
+        "Leftarrow" => "arrow.l.double",
+        "Leftrightarrow" | "iff" => "arrow.l.r.double",
+        "longrightarrow" => "arrow.r.long",
+        "longleftarrow" => "arrow.l.long",
+        "implies" => "arrow.r.double.long",
+        "impliedby" => "arrow.l.double.long",
+        "mapsto" => "arrow.r.bar",
+        "uparrow" => "arrow.t",
+        "downarrow" => "arrow.b",
+        "int" => "integral",
+        "iint" => "integral.double",
+        "iiint" => "integral.triple",
+        "oint" => "integral.cont",
+        "prod" => "product",
+        "coprod" => "product.co",
+        _ => return None,
+    };
+    Some(symbol)
+}
+
+/// Typst function of a LaTeX command that takes one argument
+fn function(name: &str) -> Option<&'static str> {
+    let function = match name {
+        "mathbf" | "boldsymbol" | "bm" | "textbf" => "bold",
+        "mathit" | "textit" => "italic",
+        "mathrm" => "upright",
+        "mathbb" => "bb",
+        "mathcal" => "cal",
+        "mathfrak" => "frak",
+        "mathsf" => "sans",
+        "mathtt" => "mono",
+        "hat" | "widehat" => "hat",
+        "tilde" | "widetilde" => "tilde",
+        "bar" => "macron",
+        "vec" => "arrow",
+        "dot" => "dot",
+        "ddot" => "dot.double",
+        "overline" => "overline",
+        "underline" => "underline",
+        "overbrace" => "overbrace",
+        "underbrace" => "underbrace",
+        "sqrt" => "sqrt",
+        _ => return None,
+    };
+    Some(function)
+}
+
+/// Delimiter of a matrix environment
+fn matrix_delimiter(environment: &str) -> Option<&'static str> {
+    let delimiter = match environment {
+        "matrix" | "smallmatrix" | "array" => "#none",
+        "pmatrix" => "\"(\"",
+        "bmatrix" => "\"[\"",
+        "Bmatrix" => "\"{\"",
+        "vmatrix" => "\"|\"",
+        "Vmatrix" => "\"||\"",
+        _ => return None,
+    };
+    Some(delimiter)
+}
+
+/// Writes LaTeX math as Typst math
+struct Converter<'a, F> {
+    chars: Peekable<Chars<'a>>,
+    /// Whether Typst math knows a name
+    known: &'a F,
+}
+
+impl<F: Fn(&str) -> bool> Converter<'_, F> {
+    /// Math up to the end of the group
+    fn group(&mut self) -> String {
+        let mut math = String::new();
+        while let Some(c) = self.chars.next() {
+            match c {
+                '}' => break,
+                c if c.is_whitespace() => (),
+                // Scripts and primes stick to what they're on
+                '^' | '_' => {
+                    let script = self.argument();
+                    math.push_str(&format!("{c}({script})"));
+                }
+                '\'' => math.push('\''),
+                c => {
+                    let token = self.token(c);
+                    if !token.is_empty() {
+                        if !math.is_empty() {
+                            math.push(' ');
+                        }
+                        math.push_str(&token);
+                    }
+                }
+            }
+        }
+        math
+    }
+
+    /// Argument of a command or script: a group or a single token
+    fn argument(&mut self) -> String {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+        match self.chars.next() {
+            Some('{') => self.group(),
+            // Only the first digit of a number, like in `\frac12`
+            Some(c) if c.is_ascii_digit() => c.into(),
+            Some(c) => self.token(c),
+            None => String::new(),
+        }
+    }
+
+    /// Text of a group as is, for `\text` and the like
+    fn literal(&mut self) -> String {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let mut text = String::new();
+        if self.chars.next_if_eq(&'{').is_none() {
+            text.extend(self.chars.next());
+        } else {
+            let mut depth = 0;
+            for c in self.chars.by_ref() {
+                match c {
+                    '{' => depth += 1,
+                    '}' if depth == 0 => break,
+                    '}' => depth -= 1,
+                    _ => (),
+                }
+                text.push(c);
+            }
+        }
+        string(&text)
+    }
+
+    /// One char of the formula, or what it starts
+    fn token(&mut self, c: char) -> String {
+        match c {
+            '{' => {
+                // Groups only hold things together in LaTeX
+                let group = self.group();
+                if group.is_empty() {
+                    group
+                } else {
+                    format!("{{{group}}}")
+                }
+            }
+            '\\' => self.command(),
+            '0'..='9' => {
+                let mut number = c.to_string();
+                while let Some(c) = self.chars.next_if(|c| c.is_ascii_digit() || *c == '.') {
+                    number.push(c);
+                }
+                number
+            }
+            '~' => "space".into(),
+            '&' => "&".into(),
+            '/' | '"' | '#' | '$' | ',' | ';' | '@' | '`' | '*' | '<' | '>' | '-' | '=' | ':'
+            | '!' | '.' | '+' | '|' | '[' | ']' | '(' | ')' => format!("\\{c}"),
+            c => c.to_string(),
+        }
+    }
+
+    fn command(&mut self) -> String {
+        let mut name = String::new();
+        while let Some(c) = self.chars.next_if(|c| c.is_ascii_alphabetic()) {
+            name.push(c);
+        }
+        if name.is_empty() {
+            // Control symbols, like \{ or \,
+            let Some(c) = self.chars.next() else {
+                return String::new();
+            };
+            return match c {
+                '\\' => "\\".into(),
+                ',' => "thin".into(),
+                ':' | '>' => "med".into(),
+                ';' => "thick".into(),
+                ' ' => "space".into(),
+                '!' => String::new(),
+                '|' => "bar.v.double".into(),
+                c => format!("\\{c}"),
+            };
+        }
+
+        match name.as_str() {
+            "frac" | "dfrac" | "tfrac" | "cfrac" => {
+                let numerator = self.argument();
+                let denominator = self.argument();
+                format!("frac({}, {})", or_empty(&numerator), or_empty(&denominator))
+            }
+            "binom" | "dbinom" | "tbinom" => {
+                let upper = self.argument();
+                let lower = self.argument();
+                format!("binom({}, {})", or_empty(&upper), or_empty(&lower))
+            }
+            "sqrt" if self.chars.next_if_eq(&'[').is_some() => {
+                let mut index = String::new();
+                let mut depth = 0;
+                for c in self.chars.by_ref() {
+                    match c {
+                        '{' => depth += 1,
+                        '}' => depth -= 1,
+                        ']' if depth == 0 => break,
+                        _ => (),
+                    }
+                    index.push(c);
+                }
+                let index = Converter {
+                    chars: index.chars().peekable(),
+                    known: self.known,
+                }
+                .group();
+                let radicand = self.argument();
+                format!("root({}, {})", or_empty(&index), or_empty(&radicand))
+            }
+            "text" | "textrm" | "textnormal" | "mbox" => self.literal(),
+            "operatorname" => format!("op({})", self.literal()),
+            "begin" => self.environment(),
+            "left" => {
+                let open = self.delimiter();
+                let body = self.source_until("\\left", "\\right");
+                let body = Converter {
+                    chars: body.chars().peekable(),
+                    known: self.known,
+                }
+                .group();
+                let close = self.delimiter();
+                format!("lr({})", [open, body, close].join(" ").trim())
+            }
+            // Typst sizes the other delimiters itself
+            "right" | "middle" | "big" | "Big" | "bigg" | "Bigg" | "bigl" | "bigr" | "Bigl"
+            | "Bigr" => self.delimiter(),
+            "displaystyle" | "textstyle" | "limits" | "nolimits" | "end" => String::new(),
+            "quad" => "quad".into(),
+            "qquad" => "wide".into(),
+            name => {
+                if let Some(function) = function(name) {
+                    return format!("{function}({})", or_empty(&self.argument()));
+                }
+                match symbol(name) {
+                    Some(symbol) => symbol.into(),
+                    None if (self.known)(name) => name.into(),
+                    // Show what wasn't understood
+                    None => string(&format!("\\{name}")),
+                }
+            }
+        }
+    }
+
+    /// Matrices, cases and aligned equations
+    fn environment(&mut self) -> String {
+        let environment = self.literal_name();
+        // The column alignment of arrays
+        if environment == "array" {
+            self.literal_name();
+        }
+        let body = self.source_until(
+            &format!("\\begin{{{environment}}}"),
+            &format!("\\end{{{environment}}}"),
+        );
+        let mut rows: Vec<Vec<String>> = split_cells(&body)
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| {
+                        Converter {
+                            chars: cell.chars().peekable(),
+                            known: self.known,
+                        }
+                        .group()
+                    })
+                    .collect()
+            })
+            .collect();
+        // A line break after the last row doesn't start another
+        if rows
+            .last()
+            .is_some_and(|row| row.iter().all(String::is_empty))
+        {
+            rows.pop();
+        }
+
+        if let Some(delimiter) = matrix_delimiter(&environment) {
+            let rows: Vec<String> = rows
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|cell| or_empty(cell))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                })
+                .collect();
+            format!("mat(delim: {delimiter}, {})", rows.join("; "))
+        } else if environment == "cases" {
+            let rows: Vec<String> = rows
+                .iter()
+                .map(|row| row.join(" & ").trim().to_owned())
+                .collect();
+            format!("cases({})", rows.join(", "))
+        } else {
+            let rows: Vec<String> = rows
+                .iter()
+                .map(|row| row.join(" & ").trim().to_owned())
+                .collect();
+            rows.join(" \\ ")
+        }
+    }
+
+    /// Name in braces, like the environment after `\begin`
+    fn literal_name(&mut self) -> String {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+        if self.chars.next_if_eq(&'{').is_none() {
+            return String::new();
+        }
+        self.chars.by_ref().take_while(|c| *c != '}').collect()
+    }
+
+    /// Delimiter after a sizing command, `.` is none
+    fn delimiter(&mut self) -> String {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+        match self.chars.next() {
+            Some('.') | None => String::new(),
+            Some(c) => self.token(c),
+        }
+    }
+
+    /// Source up to the matching end, like the `\end` of an environment or the `\right` of a
+    /// `\left`. Commands that only start with the end, like `\rightarrow`, don't count.
+    fn source_until(&mut self, begin: &str, end: &str) -> String {
+        let mut body = String::new();
+        let mut depth = 0;
+        while let Some(c) = self.chars.next() {
+            body.push(c);
+            let next_letter = self.chars.peek().is_some_and(char::is_ascii_alphabetic);
+            let ends_with = |pattern: &str| {
+                body.ends_with(pattern)
+                    && !(next_letter && pattern.ends_with(|c: char| c.is_ascii_alphabetic()))
+            };
+            if ends_with(begin) {
+                depth += 1;
+            } else if ends_with(end) {
+                if depth == 0 {
+                    body.truncate(body.len() - end.len());
+                    break;
+                }
+                depth -= 1;
+            }
+        }
+        body
+    }
+}
+
+/// Rows of cells, split at `\\` and `&` outside of groups and nested environments
+fn split_cells(body: &str) -> Vec<Vec<String>> {
+    let mut rows = vec![vec![String::new()]];
+    let mut depth = 0;
+    let mut chars = body.chars().peekable();
+    while let Some(c) = chars.next() {
+        let row = rows.last_mut().unwrap();
+        let cell = row.last_mut().unwrap();
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            '&' if depth == 0 => {
+                row.push(String::new());
+                continue;
+            }
+            '\\' => {
+                if depth == 0 && chars.next_if_eq(&'\\').is_some() {
+                    rows.push(vec![String::new()]);
+                    continue;
+                }
+                // Keep escapes together, so \& and \{ aren't taken apart
+                cell.push(c);
+                let rest: String = chars.clone().take(5).collect();
+                if rest.starts_with("begin") {
+                    depth += 1;
+                } else if rest.starts_with("end") {
+                    depth -= 1;
+                } else if let Some(escaped) = chars.next_if(|c| !c.is_ascii_alphabetic()) {
+                    cell.push(escaped);
+                }
+                continue;
+            }
+            _ => (),
+        }
+        cell.push(c);
+    }
+    rows
+}
+
+/// Function arguments can't be left out
+fn or_empty(math: &str) -> &str {
+    if math.is_empty() { "\"\"" } else { math }
+}
+
+/// Typst string literal
+fn string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Typst math for a LaTeX formula. `known` tells which names Typst math has, commands with
+/// other names are shown as they are written.
+pub fn typst_math(tex: &str, known: impl Fn(&str) -> bool) -> String {
+    let mut converter = Converter {
+        chars: tex.chars().peekable(),
+        known: &known,
+    };
+    let mut math = String::new();
+    // Stray closing braces end a group early, the rest still gets converted
+    while converter.chars.peek().is_some() {
+        let group = converter.group();
+        if !group.is_empty() {
+            if !math.is_empty() {
+                math.push(' ');
+            }
+            math.push_str(&group);
+        }
+    }
+    math
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn known(name: &str) -> bool {
+        [
+            "alpha", "beta", "pi", "sum", "lim", "sin", "in", "times", "forall",
+        ]
+        .contains(&name)
+    }
+
+    #[test]
+    fn test_typst_math_symbols() {
+        assert_eq!(typst_math(r"x^2 + y_1", known), "x^(2) \\+ y_(1)");
+        assert_eq!(typst_math(r"\alpha \leq \beta", known), "alpha lt.eq beta");
+        assert_eq!(typst_math(r"\sum_{i=1}^n i", known), "sum_(i \\= 1)^(n) i");
+        assert_eq!(typst_math(r"f'(x)", known), "f' \\( x \\)");
+        assert_eq!(typst_math(r"ab", known), "a b");
+        assert_eq!(typst_math(r"3.14", known), "3.14");
+    }
+
+    #[test]
+    fn test_typst_math_commands() {
+        assert_eq!(typst_math(r"\frac{1}{2}", known), "frac(1, 2)");
+        assert_eq!(typst_math(r"\frac12", known), "frac(1, 2)");
+        assert_eq!(typst_math(r"\sqrt[3]{x}", known), "root(3, x)");
+        assert_eq!(typst_math(r"\sqrt{x}", known), "sqrt(x)");
+        assert_eq!(typst_math(r"\mathbb{R}", known), "bb(R)");
+        assert_eq!(typst_math(r"\text{if } x", known), "\"if \" x");
+        assert_eq!(
+            typst_math(r"\left( \frac{a}{b} \right)", known),
+            "lr(\\( frac(a, b) \\))"
+        );
+        assert_eq!(
+            typst_math(r"\left. x \rightarrow y \right|", known),
+            "lr(x arrow.r y \\|)"
+        );
+        assert_eq!(typst_math(r"a \, b", known), "a thin b");
+    }
+
+    #[test]
+    fn test_typst_math_unknown() {
+        assert_eq!(typst_math(r"\foo x", known), "\"\\\\foo\" x");
+        assert_eq!(typst_math(r#"\text{a"b}"#, known), r#""a\"b""#);
+        // Stray braces don't stop the conversion
+        assert_eq!(typst_math(r"a } b", known), "a b");
+        assert_eq!(typst_math(r"\frac{1", known), "frac(1, \"\")");
+    }
+
+    #[test]
+    fn test_typst_math_environments() {
+        assert_eq!(
+            typst_math(r"\begin{pmatrix} a & b \\ c & d \end{pmatrix}", known),
+            "mat(delim: \"(\", a, b; c, d)"
+        );
+        assert_eq!(
+            typst_math(
+                r"\begin{cases} 1 & x > 0 \\ 0 & \text{else} \end{cases}",
+                known
+            ),
+            "cases(1 & x \\> 0, 0 & \"else\")"
+        );
+        assert_eq!(
+            typst_math(r"\begin{aligned} a &= b \\ &= c \\ \end{aligned}", known),
+            "a & \\= b \\ & \\= c"
+        );
+        assert_eq!(
+            typst_math(
+                r"\begin{bmatrix} \begin{matrix} 1 & 2 \end{matrix} & 3 \end{bmatrix}",
+                known
+            ),
+            "mat(delim: \"[\", mat(delim: #none, 1, 2), 3)"
+        );
+    }
+}
@@ -1,5 +1,9 @@
+pub mod encoding;
 pub mod file_actions;
+pub mod language_spec;
+pub mod math_render;
 pub mod regex;
+pub mod style_scheme;
 
 pub fn process_file_ext_text(text: &str) -> String {
     text.to_lowercase().trim_start_matches(".").to_string()
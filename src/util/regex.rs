@@ -10,6 +10,53 @@ use regex::Regex;
 pub static ATX_H_OPENING: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"^ {0,3}#{1,6}($| +)").unwrap());
 
+/// Bullet or ordered list item marker, including the whitespace after it.
+pub static LIST_ITEM_OPENING: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*([-*+]|\d{1,9}[.)])\s+").unwrap());
+
+/// Underline of a setext heading. A `---` line is only a heading if it follows a paragraph.
+pub static SETEXT_H_UNDERLINE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^ {0,3}(=+|-+)\s*$").unwrap());
+
+/// Task list item. Group 1 is everything before the box, group 2 is the state char.
+pub static TASK_ITEM: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(\s*(?:[-*+]|\d{1,9}[.)])\s+)\[([ xX])\](?:\s|$)").unwrap());
+
+/// Thematic break, like `---` or `* * *`
+pub static THEMATIC_BREAK: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?m)^ {0,3}((-( |\t)*){3,}|(_( |\t)*){3,}|(\*( |\t)*){3,})$").unwrap()
+});
+
+/// GFM table delimiter row, like `| --- | :-: |`.
+pub static TABLE_DELIMITER_ROW: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*\|?(\s*:?-+:?\s*\|)+(\s*:?-+:?)?\s*$").unwrap());
+
+/// Footnote reference, like `[^1]`. Group 1 is the label.
+/// Definition labels match too, check for those separately.
+pub static FOOTNOTE_REFERENCE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\[\^([^\]\s]+)\]").unwrap());
+
+/// Footnote definition opening, like `[^1]:`. Group 1 is the label.
+pub static FOOTNOTE_DEFINITION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?m)^ {0,3}\[\^([^\]\s]+)\]:").unwrap());
+
+/// Inline link or image, like `[title](dest "tooltip")`.
+/// Group 1 is `!` for images, group 2 the title, group 3 the destination.
+pub static INLINE_LINK: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(!?)\[([^\]\n]*)\]\(\s*(<[^>\n]*>|[^\s)]*)(?:\s+"[^"\n]*")?\s*\)"#).unwrap()
+});
+
+/// Wiki link, like `[[target#heading|label]]`. Group 1 is the target.
+pub static WIKI_LINK: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\[\[([^\]|#\n]+)(?:#[^\]|\n]*)?(?:\|[^\]\n]*)?\]\]").unwrap());
+
+/// Opening or closing fence of a fenced code block
+pub static CODE_FENCE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^ {0,3}(```|~~~)").unwrap());
+
+/// Inline math, like `$x^2$`. No space inside the dollars, so prices aren't formulas.
+pub static MATH_INLINE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\$[^\s\$]([^\$\n]*[^\s\$\\])?\$").unwrap());
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -125,6 +172,166 @@ mod tests {
         assert_eq!(ATX_H_OPENING.find(empty_h2).unwrap().as_str(), "## ");
         assert_eq!(ATX_H_OPENING.find(empty_h3).unwrap().as_str(), "### ");
     }
+
+    #[test]
+    fn test_list_item_opening() {
+        assert_eq!(LIST_ITEM_OPENING.find("- foo").unwrap().as_str(), "- ");
+        assert_eq!(LIST_ITEM_OPENING.find("* foo").unwrap().as_str(), "* ");
+        assert_eq!(LIST_ITEM_OPENING.find("+ foo").unwrap().as_str(), "+ ");
+        assert_eq!(LIST_ITEM_OPENING.find("1. foo").unwrap().as_str(), "1. ");
+        assert_eq!(LIST_ITEM_OPENING.find("12) foo").unwrap().as_str(), "12) ");
+        assert_eq!(
+            LIST_ITEM_OPENING.find("    - foo").unwrap().as_str(),
+            "    - "
+        );
+        assert!(LIST_ITEM_OPENING.find("-foo").is_none());
+        assert!(LIST_ITEM_OPENING.find("**bold**").is_none());
+        assert!(LIST_ITEM_OPENING.find("foo - bar").is_none());
+    }
+
+    #[test]
+    fn test_task_item() {
+        let unchecked = TASK_ITEM.captures("- [ ] foo").unwrap();
+        assert_eq!(&unchecked[1], "- ");
+        assert_eq!(&unchecked[2], " ");
+        let checked = TASK_ITEM.captures("  * [x] foo").unwrap();
+        assert_eq!(&checked[1], "  * ");
+        assert_eq!(&checked[2], "x");
+        let upper = TASK_ITEM.captures("1. [X] foo").unwrap();
+        assert_eq!(&upper[1], "1. ");
+        assert_eq!(&upper[2], "X");
+        let empty = TASK_ITEM.captures("- [ ]").unwrap();
+        assert_eq!(&empty[2], " ");
+    }
+
+    #[test]
+    fn test_task_item_false_positives() {
+        assert!(TASK_ITEM.find("[ ] foo").is_none());
+        assert!(TASK_ITEM.find("- [] foo").is_none());
+        assert!(TASK_ITEM.find("- [y] foo").is_none());
+        assert!(TASK_ITEM.find("- [ ]foo").is_none());
+        assert!(TASK_ITEM.find("-[ ] foo").is_none());
+        assert!(TASK_ITEM.find("- [link](url)").is_none());
+    }
+
+    #[test]
+    fn test_table_delimiter_row() {
+        assert!(TABLE_DELIMITER_ROW.is_match("| --- |"));
+        assert!(TABLE_DELIMITER_ROW.is_match("|---|---|"));
+        assert!(TABLE_DELIMITER_ROW.is_match("| :-- | :-: | --: |"));
+        assert!(TABLE_DELIMITER_ROW.is_match("--- | ---"));
+        assert!(TABLE_DELIMITER_ROW.is_match("  | - |  "));
+        assert!(TABLE_DELIMITER_ROW.is_match("---|"));
+    }
+
+    #[test]
+    fn test_table_delimiter_row_false_positives() {
+        assert!(!TABLE_DELIMITER_ROW.is_match("---"));
+        assert!(!TABLE_DELIMITER_ROW.is_match("| foo |"));
+        assert!(!TABLE_DELIMITER_ROW.is_match("| : |"));
+        assert!(!TABLE_DELIMITER_ROW.is_match("| -- | foo |"));
+        assert!(!TABLE_DELIMITER_ROW.is_match("- | foo"));
+    }
+
+    #[test]
+    fn test_footnote_reference() {
+        let caps = FOOTNOTE_REFERENCE.captures("foo[^1] bar").unwrap();
+        assert_eq!(&caps[0], "[^1]");
+        assert_eq!(&caps[1], "1");
+        let caps = FOOTNOTE_REFERENCE.captures("foo [^note-a].").unwrap();
+        assert_eq!(&caps[1], "note-a");
+        assert!(FOOTNOTE_REFERENCE.find("[^]").is_none());
+        assert!(FOOTNOTE_REFERENCE.find("[^a b]").is_none());
+        assert!(FOOTNOTE_REFERENCE.find("[1]").is_none());
+    }
+
+    #[test]
+    fn test_footnote_definition() {
+        let caps = FOOTNOTE_DEFINITION.captures("[^1]: foo").unwrap();
+        assert_eq!(&caps[0], "[^1]:");
+        assert_eq!(&caps[1], "1");
+        let caps = FOOTNOTE_DEFINITION
+            .captures("text\n   [^note]: foo")
+            .unwrap();
+        assert_eq!(&caps[1], "note");
+        assert!(FOOTNOTE_DEFINITION.find("    [^1]: foo").is_none());
+        assert!(FOOTNOTE_DEFINITION.find("foo [^1]: bar").is_none());
+        assert!(FOOTNOTE_DEFINITION.find("[^1] foo").is_none());
+    }
+
+    #[test]
+    fn test_setext_h_underline() {
+        assert!(SETEXT_H_UNDERLINE.is_match("==="));
+        assert!(SETEXT_H_UNDERLINE.is_match("  ---  "));
+        assert!(!SETEXT_H_UNDERLINE.is_match("- - -"));
+        assert!(!SETEXT_H_UNDERLINE.is_match("    ---"));
+        assert!(!SETEXT_H_UNDERLINE.is_match("=-="));
+    }
+
+    #[test]
+    fn test_inline_link() {
+        let caps = INLINE_LINK.captures("see [foo](bar.md) here").unwrap();
+        assert_eq!(&caps[0], "[foo](bar.md)");
+        assert_eq!(&caps[1], "");
+        assert_eq!(&caps[2], "foo");
+        assert_eq!(&caps[3], "bar.md");
+        let caps = INLINE_LINK
+            .captures(r#"![a figure](<my images/fig 1.png> "Figure 1")"#)
+            .unwrap();
+        assert_eq!(&caps[1], "!");
+        assert_eq!(&caps[2], "a figure");
+        assert_eq!(&caps[3], "<my images/fig 1.png>");
+        let caps = INLINE_LINK.captures("[](#heading)").unwrap();
+        assert_eq!(&caps[3], "#heading");
+        assert!(INLINE_LINK.find("[foo] (bar.md)").is_none());
+        assert!(INLINE_LINK.find("[foo](bar baz)").is_none());
+    }
+
+    #[test]
+    fn test_wiki_link() {
+        let caps = WIKI_LINK.captures("see [[My Note]] here").unwrap();
+        assert_eq!(&caps[0], "[[My Note]]");
+        assert_eq!(&caps[1], "My Note");
+        let caps = WIKI_LINK.captures("[[dir/note#heading|Label]]").unwrap();
+        assert_eq!(&caps[0], "[[dir/note#heading|Label]]");
+        assert_eq!(&caps[1], "dir/note");
+        assert!(WIKI_LINK.find("[[]]").is_none());
+        assert!(WIKI_LINK.find("[note]").is_none());
+    }
+
+    #[test]
+    fn test_code_fence() {
+        assert!(CODE_FENCE.is_match("```"));
+        assert!(CODE_FENCE.is_match("   ~~~rust"));
+        assert!(!CODE_FENCE.is_match("    ```"));
+        assert!(!CODE_FENCE.is_match("``"));
+    }
+
+    #[test]
+    fn test_math_inline() {
+        assert_eq!(MATH_INLINE.find("a $x$ b").unwrap().as_str(), "$x$");
+        assert_eq!(
+            MATH_INLINE
+                .find("so $e^{i\\pi} + 1 = 0$.")
+                .unwrap()
+                .as_str(),
+            "$e^{i\\pi} + 1 = 0$"
+        );
+        assert!(MATH_INLINE.find("$ x$").is_none());
+        assert!(MATH_INLINE.find("$x $").is_none());
+        assert!(MATH_INLINE.find(r"$x\$").is_none());
+        assert!(MATH_INLINE.find("$$").is_none());
+        assert!(MATH_INLINE.find("from $5 to $10").is_none());
+        assert!(MATH_INLINE.find("$x\ny$").is_none());
+    }
+
+    #[test]
+    fn test_thematic_break() {
+        assert!(THEMATIC_BREAK.is_match("---"));
+        assert!(THEMATIC_BREAK.is_match("   * * *"));
+        assert!(!THEMATIC_BREAK.is_match("--"));
+        assert!(!THEMATIC_BREAK.is_match("-- a"));
+    }
 }
 
 #[cfg(test)]
@@ -133,9 +340,13 @@ mod langspec_tests {
 
     use regex::Regex;
 
-    static THEMATIC_BREAK: LazyLock<Regex> = LazyLock::new(|| {
-        Regex::new(r"(?m)^ {0,3}((-( |\t)*){3,}|(_( |\t)*){3,}|(\*( |\t)*){3,})$").unwrap()
-    });
+    use super::MATH_INLINE;
+    use super::THEMATIC_BREAK;
+
+    static TASK_LIST: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"^\s*([-*+]|\d{1,9}[.)])\s+\[ \](\s|$)").unwrap());
+    static TASK_LIST_DONE: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"^\s*([-*+]|\d{1,9}[.)])\s+\[[xX]\](\s.*|$)").unwrap());
 
     static ATX_H1_WHOLELINE: LazyLock<Regex> =
         LazyLock::new(|| Regex::new(r"^ {0,3}#($| +.*)").unwrap());
@@ -272,6 +483,39 @@ mod langspec_tests {
         );
     }
 
+    #[test]
+    fn test_task_list() {
+        assert_eq!(TASK_LIST.find("- [ ] foo").unwrap().as_str(), "- [ ] ");
+        assert_eq!(TASK_LIST.find("  + [ ] foo").unwrap().as_str(), "  + [ ] ");
+        assert_eq!(TASK_LIST.find("3. [ ]").unwrap().as_str(), "3. [ ]");
+        assert!(TASK_LIST.find("- [x] foo").is_none());
+        assert!(TASK_LIST.find("- [ ]foo").is_none());
+        assert!(TASK_LIST.find("[ ] foo").is_none());
+    }
+
+    #[test]
+    fn test_task_list_done() {
+        assert_eq!(
+            TASK_LIST_DONE.find("- [x] foo").unwrap().as_str(),
+            "- [x] foo"
+        );
+        assert_eq!(
+            TASK_LIST_DONE.find("* [X] foo").unwrap().as_str(),
+            "* [X] foo"
+        );
+        assert_eq!(TASK_LIST_DONE.find("- [x]").unwrap().as_str(), "- [x]");
+        assert!(TASK_LIST_DONE.find("- [ ] foo").is_none());
+        assert!(TASK_LIST_DONE.find("- [x]foo").is_none());
+    }
+
+    #[test]
+    fn test_math_inline() {
+        assert_eq!(MATH_INLINE.find("a $x^2$ b").unwrap().as_str(), "$x^2$");
+        assert_eq!(MATH_INLINE.find("$a$ and $b$").unwrap().as_str(), "$a$");
+        assert!(MATH_INLINE.find("costs $5, or $6").is_none());
+        assert!(MATH_INLINE.find("$ x $").is_none());
+    }
+
     #[test]
     fn test_atx_h1() {
         heading_test_suite(&ATX_H1_WHOLELINE, 1);
@@ -0,0 +1,154 @@
+//! Editor color schemes follow the light or dark style of the app.
+//! A scheme can name its variants in its metadata, otherwise they're found by id,
+//! like `Adwaita` and `Adwaita-dark` or `solarized-light` and `solarized-dark`.
+
+use sourceview5::StyleScheme;
+use sourceview5::StyleSchemeManager;
+
+/// The scheme to use for the chosen one in light or dark mode
+pub fn resolve_style_scheme(
+    manager: &StyleSchemeManager,
+    id: &str,
+    dark: bool,
+) -> Option<StyleScheme> {
+    let key = if dark {
+        "dark-variant"
+    } else {
+        "light-variant"
+    };
+    let declared = manager.scheme(id).and_then(|scheme| scheme.metadata(key));
+    let id = scheme_variant(id, dark, declared.as_deref(), |id| {
+        manager.scheme(id).is_some()
+    });
+    manager.scheme(&id)
+}
+
+/// Id of the light or dark variant of a scheme.
+/// `declared` is the variant named in the scheme's metadata.
+/// Schemes without a variant are used as they are.
+pub fn scheme_variant(
+    id: &str,
+    dark: bool,
+    declared: Option<&str>,
+    exists: impl Fn(&str) -> bool,
+) -> String {
+    if let Some(declared) = declared
+        && exists(declared)
+    {
+        return declared.to_owned();
+    }
+
+    let base = id
+        .strip_suffix("-dark")
+        .or_else(|| id.strip_suffix("-light"))
+        .unwrap_or(id);
+    let suffix = if dark { "-dark" } else { "-light" };
+    [format!("{base}{suffix}"), base.to_owned()]
+        .into_iter()
+        .find(|candidate| exists(candidate))
+        .unwrap_or_else(|| id.to_owned())
+}
+
+/// Whether a scheme is the variant of another installed scheme, so it can be left out of lists
+pub fn is_variant(id: &str, exists: impl Fn(&str) -> bool) -> bool {
+    id.strip_suffix("-dark")
+        .is_some_and(|base| exists(base) || exists(&format!("{base}-light")))
+}
+
+#[cfg(test)]
+mod tests {
+    use regex::Regex;
+
+    use super::*;
+
+    const IDS: [&str; 7] = [
+        "Adwaita",
+        "Adwaita-dark",
+        "solarized-light",
+        "solarized-dark",
+        "oblivion",
+        "scratchmark",
+        "custom",
+    ];
+
+    fn exists(id: &str) -> bool {
+        IDS.contains(&id)
+    }
+
+    #[test]
+    fn test_scheme_variant_by_id() {
+        assert_eq!(
+            scheme_variant("Adwaita", true, None, exists),
+            "Adwaita-dark"
+        );
+        assert_eq!(scheme_variant("Adwaita", false, None, exists), "Adwaita");
+        assert_eq!(
+            scheme_variant("Adwaita-dark", false, None, exists),
+            "Adwaita"
+        );
+        assert_eq!(
+            scheme_variant("Adwaita-dark", true, None, exists),
+            "Adwaita-dark"
+        );
+        assert_eq!(
+            scheme_variant("solarized-light", true, None, exists),
+            "solarized-dark"
+        );
+        assert_eq!(
+            scheme_variant("solarized-dark", false, None, exists),
+            "solarized-light"
+        );
+    }
+
+    #[test]
+    fn test_scheme_variant_without_variants() {
+        assert_eq!(scheme_variant("oblivion", false, None, exists), "oblivion");
+        assert_eq!(
+            scheme_variant("scratchmark", true, None, exists),
+            "scratchmark"
+        );
+        assert_eq!(scheme_variant("missing", true, None, exists), "missing");
+    }
+
+    #[test]
+    fn test_scheme_variant_declared() {
+        assert_eq!(
+            scheme_variant("custom", true, Some("oblivion"), exists),
+            "oblivion"
+        );
+        // Missing variants fall back to the ids
+        assert_eq!(
+            scheme_variant("Adwaita", true, Some("missing"), exists),
+            "Adwaita-dark"
+        );
+    }
+
+    #[test]
+    fn test_is_variant() {
+        assert!(is_variant("Adwaita-dark", exists));
+        assert!(is_variant("solarized-dark", exists));
+        assert!(!is_variant("solarized-light", exists));
+        assert!(!is_variant("Adwaita", exists));
+        assert!(!is_variant("oblivion", exists));
+        assert!(!is_variant("missing-dark", exists));
+    }
+
+    /// Other schemes don't know the markdown styles, so they need a fallback to show headings
+    /// and emphasis
+    #[test]
+    fn test_markdown_styles_map_to_defaults() {
+        let spec = include_str!("../../data/language_specs/markdown.lang");
+        let scheme = include_str!("../../data/editor_schemes/scratchmark.xml");
+        let style = Regex::new(r#"<style id="([^"]+)"[^>]*>"#).unwrap();
+        let styles: Vec<_> = style.captures_iter(spec).collect();
+        assert!(!styles.is_empty());
+        for caps in styles {
+            let id = &caps[1];
+            assert!(
+                caps[0].contains(r#"map-to="def:"#)
+                    || scheme.contains(&format!(r#"<style name="markdown:{id}""#)),
+                "{id}"
+            );
+        }
+    }
+}
@@ -3,6 +3,14 @@ mod imp {
     use gtk::CompositeTemplate;
     use gtk::Label;
     use gtk::glib;
+    use gtk::pango::AttrColor;
+    use gtk::pango::AttrInt;
+    use gtk::pango::AttrList;
+    use gtk::pango::Underline;
+    use gtk::pango::Weight;
+
+    use crate::data::StyleIssueKind;
+    use crate::data::style_issue_color;
 
     #[derive(CompositeTemplate, Default)]
     #[template(resource = "/org/scratchmark/Scratchmark/ui/editor/document_stats_view.ui")]
@@ -15,6 +23,32 @@ mod imp {
         pub(super) lab_num_words: TemplateChild<Label>,
         #[template_child]
         pub(super) lab_num_lines: TemplateChild<Label>,
+        #[template_child]
+        pub(super) tasks_row: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub(super) lab_num_tasks: TemplateChild<Label>,
+        #[template_child]
+        pub(super) style_box: TemplateChild<gtk::Box>,
+        #[template_child]
+        lab_style_adverbs: TemplateChild<Label>,
+        #[template_child]
+        pub(super) lab_num_adverbs: TemplateChild<Label>,
+        #[template_child]
+        lab_style_passive: TemplateChild<Label>,
+        #[template_child]
+        pub(super) lab_num_passive: TemplateChild<Label>,
+        #[template_child]
+        lab_style_fillers: TemplateChild<Label>,
+        #[template_child]
+        pub(super) lab_num_fillers: TemplateChild<Label>,
+        #[template_child]
+        lab_style_long_sentences: TemplateChild<Label>,
+        #[template_child]
+        pub(super) lab_num_long_sentences: TemplateChild<Label>,
+        #[template_child]
+        lab_style_repeated_words: TemplateChild<Label>,
+        #[template_child]
+        pub(super) lab_num_repeated_words: TemplateChild<Label>,
     }
 
     #[glib::object_subclass]
@@ -35,6 +69,34 @@ mod imp {
     impl ObjectImpl for DocumentStatsView {
         fn constructed(&self) {
             self.parent_constructed();
+
+            // Style labels are marked like the issues in the editor
+            for (label, kind) in [
+                (&self.lab_style_adverbs, StyleIssueKind::Adverb),
+                (&self.lab_style_passive, StyleIssueKind::Passive),
+                (&self.lab_style_fillers, StyleIssueKind::Filler),
+                (&self.lab_style_long_sentences, StyleIssueKind::LongSentence),
+                (&self.lab_style_repeated_words, StyleIssueKind::RepeatedWord),
+            ] {
+                let color = style_issue_color(kind);
+                let (red, green, blue) = (
+                    (color.red() * 65535.) as u16,
+                    (color.green() * 65535.) as u16,
+                    (color.blue() * 65535.) as u16,
+                );
+                let attributes = AttrList::new();
+                attributes.insert(AttrInt::new_weight(Weight::Bold));
+                if kind == StyleIssueKind::LongSentence {
+                    attributes.insert(AttrColor::new_background(red, green, blue));
+                    attributes.insert(AttrInt::new_background_alpha(
+                        (color.alpha() * 65535.) as u16,
+                    ));
+                } else {
+                    attributes.insert(AttrInt::new_underline(Underline::Single));
+                    attributes.insert(AttrColor::new_underline_color(red, green, blue));
+                }
+                label.set_attributes(Some(&attributes));
+            }
         }
     }
 
@@ -43,10 +105,13 @@ mod imp {
 }
 
 use adw::subclass::prelude::*;
+use gettextrs::gettext;
 use gtk::glib;
 use gtk::glib::Object;
+use gtk::prelude::*;
 
 use crate::data::DocumentStats;
+use crate::data::StyleSummary;
 
 glib::wrapper! {
     pub struct DocumentStatsView(ObjectSubclass<imp::DocumentStatsView>)
@@ -68,5 +133,30 @@ impl DocumentStatsView {
             .set_label(&format!("{}", data.num_chars - data.num_spaces));
         imp.lab_num_words.set_label(&format!("{}", data.num_words));
         imp.lab_num_lines.set_label(&format!("{}", data.num_lines));
+        imp.tasks_row.set_visible(data.num_tasks > 0);
+        imp.lab_num_tasks.set_label(
+            &gettext("{done}/{total} done")
+                .replace("{done}", &data.num_tasks_done.to_string())
+                .replace("{total}", &data.num_tasks.to_string()),
+        );
+    }
+
+    /// Hidden without a summary
+    pub fn set_style_summary(&self, summary: Option<&StyleSummary>) {
+        let imp = self.imp();
+        imp.style_box.set_visible(summary.is_some());
+        let Some(summary) = summary else {
+            return;
+        };
+        imp.lab_num_adverbs
+            .set_label(&format!("{}", summary.adverbs));
+        imp.lab_num_passive
+            .set_label(&format!("{}", summary.passive));
+        imp.lab_num_fillers
+            .set_label(&format!("{}", summary.fillers));
+        imp.lab_num_long_sentences
+            .set_label(&format!("{}", summary.long_sentences));
+        imp.lab_num_repeated_words
+            .set_label(&format!("{}", summary.repeated_words));
     }
 }
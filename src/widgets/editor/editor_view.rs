@@ -2,8 +2,12 @@ mod imp {
     use std::cell::Cell;
     use std::cell::OnceCell;
     use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::path::Path;
     use std::path::PathBuf;
     use std::sync::OnceLock;
+    use std::time::Duration;
+    use std::time::SystemTime;
 
     use adw::AlertDialog;
     use adw::Banner;
@@ -11,10 +15,14 @@ mod imp {
     use adw::OverlaySplitView;
     use adw::prelude::*;
     use adw::subclass::prelude::*;
+    use gettextrs::gettext;
     use gtk::CompositeTemplate;
+    use gtk::Label;
     use gtk::ScrolledWindow;
     use gtk::TemplateChild;
     use gtk::TextMark;
+    use gtk::TextTag;
+    use gtk::gdk::Texture;
     use gtk::gio;
     use gtk::gio::Cancellable;
     use gtk::gio::File;
@@ -25,24 +33,66 @@ mod imp {
     use gtk::glib;
     use gtk::glib::GString;
     use gtk::glib::Properties;
+    use gtk::glib::SignalHandlerId;
     use gtk::glib::VariantTy;
     use gtk::glib::clone;
     use gtk::glib::closure_local;
     use gtk::glib::subclass::Signal;
     use libspelling::Checker;
     use libspelling::TextBufferAdapter;
+    use sourceview5::MarkAttributes;
+    use sourceview5::StyleSchemeManager;
     use sourceview5::prelude::ViewExt;
 
     use crate::data::DocumentStats;
+    use crate::data::FOLD_MARK_CATEGORY;
+    use crate::data::FOLDED_MARK_CATEGORY;
+    use crate::data::FormatOptions;
+    use crate::data::KeybindingMode;
+    use crate::data::LINT_MARK_CATEGORY;
+    use crate::data::LintConfig;
+    use crate::data::LintDiagnostic;
     use crate::data::MarkdownBuffer;
+    use crate::data::StyleWords;
+    use crate::data::TableAlignment;
+    use crate::data::section_at;
+    use crate::error::ScratchmarkError;
+    use crate::util::encoding::TextEncoding;
     use crate::util::file_actions;
+    use crate::util::style_scheme;
+    use crate::widgets::DocumentRow;
     use crate::widgets::editor::document_stats_view::DocumentStatsView;
     use crate::widgets::editor::file_changed_on_disk_dialog::FileChangedOnDiskDialog;
+    use crate::widgets::editor::math_hover::MathHoverProvider;
     use crate::widgets::editor::minimap::Minimap;
     use crate::widgets::editor::search_bar::EditorSearchBar;
     use crate::widgets::editor::text_view::EditorTextView;
 
     const DEFAULT_TOP_MARGIN: i32 = 96;
+    const IMAGE_PREVIEW_MAX_HEIGHT: i32 = 360;
+    const IMAGE_PREVIEW_SPACING: i32 = 12;
+    const DELAYED_REFRESH_DELAY: Duration = Duration::from_millis(500);
+
+    /// Thumbnail shown below a line that links a local image
+    #[derive(Debug)]
+    struct ImagePreview {
+        picture: gtk::Picture,
+        /// Makes room for the picture below the line
+        tag: TextTag,
+        /// Start of the line
+        mark: TextMark,
+        position: Cell<(i32, i32)>,
+        texture: Texture,
+        size: (i32, i32),
+    }
+
+    /// Word lists of the style check, loaded again when the file changes
+    #[derive(Debug)]
+    struct CachedStyleWords {
+        path: PathBuf,
+        modified: Option<SystemTime>,
+        words: StyleWords,
+    }
 
     #[derive(Debug, Properties, CompositeTemplate, Default)]
     #[properties(wrapper_type = super::EditorView)]
@@ -70,6 +120,37 @@ mod imp {
         use_spellcheck: Cell<bool>,
         #[property(get, set)]
         typewriter_mode: Cell<bool>,
+        /// Name of the [FocusScope](crate::data::FocusScope) that stays bright in typewriter mode
+        #[property(get, set)]
+        focus_scope: RefCell<String>,
+        /// Pasted images are saved here, relative to the document
+        #[property(get, set)]
+        assets_folder: RefCell<String>,
+        #[property(get, set)]
+        show_image_previews: Cell<bool>,
+        #[property(get, set)]
+        smart_typography: Cell<bool>,
+        /// Mark adverbs, passive voice, filler words, long sentences and repeated words
+        #[property(get, set)]
+        style_check: Cell<bool>,
+        /// Marker of bullet list items in formatted documents
+        #[property(get, set)]
+        format_bullet: RefCell<String>,
+        /// Delimiter of emphasis in formatted documents
+        #[property(get, set)]
+        format_emphasis: RefCell<String>,
+        /// Rewrap paragraphs at the wrap column when formatting
+        #[property(get, set)]
+        format_rewrap: Cell<bool>,
+        /// Column that paragraphs are wrapped at
+        #[property(get, set)]
+        wrap_column: Cell<u32>,
+        /// Name of the [KeybindingMode]
+        #[property(get, set)]
+        keybindings: RefCell<String>,
+        /// Id of the chosen color scheme. Its light or dark variant is applied.
+        #[property(get, set)]
+        style_scheme: RefCell<String>,
 
         #[template_child]
         pub(super) source_view: TemplateChild<EditorTextView>,
@@ -81,8 +162,16 @@ mod imp {
         #[template_child]
         pub(super) search_bar: TemplateChild<EditorSearchBar>,
         #[template_child]
+        vim_status_bar: TemplateChild<gtk::Box>,
+        #[template_child]
+        vim_status_label: TemplateChild<Label>,
+        #[template_child]
+        vim_pending_label: TemplateChild<Label>,
+        #[template_child]
         file_changed_on_disk_banner: TemplateChild<Banner>,
         #[template_child]
+        encoding_banner: TemplateChild<Banner>,
+        #[template_child]
         pub(super) editor_split: TemplateChild<OverlaySplitView>,
         #[template_child]
         pub(super) minimap: TemplateChild<Minimap>,
@@ -98,6 +187,23 @@ mod imp {
         file_monitor: RefCell<Option<FileMonitor>>,
         pub(super) checker: OnceCell<Checker>,
         pub(super) adapter: OnceCell<TextBufferAdapter>,
+        image_previews: RefCell<Vec<ImagePreview>>,
+        /// Pending refresh, started over on each change
+        delayed_refresh: RefCell<Option<glib::SourceId>>,
+        image_textures: RefCell<HashMap<PathBuf, (SystemTime, Texture)>>,
+        /// Folder of the style word lists, created on the first style check
+        style_words_folder: OnceCell<PathBuf>,
+        style_words: RefCell<Option<CachedStyleWords>>,
+        /// Lint while this is set
+        pub(super) lint_config: RefCell<Option<LintConfig>>,
+        pub(super) lint_diagnostics: RefCell<Vec<LintDiagnostic>>,
+        dark_handler: RefCell<Option<SignalHandlerId>>,
+        /// Whether the document has headings with something under them
+        foldable: Cell<bool>,
+        /// Start of the table that is being edited. It's realigned when the cursor leaves it.
+        edited_table: RefCell<Option<TextMark>>,
+        /// Encoding that the file is saved in
+        pub(super) encoding: Cell<TextEncoding>,
     }
 
     #[glib::object_subclass]
@@ -186,9 +292,10 @@ mod imp {
                                         obj.set_file_changed_on_disk(false);
                                     } else if response == "discard" {
                                         let file = gio::File::for_path(obj.path());
-                                        match file_actions::read_file_to_string(&file) {
-                                            Ok(text) => {
+                                        match file_actions::read_text_file(&file) {
+                                            Ok((text, encoding)) => {
                                                 obj.imp().source_view.buffer().set_text(&text);
+                                                obj.imp().set_encoding(encoding);
                                                 obj.set_file_changed_on_disk(false);
                                             }
                                             Err(e) => {
@@ -203,6 +310,15 @@ mod imp {
                     }
                 ));
 
+            self.encoding_banner.connect_button_clicked(clone!(
+                #[weak]
+                obj,
+                move |_| {
+                    obj.imp().set_encoding(TextEncoding::Utf8);
+                    obj.set_unsaved_changes(true);
+                }
+            ));
+
             self.minimap.bind(&self.source_view);
             self.minimap
                 .bind_property("visible", obj.as_ref(), "show_minimap")
@@ -228,8 +344,68 @@ mod imp {
 
             obj.connect_max_width_notify(clone!(move |obj| {
                 obj.imp().refresh_max_width();
+                obj.imp().refresh_image_previews();
+            }));
+
+            obj.connect_show_image_previews_notify(clone!(move |obj| {
+                obj.imp().refresh_image_previews();
             }));
 
+            obj.connect_style_check_notify(clone!(move |obj| {
+                obj.imp().refresh_style_check();
+            }));
+
+            let lint_attributes = MarkAttributes::new();
+            lint_attributes.set_icon_name("smk-error-symbolic");
+            lint_attributes.connect_query_tooltip_text(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                #[upgrade_or_default]
+                move |_, mark| imp.lint_tooltip(mark)
+            ));
+            self.source_view
+                .set_mark_attributes(LINT_MARK_CATEGORY, &lint_attributes, 0);
+
+            // Lint marks take precedence over fold arrows, a click on either toggles the fold
+            for (category, icon, tooltip) in [
+                (
+                    FOLD_MARK_CATEGORY,
+                    "pan-down-symbolic",
+                    gettext("Fold Section"),
+                ),
+                (
+                    FOLDED_MARK_CATEGORY,
+                    "pan-end-symbolic",
+                    gettext("Unfold Section"),
+                ),
+            ] {
+                let attributes = MarkAttributes::new();
+                attributes.set_icon_name(icon);
+                attributes.connect_query_tooltip_text(move |_, _| tooltip.clone());
+                self.source_view
+                    .set_mark_attributes(category, &attributes, -1);
+            }
+            self.source_view.connect_line_mark_activated(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move |_, iter, button, _, _| {
+                    if button == gtk::gdk::BUTTON_PRIMARY {
+                        imp.toggle_fold(iter.line() as usize);
+                    }
+                }
+            ));
+
+            self.source_view
+                .hover()
+                .add_provider(&MathHoverProvider::default());
+
+            // Layout changes move the lines the previews belong to
+            self.scrolled_window.vadjustment().connect_changed(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move |_| imp.position_image_previews()
+            ));
+
             obj.connect_typewriter_mode_notify(clone!(move |obj| {
                 let imp = obj.imp();
                 imp.refresh_vertical_margins();
@@ -244,6 +420,38 @@ mod imp {
             obj.bind_property("typewriter_mode", source_view, "typewriter_mode")
                 .sync_create()
                 .build();
+            obj.bind_property("focus_scope", source_view, "focus_scope")
+                .sync_create()
+                .build();
+            obj.bind_property("keybindings", source_view, "keybindings")
+                .sync_create()
+                .build();
+
+            let vim_status_bar: &gtk::Box = self.vim_status_bar.as_ref();
+            obj.bind_property("keybindings", vim_status_bar, "visible")
+                .transform_to(|_, name: String| {
+                    Some(KeybindingMode::try_from(name.as_str()) == Ok(KeybindingMode::Vim))
+                })
+                .sync_create()
+                .build();
+            let vim_status_label: &Label = self.vim_status_label.as_ref();
+            source_view
+                .bind_property("vim_status", vim_status_label, "label")
+                .transform_to(|_, status: String| {
+                    // Normal mode has no status of its own
+                    Some(if status.is_empty() {
+                        gettext("-- NORMAL --")
+                    } else {
+                        status
+                    })
+                })
+                .sync_create()
+                .build();
+            let vim_pending_label: &Label = self.vim_pending_label.as_ref();
+            source_view
+                .bind_property("vim_pending", vim_pending_label, "label")
+                .sync_create()
+                .build();
 
             self.source_view.connect_move_cursor(clone!(
                 #[weak]
@@ -268,6 +476,128 @@ mod imp {
             ));
             obj.add_controller(source_click_gesture);
 
+            self.source_view.connect_closure(
+                "image-pasted",
+                false,
+                closure_local!(
+                    #[weak(rename_to = imp)]
+                    self,
+                    move |_: EditorTextView, texture: gtk::gdk::Texture| {
+                        imp.insert_pasted_image(&texture);
+                    }
+                ),
+            );
+
+            // Dropped files and library documents are inserted as links
+            let drop_target = gtk::DropTarget::new(
+                glib::Type::INVALID,
+                gtk::gdk::DragAction::COPY | gtk::gdk::DragAction::MOVE,
+            );
+            drop_target.set_types(&[
+                gtk::gdk::FileList::static_type(),
+                DocumentRow::static_type(),
+            ]);
+            drop_target.connect_drop(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                #[upgrade_or]
+                false,
+                move |_: &gtk::DropTarget, value: &glib::Value, x: f64, y: f64| {
+                    let paths: Vec<PathBuf> = if let Ok(files) = value.get::<gtk::gdk::FileList>() {
+                        files
+                            .files()
+                            .iter()
+                            .filter_map(|file| file.path())
+                            .collect()
+                    } else if let Ok(doc) = value.get::<DocumentRow>() {
+                        vec![doc.path()]
+                    } else {
+                        return false;
+                    };
+                    imp.insert_file_links(&paths, x, y);
+                    true
+                }
+            ));
+            self.source_view.add_controller(drop_target);
+
+            // Ctrl+click toggles tasks and jumps between footnotes
+            let ctrl_click_gesture = gtk::GestureClick::new();
+            ctrl_click_gesture.set_button(gtk::gdk::ffi::GDK_BUTTON_PRIMARY as u32);
+            ctrl_click_gesture.set_propagation_phase(gtk::PropagationPhase::Capture);
+            ctrl_click_gesture.connect_pressed(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move |gesture, _, x, y| {
+                    if !gesture
+                        .current_event_state()
+                        .contains(gtk::gdk::ModifierType::CONTROL_MASK)
+                    {
+                        return;
+                    }
+                    let (bx, by) = imp.source_view.window_to_buffer_coords(
+                        gtk::TextWindowType::Widget,
+                        x as i32,
+                        y as i32,
+                    );
+                    let Some(iter) = imp.source_view.iter_at_location(bx, by) else {
+                        return;
+                    };
+                    let buffer = imp.buffer.get().unwrap();
+                    if buffer.toggle_task_at(&iter) {
+                        gesture.set_state(gtk::EventSequenceState::Claimed);
+                    } else if let Some(mut target) = buffer.footnote_target_at(&iter) {
+                        gesture.set_state(gtk::EventSequenceState::Claimed);
+                        buffer.place_cursor(&target);
+                        imp.source_view
+                            .scroll_to_iter(&mut target, 0.0, false, 0.0, 0.5);
+                    }
+                }
+            ));
+            self.source_view.add_controller(ctrl_click_gesture);
+
+            // Tab and Shift+Tab move between table cells
+            let table_key_controller = gtk::EventControllerKey::new();
+            table_key_controller.set_propagation_phase(gtk::PropagationPhase::Capture);
+            table_key_controller.connect_key_pressed(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                #[upgrade_or]
+                glib::Propagation::Proceed,
+                move |_, key, _, modifiers| {
+                    use gtk::gdk::Key;
+                    use gtk::gdk::ModifierType;
+
+                    if modifiers.intersects(ModifierType::CONTROL_MASK | ModifierType::ALT_MASK) {
+                        return glib::Propagation::Proceed;
+                    }
+                    let buffer = imp.buffer.get().unwrap();
+                    // Enter realigns the edited table before the line break goes in
+                    if matches!(key, Key::Return | Key::KP_Enter) {
+                        if imp.edited_table_at_cursor() {
+                            buffer.table_realign();
+                        }
+                        return glib::Propagation::Proceed;
+                    }
+                    let backwards = match key {
+                        Key::Tab => false,
+                        Key::ISO_Left_Tab => true,
+                        _ => return glib::Propagation::Proceed,
+                    };
+                    // Multi-line selections get indented instead
+                    if let Some((start, end)) = buffer.selection_bounds()
+                        && start.line() != end.line()
+                    {
+                        return glib::Propagation::Proceed;
+                    }
+                    if buffer.table_move_cell(backwards) {
+                        imp.source_view.scroll_mark_onscreen(&buffer.get_insert());
+                        return glib::Propagation::Stop;
+                    }
+                    glib::Propagation::Proceed
+                }
+            ));
+            self.source_view.add_controller(table_key_controller);
+
             let actions = SimpleActionGroup::new();
             obj.insert_action_group("editor", Some(&actions));
 
@@ -398,8 +728,227 @@ mod imp {
             ));
             actions.add_action(&action);
 
+            let action = gio::SimpleAction::new("format-task", None);
+            action.connect_activate(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move |_, _| imp.buffer.get().unwrap().format_task()
+            ));
+            actions.add_action(&action);
+
+            let action = gio::SimpleAction::new("format-footnote", None);
+            action.connect_activate(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move |_, _| {
+                    let buffer = imp.buffer.get().unwrap();
+                    buffer.format_footnote();
+                    imp.source_view.scroll_mark_onscreen(&buffer.get_insert());
+                }
+            ));
+            actions.add_action(&action);
+
+            let action =
+                gio::SimpleAction::new("format-table", Some(VariantTy::new("(ii)").unwrap()));
+            action.connect_activate(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move |_, param| {
+                    let (num_rows, num_columns): (i32, i32) = param.unwrap().get().unwrap();
+                    imp.buffer
+                        .get()
+                        .unwrap()
+                        .format_table(num_rows.max(0) as usize, num_columns.max(1) as usize);
+                    imp.source_view.grab_focus();
+                }
+            ));
+            actions.add_action(&action);
+
+            let action = gio::SimpleAction::new("format-document", None);
+            action.connect_activate(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move |_, _| {
+                    let buffer = imp.buffer.get().unwrap();
+                    buffer.format_document(&imp.format_options());
+                    imp.source_view.scroll_mark_onscreen(&buffer.get_insert());
+                }
+            ));
+            actions.add_action(&action);
+
+            let action = gio::SimpleAction::new("reflow-paragraph", None);
+            action.connect_activate(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move |_, _| {
+                    let buffer = imp.buffer.get().unwrap();
+                    buffer.reflow_paragraph(Some(imp.wrap_column.get() as usize));
+                    imp.source_view.scroll_mark_onscreen(&buffer.get_insert());
+                }
+            ));
+            actions.add_action(&action);
+
+            let action = gio::SimpleAction::new("unwrap-paragraph", None);
+            action.connect_activate(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move |_, _| {
+                    let buffer = imp.buffer.get().unwrap();
+                    buffer.reflow_paragraph(None);
+                    imp.source_view.scroll_mark_onscreen(&buffer.get_insert());
+                }
+            ));
+            actions.add_action(&action);
+
+            let action = gio::SimpleAction::new("table-reformat", None);
+            action.connect_activate(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move |_, _| {
+                    imp.buffer.get().unwrap().format_table_reformat();
+                }
+            ));
+            actions.add_action(&action);
+
+            let action = gio::SimpleAction::new("table-insert-row", None);
+            action.connect_activate(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move |_, _| imp.buffer.get().unwrap().table_insert_row()
+            ));
+            actions.add_action(&action);
+
+            let action = gio::SimpleAction::new("table-remove-row", None);
+            action.connect_activate(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move |_, _| imp.buffer.get().unwrap().table_remove_row()
+            ));
+            actions.add_action(&action);
+
+            let action = gio::SimpleAction::new("table-insert-column", None);
+            action.connect_activate(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move |_, _| imp.buffer.get().unwrap().table_insert_column()
+            ));
+            actions.add_action(&action);
+
+            let action = gio::SimpleAction::new("table-remove-column", None);
+            action.connect_activate(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move |_, _| imp.buffer.get().unwrap().table_remove_column()
+            ));
+            actions.add_action(&action);
+
+            let action = gio::SimpleAction::new("table-align", Some(VariantTy::STRING));
+            action.connect_activate(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move |_, param| {
+                    let name: String = param.unwrap().get().unwrap();
+                    if let Some(alignment) = TableAlignment::from_name(&name) {
+                        imp.buffer.get().unwrap().table_align_column(alignment);
+                    }
+                }
+            ));
+            actions.add_action(&action);
+
+            for (name, down) in [("move-lines-up", false), ("move-lines-down", true)] {
+                let action = gio::SimpleAction::new(name, None);
+                action.connect_activate(clone!(
+                    #[weak(rename_to = imp)]
+                    self,
+                    move |_, _| imp.source_view.emit_move_lines(down)
+                ));
+                actions.add_action(&action);
+            }
+
+            for (name, up) in [("move-section-up", true), ("move-section-down", false)] {
+                let action = gio::SimpleAction::new(name, None);
+                action.connect_activate(clone!(
+                    #[weak(rename_to = imp)]
+                    self,
+                    move |_, _| {
+                        if imp.buffer.get().unwrap().move_section(up) {
+                            imp.on_folds_changed();
+                            imp.scroll_to_cursor();
+                        }
+                    }
+                ));
+                actions.add_action(&action);
+            }
+
+            for (name, promote) in [("promote-section", true), ("demote-section", false)] {
+                let action = gio::SimpleAction::new(name, None);
+                action.connect_activate(clone!(
+                    #[weak(rename_to = imp)]
+                    self,
+                    move |_, _| {
+                        if imp.buffer.get().unwrap().shift_section(promote) {
+                            imp.on_folds_changed();
+                        }
+                    }
+                ));
+                actions.add_action(&action);
+            }
+
+            let action = gio::SimpleAction::new("fold-toggle", None);
+            action.connect_activate(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move |_, _| {
+                    let buffer = imp.buffer.get().unwrap();
+                    let cursor = buffer.iter_at_mark(&buffer.get_insert());
+                    if let Some(section) = section_at(&buffer.sections(), cursor.line() as usize) {
+                        imp.toggle_fold(section.line);
+                    }
+                }
+            ));
+            actions.add_action(&action);
+
+            let action = gio::SimpleAction::new("fold-to-level", Some(VariantTy::INT32));
+            action.connect_activate(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move |_, param| {
+                    let level: i32 = param.unwrap().get().unwrap();
+                    imp.buffer
+                        .get()
+                        .unwrap()
+                        .fold_to_level(level.max(1) as usize);
+                    imp.on_folds_changed();
+                }
+            ));
+            actions.add_action(&action);
+
+            let action = gio::SimpleAction::new("unfold-all", None);
+            action.connect_activate(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move |_, _| {
+                    imp.buffer.get().unwrap().unfold_all();
+                    imp.on_folds_changed();
+                }
+            ));
+            actions.add_action(&action);
+
             self.setup_height_watcher();
 
+            obj.connect_style_scheme_notify(|obj| obj.imp().refresh_style_scheme());
+            let dark_handler = adw::StyleManager::default().connect_dark_notify(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move |_| imp.refresh_style_scheme()
+            ));
+            self.dark_handler.replace(Some(dark_handler));
+            obj.connect_destroy(move |obj| {
+                if let Some(handler) = obj.imp().dark_handler.take() {
+                    adw::StyleManager::default().disconnect(handler);
+                }
+            });
+
             self.parent_constructed();
         }
 
@@ -410,6 +959,8 @@ mod imp {
                     Signal::builder("saved").build(),
                     Signal::builder("saved-as").build(),
                     Signal::builder("stats-changed").build(),
+                    Signal::builder("lint-changed").build(),
+                    Signal::builder("folds-changed").build(),
                     Signal::builder("touched").build(),
                     Signal::builder("toast")
                         .param_types([String::static_type()])
@@ -423,6 +974,39 @@ mod imp {
     impl BinImpl for EditorView {}
 
     impl EditorView {
+        /// Files that aren't UTF-8 are saved in their encoding until they're converted
+        pub(super) fn set_encoding(&self, encoding: TextEncoding) {
+            self.encoding.set(encoding);
+            if encoding != TextEncoding::Utf8 {
+                self.encoding_banner.set_title(
+                    &gettext("Opened as {encoding}, and saved in it until converted")
+                        .replace("{encoding}", &encoding.to_string()),
+                );
+            }
+            self.encoding_banner
+                .set_revealed(encoding != TextEncoding::Utf8);
+        }
+
+        /// Options of the format-document action. Unknown markers fall back to the defaults.
+        fn format_options(&self) -> FormatOptions {
+            let defaults = FormatOptions::default();
+            let marker = |value: &str, allowed: &str, default: char| {
+                value
+                    .chars()
+                    .next()
+                    .filter(|c| allowed.contains(*c))
+                    .unwrap_or(default)
+            };
+            FormatOptions {
+                bullet: marker(&self.format_bullet.borrow(), "-*+", defaults.bullet),
+                emphasis: marker(&self.format_emphasis.borrow(), "*_", defaults.emphasis),
+                wrap_column: self
+                    .format_rewrap
+                    .get()
+                    .then_some(self.wrap_column.get() as usize),
+            }
+        }
+
         pub(super) fn start_file_monitor(&self) {
             self.stop_file_monitor();
 
@@ -453,6 +1037,468 @@ mod imp {
             self.obj().height() - self.obj().font_size() as i32 * 2
         }
 
+        /// Save the image as a PNG in the assets folder and link it at the cursor
+        fn insert_pasted_image(&self, texture: &gtk::gdk::Texture) {
+            let obj = self.obj();
+            let path = obj.path();
+            let document_dir = path.parent().expect("Editor: document has no parent");
+            let assets_dir = document_dir.join(obj.assets_folder().trim());
+
+            if let Err(e) = std::fs::create_dir_all(&assets_dir) {
+                println!("{e}");
+                obj.emit_by_name::<()>("toast", &[&ScratchmarkError::FolderCreateFail.to_string()]);
+                return;
+            }
+            let timestamp = glib::DateTime::now_local()
+                .and_then(|now| now.format("%Y%m%d-%H%M%S"))
+                .map(|s| s.to_string())
+                .unwrap_or_default();
+            let image_path =
+                file_actions::incremented_path(assets_dir.join(format!("image-{timestamp}.png")));
+            if let Err(e) = texture.save_to_png(&image_path) {
+                println!("{e}");
+                obj.emit_by_name::<()>("toast", &[&ScratchmarkError::FileCreateFail.to_string()]);
+                return;
+            }
+
+            let buffer = self.buffer.get().unwrap();
+            buffer.delete_selection(true, true);
+            let destination = file_actions::markdown_link_destination(
+                &file_actions::relative_path(document_dir, &image_path),
+            );
+            buffer.insert_link(
+                &buffer.iter_at_mark(&buffer.get_insert()),
+                "",
+                &destination,
+                true,
+            );
+            self.source_view.scroll_mark_onscreen(&buffer.get_insert());
+        }
+
+        /// Insert links to the files at the drop location, relative to the document
+        fn insert_file_links(&self, paths: &[PathBuf], x: f64, y: f64) {
+            let obj = self.obj();
+            let path = obj.path();
+            let document_dir = path.parent().expect("Editor: document has no parent");
+            let buffer = self.buffer.get().unwrap();
+
+            let (bx, by) = self.source_view.window_to_buffer_coords(
+                gtk::TextWindowType::Widget,
+                x as i32,
+                y as i32,
+            );
+            let iter = self
+                .source_view
+                .iter_at_location(bx, by)
+                .unwrap_or_else(|| buffer.end_iter());
+            buffer.place_cursor(&iter);
+
+            buffer.begin_user_action();
+            for (i, file_path) in paths.iter().enumerate() {
+                if i > 0 {
+                    buffer.insert_at_cursor("\n");
+                }
+                let is_image = file_actions::is_image_file(file_path);
+                let title = if is_image {
+                    String::new()
+                } else {
+                    file_path
+                        .file_stem()
+                        .map(|stem| stem.to_string_lossy().into_owned())
+                        .unwrap_or_default()
+                };
+                let destination = file_actions::markdown_link_destination(
+                    &file_actions::relative_path(document_dir, file_path),
+                );
+                buffer.insert_link(
+                    &buffer.iter_at_mark(&buffer.get_insert()),
+                    &title,
+                    &destination,
+                    is_image,
+                );
+            }
+            buffer.end_user_action();
+            self.source_view.grab_focus();
+        }
+
+        /// Link checks and image previews touch the disk, and the style check, the linter, the
+        /// footnote check and the fold arrows read the whole text, so they wait until typing
+        /// pauses
+        pub(super) fn queue_delayed_refresh(&self) {
+            // Every change starts the wait over
+            if let Some(source) = self.delayed_refresh.take() {
+                source.remove();
+            }
+            let source = glib::timeout_add_local_once(
+                DELAYED_REFRESH_DELAY,
+                clone!(
+                    #[weak(rename_to = imp)]
+                    self,
+                    move || {
+                        imp.delayed_refresh.take();
+                        imp.buffer.get().unwrap().refresh_footnote_check();
+                        imp.refresh_folds();
+                        imp.refresh_link_check();
+                        imp.refresh_image_previews();
+                        imp.refresh_style_check();
+                        if imp.lint_config.borrow().is_some() {
+                            imp.refresh_lint();
+                        }
+                    }
+                ),
+            );
+            self.delayed_refresh.replace(Some(source));
+        }
+
+        fn refresh_link_check(&self) {
+            if let Some(buffer) = self.buffer.get() {
+                buffer.refresh_link_check(&self.obj().path());
+            }
+        }
+
+        /// Mark style issues and show how many there are in the sidebar.
+        /// The word lists follow the spell checker language.
+        pub(super) fn refresh_style_check(&self) {
+            let Some(buffer) = self.buffer.get() else {
+                return;
+            };
+            if !self.obj().style_check() {
+                buffer.clear_style_check();
+                self.stats_view.set_style_summary(None);
+                return;
+            }
+
+            let language = self
+                .checker
+                .get()
+                .and_then(|checker| checker.language())
+                .map(|language| language.to_string())
+                .or_else(|| {
+                    glib::language_names()
+                        .into_iter()
+                        .next()
+                        .map(|language| language.to_string())
+                })
+                .unwrap_or_default();
+            let folder = self
+                .style_words_folder
+                .get_or_init(file_actions::path_style_words);
+            let path = StyleWords::path(folder, &language);
+            let modified = |path: &Path| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+
+            let mut cached = self.style_words.borrow_mut();
+            let stale = cached.as_ref().is_none_or(|cached| {
+                cached.path != path
+                    || cached.modified.is_none()
+                    || cached.modified != modified(&path)
+            });
+            if stale {
+                let words = StyleWords::load(folder, &language).unwrap_or_else(|e| {
+                    println!("{e}");
+                    StyleWords::default()
+                });
+                *cached = Some(CachedStyleWords {
+                    modified: modified(&path),
+                    path,
+                    words,
+                });
+            }
+            let summary = buffer.refresh_style_check(&cached.as_ref().unwrap().words);
+            self.stats_view.set_style_summary(Some(&summary));
+        }
+
+        /// Fold or unfold the section of the heading on the line.
+        /// The cursor moves to the heading if it would be hidden.
+        fn toggle_fold(&self, line: usize) {
+            if self.buffer.get().unwrap().toggle_fold(line) {
+                self.on_folds_changed();
+            }
+        }
+
+        /// Keep the cursor out of folded sections and remember the folds
+        fn on_folds_changed(&self) {
+            let buffer = self.buffer.get().unwrap();
+            let cursor = buffer.iter_at_mark(&buffer.get_insert());
+            if let Some(section) = buffer.folded_section_at(cursor.line() as usize)
+                && let Some(mut heading) = buffer.iter_at_line(section.line as i32)
+            {
+                if !heading.ends_line() {
+                    heading.forward_to_line_end();
+                }
+                buffer.place_cursor(&heading);
+            }
+            self.refresh_line_marks();
+            self.obj().emit_by_name::<()>("folds-changed", &[]);
+        }
+
+        fn scroll_to_cursor(&self) {
+            let buffer = self.buffer.get().unwrap();
+            self.source_view
+                .scroll_to_mark(&buffer.get_insert(), 0.0, false, 0.0, 0.0);
+        }
+
+        /// Remember the table at the cursor as edited
+        pub(super) fn mark_edited_table(&self) {
+            let buffer = self.buffer.get().unwrap();
+            let cursor = buffer.iter_at_mark(&buffer.get_insert());
+            if let Some(mark) = self.edited_table.borrow().as_ref() {
+                buffer.move_mark(mark, &cursor);
+                return;
+            }
+            let mark = buffer.create_mark(None, &cursor, true);
+            self.edited_table.replace(Some(mark));
+        }
+
+        /// Whether the cursor is in the table that is being edited
+        fn edited_table_at_cursor(&self) -> bool {
+            let buffer = self.buffer.get().unwrap();
+            self.edited_table
+                .borrow()
+                .as_ref()
+                .is_some_and(|mark| buffer.cursor_in_table_at(&buffer.iter_at_mark(mark)))
+        }
+
+        /// Realign the edited table once the cursor is out of it. Changes made by undo and
+        /// redo are left as they are.
+        pub(super) fn realign_left_table(&self) {
+            let buffer = self.buffer.get().unwrap();
+            if buffer.history_action_in_progress()
+                || self.edited_table.borrow().is_none()
+                || self.edited_table_at_cursor()
+            {
+                return;
+            }
+            let mark = self.edited_table.take().unwrap();
+            // The cursor is still being moved, the text can change after that
+            glib::idle_add_local_once(clone!(
+                #[weak]
+                buffer,
+                move || {
+                    buffer.table_realign_at(&buffer.iter_at_mark(&mark));
+                    buffer.delete_mark(&mark);
+                }
+            ));
+        }
+
+        /// Unfold the sections around the cursor when it moves into one, like to a search result
+        pub(super) fn unfold_at_cursor(&self) {
+            let buffer = self.buffer.get().unwrap();
+            let cursor = buffer.iter_at_mark(&buffer.get_insert());
+            if buffer.is_folded_at(&cursor) && buffer.unfold_line(cursor.line() as usize) {
+                self.obj().emit_by_name::<()>("folds-changed", &[]);
+            }
+        }
+
+        pub(super) fn refresh_folds(&self) {
+            if let Some(buffer) = self.buffer.get() {
+                self.foldable.set(buffer.refresh_folds());
+                self.refresh_line_marks();
+            }
+        }
+
+        /// The gutter shows lint marks and fold arrows
+        fn refresh_line_marks(&self) {
+            self.source_view
+                .set_show_line_marks(self.lint_config.borrow().is_some() || self.foldable.get());
+        }
+
+        /// Lint with the current config, or remove the gutter marks if there is none
+        pub(super) fn refresh_lint(&self) {
+            let Some(buffer) = self.buffer.get() else {
+                return;
+            };
+            let diagnostics = match self.lint_config.borrow().as_ref() {
+                Some(config) => buffer.refresh_lint(config),
+                None => {
+                    buffer.clear_lint();
+                    Vec::new()
+                }
+            };
+            self.refresh_line_marks();
+            self.lint_diagnostics.replace(diagnostics);
+            self.obj().emit_by_name::<()>("lint-changed", &[]);
+        }
+
+        fn lint_tooltip(&self, mark: &sourceview5::Mark) -> String {
+            let line = self.source_view.buffer().iter_at_mark(mark).line() as usize;
+            self.lint_diagnostics
+                .borrow()
+                .iter()
+                .filter(|diagnostic| diagnostic.line == line)
+                .map(|diagnostic| {
+                    format!(
+                        "{}: {}",
+                        diagnostic.rule.id(),
+                        diagnostic.rule.description()
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        }
+
+        /// Show the first local image of each line below it. Previews that are still on their
+        /// line with the same image and size are kept.
+        pub(super) fn refresh_image_previews(&self) {
+            let Some(buffer) = self.buffer.get() else {
+                return;
+            };
+            let obj = self.obj();
+            if !obj.show_image_previews() {
+                for preview in self.image_previews.take() {
+                    self.remove_image_preview(buffer, &preview);
+                }
+                self.image_textures.borrow_mut().clear();
+                return;
+            }
+            let path = obj.path();
+            let document_dir = path.parent().expect("Editor: document has no parent");
+
+            let mut max_width = obj.max_width() as i32;
+            let text_width = self.source_view.width()
+                - self.source_view.left_margin()
+                - self.source_view.right_margin();
+            if text_width > 0 {
+                max_width = max_width.min(text_width);
+            }
+
+            let mut old_previews = self.image_previews.take();
+            let mut previews = Vec::new();
+            let mut image_paths = Vec::new();
+            let mut last_line = None;
+            for link in buffer.links() {
+                // One preview per line, for the first image
+                if !link.is_image || link.is_external() || last_line == Some(link.line) {
+                    continue;
+                }
+                let Some(image_path) =
+                    file_actions::link_target_path(document_dir, &link.destination)
+                else {
+                    continue;
+                };
+                let texture = self.image_texture(&image_path);
+                image_paths.push(image_path);
+                let Some(texture) = texture else {
+                    continue;
+                };
+                let Some(line_start) = buffer.iter_at_line(link.line as i32) else {
+                    continue;
+                };
+                last_line = Some(link.line);
+
+                let size = preview_size(
+                    texture.width(),
+                    texture.height(),
+                    max_width,
+                    IMAGE_PREVIEW_MAX_HEIGHT,
+                );
+                let mut line_end = line_start;
+                line_end.forward_to_line_end();
+
+                let kept = old_previews.iter().position(|preview| {
+                    let start = buffer.iter_at_mark(&preview.mark);
+                    start.starts_line()
+                        && start.line() == link.line as i32
+                        && preview.texture == texture
+                        && preview.size == size
+                });
+                if let Some(index) = kept {
+                    let preview = old_previews.swap_remove(index);
+                    // Edits can split the line or add text that the tag isn't on
+                    let (start, end) = buffer.bounds();
+                    buffer.remove_tag(&preview.tag, &start, &end);
+                    buffer.apply_tag(&preview.tag, &line_start, &line_end);
+                    preview.picture.set_tooltip_text(Some(&link.destination));
+                    previews.push(preview);
+                    continue;
+                }
+
+                let (width, height) = size;
+                let snapshot = gtk::Snapshot::new();
+                snapshot.append_texture(
+                    &texture,
+                    &gtk::graphene::Rect::new(0.0, 0.0, width as f32, height as f32),
+                );
+                let Some(paintable) = snapshot
+                    .to_paintable(Some(&gtk::graphene::Size::new(width as f32, height as f32)))
+                else {
+                    continue;
+                };
+                let picture = gtk::Picture::for_paintable(&paintable);
+                picture.set_tooltip_text(Some(&link.destination));
+                picture.set_can_target(false);
+
+                let tag = TextTag::builder()
+                    .pixels_below_lines(height + IMAGE_PREVIEW_SPACING * 2)
+                    .build();
+                buffer.tag_table().add(&tag);
+                buffer.apply_tag(&tag, &line_start, &line_end);
+
+                self.source_view.add_overlay(&picture, 0, 0);
+                previews.push(ImagePreview {
+                    picture,
+                    tag,
+                    mark: buffer.create_mark(None, &line_start, true),
+                    position: Cell::new((0, 0)),
+                    texture,
+                    size,
+                });
+            }
+            for preview in old_previews {
+                self.remove_image_preview(buffer, &preview);
+            }
+            self.image_previews.replace(previews);
+            // Images that aren't linked anymore don't stay in memory
+            self.image_textures
+                .borrow_mut()
+                .retain(|path, _| image_paths.contains(path));
+
+            // Line positions are known after the tags are laid out
+            glib::idle_add_local_once(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move || imp.position_image_previews()
+            ));
+        }
+
+        fn remove_image_preview(&self, buffer: &MarkdownBuffer, preview: &ImagePreview) {
+            self.source_view.remove(&preview.picture);
+            buffer.tag_table().remove(&preview.tag);
+            buffer.delete_mark(&preview.mark);
+        }
+
+        fn position_image_previews(&self) {
+            let Some(buffer) = self.buffer.get() else {
+                return;
+            };
+            for preview in self.image_previews.borrow().iter() {
+                let line_start = buffer.iter_at_mark(&preview.mark);
+                let mut line_end = line_start;
+                line_end.forward_to_line_end();
+                // The last wrapped line includes the space made by the tag
+                let (y, height) = self.source_view.line_yrange(&line_end);
+                let x = self.source_view.iter_location(&line_start).x();
+                let y = y + height - preview.tag.pixels_below_lines() + IMAGE_PREVIEW_SPACING;
+                if preview.position.replace((x, y)) != (x, y) {
+                    self.source_view.move_overlay(&preview.picture, x, y);
+                }
+            }
+        }
+
+        /// Load an image, or reuse it if the file hasn't changed since
+        fn image_texture(&self, path: &Path) -> Option<Texture> {
+            let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+            if let Some((cached_modified, texture)) = self.image_textures.borrow().get(path)
+                && *cached_modified == modified
+            {
+                return Some(texture.clone());
+            }
+            let texture = Texture::from_filename(path).ok()?;
+            self.image_textures
+                .borrow_mut()
+                .insert(path.to_owned(), (modified, texture.clone()));
+            Some(texture)
+        }
+
         fn refresh_font(&self) {
             let obj = self.obj();
             self.source_view
@@ -502,6 +1548,19 @@ mod imp {
             self.scrolled_window.vadjustment().set_value(scroll_value);
         }
 
+        /// Apply the variant of the chosen scheme that matches the app style
+        fn refresh_style_scheme(&self) {
+            let Some(buffer) = self.buffer.get() else {
+                return;
+            };
+            let id = self.style_scheme.borrow().clone();
+            let dark = adw::StyleManager::default().is_dark();
+            match style_scheme::resolve_style_scheme(&StyleSchemeManager::default(), &id, dark) {
+                Some(scheme) => buffer.set_style_scheme(Some(&scheme)),
+                None => println!("EditorView: Failed to load scheme with id '{id}'."),
+            }
+        }
+
         fn refresh_vertical_margins(&self) {
             let old_top_margin = self.source_view.top_margin();
             let scroll_margin = self.scroll_margin();
@@ -529,6 +1588,17 @@ mod imp {
             });
         }
     }
+
+    /// Scale down to fit the bounds, keeping the aspect ratio
+    fn preview_size(width: i32, height: i32, max_width: i32, max_height: i32) -> (i32, i32) {
+        let scale = (max_width as f64 / width as f64)
+            .min(max_height as f64 / height as f64)
+            .min(1.0);
+        (
+            ((width as f64 * scale) as i32).max(1),
+            ((height as f64 * scale) as i32).max(1),
+        )
+    }
 }
 
 use std::path::PathBuf;
@@ -547,7 +1617,11 @@ use sourceview5::SearchContext;
 use sourceview5::SearchSettings;
 use sourceview5::prelude::*;
 
+use crate::data::BrokenLink;
 use crate::data::DocumentStats;
+use crate::data::LinkEdit;
+use crate::data::LintConfig;
+use crate::data::LintDiagnostic;
 use crate::data::MarkdownBuffer;
 use crate::error::ScratchmarkError;
 use crate::util::file_actions;
@@ -562,7 +1636,7 @@ glib::wrapper! {
 impl EditorView {
     pub fn new(path: PathBuf) -> Result<Self, ScratchmarkError> {
         let file = gtk::gio::File::for_path(&path);
-        let text = file_actions::read_file_to_string(&file)?;
+        let (text, encoding) = file_actions::read_text_file(&file)?;
         let buffer = MarkdownBuffer::default().with_style_scheme("scratchmark");
         buffer.set_text(&text);
 
@@ -576,9 +1650,15 @@ impl EditorView {
         let checker = Checker::default();
         let adapter = TextBufferAdapter::new(&buffer.clone().upcast::<Buffer>(), &checker);
         imp.adapter.set(adapter.clone()).unwrap();
+        checker.connect_language_notify(clone!(
+            #[weak]
+            obj,
+            move |_| obj.imp().refresh_style_check()
+        ));
         imp.checker.set(checker).unwrap();
         imp.file.replace(Some(file));
         imp.path.replace(Some(path));
+        imp.set_encoding(encoding);
         imp.source_view.set_monospace(true);
         imp.source_view.set_buffer(Some(&buffer));
         imp.search_bar.set_search_context(search_context);
@@ -591,6 +1671,9 @@ impl EditorView {
             .bidirectional()
             .sync_create()
             .build();
+        obj.bind_property("smart_typography", &buffer, "smart_typography")
+            .sync_create()
+            .build();
         buffer.connect_changed(clone!(
             #[weak]
             obj,
@@ -608,7 +1691,20 @@ impl EditorView {
                 buffer.open_paste();
             }
         ));
+        buffer.connect_mark_set(clone!(
+            #[weak]
+            obj,
+            move |buffer, _, mark| {
+                if *mark == buffer.get_insert() {
+                    obj.imp().unfold_at_cursor();
+                    obj.imp().realign_left_table();
+                }
+            }
+        ));
         obj.refresh_document_stats(&buffer);
+        buffer.refresh_footnote_check();
+        imp.refresh_folds();
+        imp.queue_delayed_refresh();
         Ok(obj)
     }
 
@@ -618,13 +1714,13 @@ impl EditorView {
             return Err(ScratchmarkError::FileChanged);
         }
 
-        self.stop_file_monitor();
-
         let buffer = imp.source_view.buffer();
         let start = buffer.start_iter();
         let end = buffer.end_iter();
         let text = buffer.text(&start, &end, true).to_string();
-        let bytes = text.as_bytes();
+        let bytes = file_actions::encode_text(&text, imp.encoding.get())?;
+
+        self.stop_file_monitor();
         {
             let Some(ref mut file) = *imp.file.borrow_mut() else {
                 panic!("Editor file uninitialized");
@@ -635,7 +1731,7 @@ impl EditorView {
                 .unwrap();
 
             output_stream
-                .write_all(bytes, None::<&Cancellable>)
+                .write_all(&bytes, None::<&Cancellable>)
                 .unwrap();
             output_stream.flush(None::<&Cancellable>).unwrap();
         }
@@ -656,6 +1752,7 @@ impl EditorView {
         imp.file.replace(Some(file));
         imp.path.replace(Some(path));
         imp.start_file_monitor();
+        imp.queue_delayed_refresh();
     }
 
     /// For preventing "file changed" banner when renaming the file or such.
@@ -675,6 +1772,37 @@ impl EditorView {
         source_view.scroll_to_iter(&mut iter, 0., false, 0., 0.);
     }
 
+    /// Headings of the folded sections
+    pub fn folded_headings(&self) -> Vec<String> {
+        self.imp().buffer.get().unwrap().folded_headings()
+    }
+
+    /// Fold the sections of the headings, like they were when the document was last open
+    pub fn fold_headings(&self, headings: &[String]) {
+        let imp = self.imp();
+        imp.buffer.get().unwrap().fold_headings(headings);
+        imp.refresh_folds();
+    }
+
+    /// Put the cursor at the start of the line and bring it into view
+    pub fn jump_to_line(&self, line: i32) {
+        let source_view: EditorTextView = self.imp().source_view.get();
+        let buffer = source_view.buffer();
+        let Some(iter) = buffer.iter_at_line(line) else {
+            return;
+        };
+        buffer.place_cursor(&iter);
+        source_view.grab_focus();
+        // A freshly loaded document isn't laid out yet
+        glib::idle_add_local_once(clone!(
+            #[weak]
+            source_view,
+            move || {
+                source_view.scroll_to_mark(&source_view.buffer().get_insert(), 0.0, true, 0.0, 0.3);
+            }
+        ));
+    }
+
     pub fn scroll_to_top(&self) {
         let vadjustment = self.imp().scrolled_window.vadjustment();
         vadjustment.set_value(vadjustment.lower());
@@ -683,6 +1811,35 @@ impl EditorView {
             .set_vadjustment(Some(&vadjustment));
     }
 
+    /// The current text, including unsaved changes
+    pub fn text(&self) -> String {
+        let buffer = self.imp().source_view.buffer();
+        let (start, end) = buffer.bounds();
+        buffer.text(&start, &end, true).to_string()
+    }
+
+    pub fn apply_link_edits(&self, edits: &[LinkEdit]) {
+        self.imp().buffer.get().unwrap().apply_link_edits(edits);
+    }
+
+    /// Broken links in the current text, including unsaved changes
+    pub fn broken_links(&self) -> Vec<BrokenLink> {
+        let buffer = self.imp().buffer.get().unwrap();
+        buffer.broken_links(&self.path())
+    }
+
+    /// Lint the document while typing, with gutter marks on the lines with problems.
+    /// `None` stops linting.
+    pub fn set_lint_config(&self, config: Option<LintConfig>) {
+        let imp = self.imp();
+        imp.lint_config.replace(config);
+        imp.refresh_lint();
+    }
+
+    pub fn lint_diagnostics(&self) -> Vec<LintDiagnostic> {
+        self.imp().lint_diagnostics.borrow().clone()
+    }
+
     pub fn max_width_px(&self) -> i32 {
         self.imp().source_view_clamp.maximum_size()
     }
@@ -696,7 +1853,11 @@ impl EditorView {
     }
 
     fn on_buffer_changed(&self, buffer: &MarkdownBuffer) {
+        if !buffer.history_action_in_progress() && buffer.cursor_in_table() {
+            self.imp().mark_edited_table();
+        }
         self.refresh_document_stats(buffer);
+        self.imp().queue_delayed_refresh();
         self.set_unsaved_changes(true);
         self.emit_by_name::<()>("touched", &[]);
     }
@@ -0,0 +1,104 @@
+mod imp {
+    use gtk::gdk;
+    use gtk::gio;
+    use gtk::glib;
+    use gtk::prelude::*;
+    use sourceview5::HoverContext;
+    use sourceview5::HoverDisplay;
+    use sourceview5::subclass::prelude::*;
+
+    use gettextrs::gettext;
+
+    use crate::data::math_at;
+    use crate::util::math_render::render_math;
+
+    #[derive(Default)]
+    pub struct MathHoverProvider {}
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MathHoverProvider {
+        const NAME: &'static str = "MathHoverProvider";
+        type Type = super::MathHoverProvider;
+        type Interfaces = (sourceview5::HoverProvider,);
+    }
+
+    impl ObjectImpl for MathHoverProvider {}
+
+    impl HoverProviderImpl for MathHoverProvider {
+        /// Render the formula under the pointer
+        fn populate(
+            &self,
+            context: &HoverContext,
+            display: &HoverDisplay,
+        ) -> Result<(), glib::Error> {
+            let not_found = || glib::Error::new(gio::IOErrorEnum::NotFound, "No math here");
+            let iter = context.iter().ok_or_else(not_found)?;
+            let buffer = iter.buffer();
+            let text = buffer.text(&buffer.start_iter(), &buffer.end_iter(), true);
+            let offset = text
+                .char_indices()
+                .nth(iter.offset() as usize)
+                .map_or(text.len(), |(i, _)| i);
+            let math = math_at(&text, offset).ok_or_else(not_found)?;
+            if math.tex.trim().is_empty() {
+                return Err(not_found());
+            }
+
+            let color = display.color();
+            let color = [color.red(), color.green(), color.blue()].map(|c| (c * 255.0) as u8);
+            let scale = display.scale_factor();
+            match render_math(math.tex, math.display, color, scale as f32) {
+                Some(image) => {
+                    let texture = gdk::MemoryTexture::new(
+                        image.width as i32,
+                        image.height as i32,
+                        gdk::MemoryFormat::R8g8b8a8Premultiplied,
+                        &glib::Bytes::from_owned(image.pixels),
+                        image.width as usize * 4,
+                    );
+                    let picture = gtk::Picture::builder()
+                        .paintable(&texture)
+                        .width_request(image.width as i32 / scale)
+                        .height_request(image.height as i32 / scale)
+                        .margin_top(6)
+                        .margin_bottom(6)
+                        .margin_start(6)
+                        .margin_end(6)
+                        .build();
+                    display.append(&picture);
+                }
+                None => {
+                    let source = gtk::Label::builder()
+                        .label(math.tex.trim())
+                        .selectable(true)
+                        .wrap(true)
+                        .css_classes(["monospace"])
+                        .build();
+                    display.append(&source);
+                    let note = gtk::Label::builder()
+                        .label(gettext("This formula can't be previewed"))
+                        .wrap(true)
+                        .css_classes(["caption", "dim-label"])
+                        .build();
+                    display.append(&note);
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+use gtk::glib;
+use gtk::glib::Object;
+
+glib::wrapper! {
+    /// Shows rendered math when hovering `$formulas$`
+    pub struct MathHoverProvider(ObjectSubclass<imp::MathHoverProvider>)
+        @implements sourceview5::HoverProvider;
+}
+
+impl Default for MathHoverProvider {
+    fn default() -> Self {
+        Object::builder().build()
+    }
+}
@@ -1,6 +1,7 @@
 mod document_stats_view;
 mod editor_view;
 mod file_changed_on_disk_dialog;
+mod math_hover;
 mod minimap;
 mod search_bar;
 mod text_view;
@@ -1,14 +1,30 @@
 mod imp {
     use adw::subclass::prelude::*;
     use gtk::CssProvider;
+    use gtk::DeleteType;
+    use gtk::EventControllerKey;
+    use gtk::MovementStep;
+    use gtk::PropagationPhase;
     use gtk::TextIter;
     use gtk::TextView;
+    use gtk::gdk::Key;
+    use gtk::gdk::ModifierType;
+    use gtk::gdk::Texture;
+    use gtk::gio::Cancellable;
     use gtk::glib;
     use gtk::glib::Properties;
     use gtk::glib::clone;
+    use gtk::glib::subclass::Signal;
     use gtk::prelude::*;
+    use sourceview5::VimIMContext;
     use sourceview5::subclass::prelude::*;
     use std::cell::Cell;
+    use std::cell::RefCell;
+    use std::sync::OnceLock;
+
+    use crate::data::FocusScope;
+    use crate::data::KeybindingMode;
+    use crate::data::MarkdownBuffer;
 
     const TYPEWRITER_DIM_TAG: &str = "typewriter-dim";
 
@@ -17,8 +33,27 @@ mod imp {
     pub struct EditorTextView {
         #[property(get, set)]
         typewriter_mode: Cell<bool>,
+        /// Name of the [FocusScope] that stays bright in typewriter mode
+        #[property(get, set)]
+        focus_scope: RefCell<String>,
+        /// Name of the [KeybindingMode]
+        #[property(get, set)]
+        keybindings: RefCell<String>,
+        /// Vim mode, or the command being typed
+        #[property(get)]
+        vim_status: RefCell<String>,
+        /// Keys of a Vim command that isn't complete yet
+        #[property(get)]
+        vim_pending: RefCell<String>,
 
         pub(super) source_view_css_provider: CssProvider,
+        key_controller: RefCell<Option<EventControllerKey>>,
+        /// Emacs: movement extends the selection
+        emacs_mark: Cell<bool>,
+        /// Emacs: C-x was pressed
+        emacs_prefix: Cell<bool>,
+        /// Start and end of the text that isn't dimmed in typewriter mode
+        focus_marks: RefCell<Option<(gtk::TextMark, gtk::TextMark)>>,
     }
 
     #[glib::object_subclass]
@@ -50,9 +85,27 @@ mod imp {
                 obj.imp().setup_typewriter_dimming();
             });
             obj.connect_typewriter_mode_notify(move |obj| {
-                obj.imp().update_typewriter_dimming();
+                obj.imp().refresh_typewriter_dimming();
+            });
+            obj.connect_focus_scope_notify(move |obj| {
+                obj.imp().refresh_typewriter_dimming();
             });
             obj.imp().setup_typewriter_dimming();
+
+            obj.connect_keybindings_notify(move |obj| {
+                obj.imp().update_keybindings();
+            });
+        }
+
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| {
+                vec![
+                    Signal::builder("image-pasted")
+                        .param_types([Texture::static_type()])
+                        .build(),
+                ]
+            })
         }
     }
 
@@ -98,6 +151,39 @@ mod imp {
         }
 
         fn paste_clipboard(&self) {
+            let obj = self.obj();
+            let clipboard = obj.clipboard();
+            let formats = clipboard.formats();
+            let has_text = formats.contain_gtype(glib::Type::STRING)
+                || formats.contain_mime_type("text/plain")
+                || formats.contain_mime_type("text/plain;charset=utf-8");
+            let has_image = formats.contain_gtype(Texture::static_type())
+                || formats
+                    .mime_types()
+                    .iter()
+                    .any(|mime| mime.starts_with("image/"));
+            if has_image && !has_text {
+                // No text gets pasted, so paste_done won't reset this
+                if let Ok(buffer) = obj.buffer().downcast::<MarkdownBuffer>() {
+                    buffer.set_paste_in_progress(false);
+                }
+                clipboard.read_texture_async(
+                    None::<&Cancellable>,
+                    clone!(
+                        #[weak]
+                        obj,
+                        move |result| match result {
+                            Ok(Some(texture)) => {
+                                obj.emit_by_name::<()>("image-pasted", &[&texture]);
+                            }
+                            Ok(None) => (),
+                            Err(e) => println!("Couldn't read image from clipboard: {e}"),
+                        }
+                    ),
+                );
+                return;
+            }
+
             glib::idle_add_local_once(clone!(
                 #[weak(rename_to = imp)]
                 self,
@@ -110,27 +196,260 @@ mod imp {
     impl ViewImpl for EditorTextView {}
 
     impl EditorTextView {
-        fn update_typewriter_dimming(&self) {
-            let buffer = TextViewExt::buffer(self.obj().upcast_ref::<TextView>());
+        fn update_keybindings(&self) {
+            let obj = self.obj();
+            if let Some(controller) = self.key_controller.take() {
+                obj.remove_controller(&controller);
+            }
+            self.emacs_mark.set(false);
+            self.emacs_prefix.set(false);
+            self.set_vim_status("", "");
 
-            if !self.obj().typewriter_mode() {
-                let (start, end) = buffer.bounds();
-                buffer.remove_tag_by_name(TYPEWRITER_DIM_TAG, &start, &end);
-                return;
+            let mode =
+                KeybindingMode::try_from(self.keybindings.borrow().as_str()).unwrap_or_default();
+            let controller = match mode {
+                KeybindingMode::Default => return,
+                KeybindingMode::Vim => self.vim_controller(),
+                KeybindingMode::Emacs => {
+                    let controller = EventControllerKey::new();
+                    controller.connect_key_pressed(clone!(
+                        #[weak(rename_to = imp)]
+                        self,
+                        #[upgrade_or]
+                        glib::Propagation::Proceed,
+                        move |_, key, _, state| imp.emacs_key_pressed(key, state)
+                    ));
+                    controller
+                }
+            };
+            controller.set_propagation_phase(PropagationPhase::Capture);
+            obj.add_controller(controller.clone());
+            self.key_controller.replace(Some(controller));
+        }
+
+        fn vim_controller(&self) -> EventControllerKey {
+            let obj = self.obj();
+            let context = VimIMContext::new();
+            let controller = EventControllerKey::new();
+            controller.set_im_context(Some(&context));
+            context.set_client_widget(Some(&*obj));
+
+            let update_status = clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move |context: &VimIMContext| {
+                    imp.set_vim_status(
+                        &context
+                            .property::<Option<String>>("command-bar-text")
+                            .unwrap_or_default(),
+                        &context
+                            .property::<Option<String>>("command-text")
+                            .unwrap_or_default(),
+                    );
+                }
+            );
+            context.connect_command_bar_text_notify(update_status.clone());
+            context.connect_command_text_notify(update_status);
+
+            context.connect_execute_command(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                #[upgrade_or]
+                false,
+                move |_, command| imp.run_vim_command(command)
+            ));
+            controller
+        }
+
+        fn set_vim_status(&self, status: &str, pending: &str) {
+            let obj = self.obj();
+            if *self.vim_status.borrow() != status {
+                self.vim_status.replace(status.to_owned());
+                obj.notify_vim_status();
             }
+            if *self.vim_pending.borrow() != pending {
+                self.vim_pending.replace(pending.to_owned());
+                obj.notify_vim_pending();
+            }
+        }
 
-            let insert = buffer.get_insert();
-            let iter = buffer.iter_at_mark(&insert);
-            let line = iter.line();
+        /// Run ex commands and searches with the app's actions. Returns false to let Vim handle them.
+        fn run_vim_command(&self, command: &str) -> bool {
+            let obj = self.obj();
+            if let Some(text) = command.strip_prefix('/').or(command.strip_prefix('?')) {
+                obj.activate_action("editor.show-search-with-text", Some(&text.to_variant()))
+                    .is_ok()
+            } else {
+                match command.trim() {
+                    ":w" | ":write" => obj.activate_action("win.file-save", None).is_ok(),
+                    ":q" | ":quit" | ":q!" => {
+                        // Closing the document destroys this view
+                        glib::idle_add_local_once(clone!(
+                            #[weak]
+                            obj,
+                            move || {
+                                let _ = obj.activate_action("win.file-close", None);
+                            }
+                        ));
+                        true
+                    }
+                    ":wq" | ":x" => {
+                        if obj.activate_action("win.file-save", None).is_err() {
+                            return false;
+                        }
+                        glib::idle_add_local_once(clone!(
+                            #[weak]
+                            obj,
+                            move || {
+                                let _ = obj.activate_action("win.file-close", None);
+                            }
+                        ));
+                        true
+                    }
+                    _ => false,
+                }
+            }
+        }
+
+        fn emacs_key_pressed(&self, key: Key, state: ModifierType) -> glib::Propagation {
+            let obj = self.obj();
+            let ctrl = state.contains(ModifierType::CONTROL_MASK);
+            let alt = state.contains(ModifierType::ALT_MASK);
+            let key = key.to_lower();
+
+            if self.emacs_prefix.replace(false) {
+                if is_modifier(key) {
+                    self.emacs_prefix.set(true);
+                    return glib::Propagation::Proceed;
+                }
+                let action = match (ctrl, key) {
+                    (true, Key::s) => Some("win.file-save"),
+                    (false, Key::k) => Some("win.file-close"),
+                    (false, Key::h) => {
+                        obj.emit_select_all(true);
+                        None
+                    }
+                    (false, Key::u) => {
+                        obj.buffer().undo();
+                        None
+                    }
+                    _ => None,
+                };
+                if let Some(action) = action {
+                    let _ = obj.activate_action(action, None);
+                }
+                return glib::Propagation::Stop;
+            }
+
+            let extend = self.emacs_mark.get();
+            let move_cursor = |step: MovementStep, count: i32| {
+                obj.emit_move_cursor(step, count, extend);
+            };
+            match (ctrl, alt, key) {
+                (true, false, Key::f) => move_cursor(MovementStep::VisualPositions, 1),
+                (true, false, Key::b) => move_cursor(MovementStep::VisualPositions, -1),
+                (true, false, Key::n) => move_cursor(MovementStep::DisplayLines, 1),
+                (true, false, Key::p) => move_cursor(MovementStep::DisplayLines, -1),
+                (true, false, Key::a) => move_cursor(MovementStep::ParagraphEnds, -1),
+                (true, false, Key::e) => move_cursor(MovementStep::ParagraphEnds, 1),
+                (true, false, Key::v) => move_cursor(MovementStep::Pages, 1),
+                (false, true, Key::f) => move_cursor(MovementStep::Words, 1),
+                (false, true, Key::b) => move_cursor(MovementStep::Words, -1),
+                (false, true, Key::v) => move_cursor(MovementStep::Pages, -1),
+                (false, true, Key::less) => move_cursor(MovementStep::BufferEnds, -1),
+                (false, true, Key::greater) => move_cursor(MovementStep::BufferEnds, 1),
+                (true, false, Key::d) => obj.emit_delete_from_cursor(DeleteType::Chars, 1),
+                (false, true, Key::d) => obj.emit_delete_from_cursor(DeleteType::WordEnds, 1),
+                (true, false, Key::k) => {
+                    // Kill to the end of the line, or the line break when there's nothing left
+                    let buffer = obj.buffer();
+                    let start = buffer.iter_at_mark(&buffer.get_insert());
+                    let mut end = start;
+                    if start.ends_line() {
+                        end.forward_char();
+                    } else {
+                        end.forward_to_line_end();
+                    }
+                    buffer.select_range(&start, &end);
+                    obj.emit_cut_clipboard();
+                }
+                (true, false, Key::w) => {
+                    self.emacs_mark.set(false);
+                    obj.emit_cut_clipboard();
+                }
+                (false, true, Key::w) => {
+                    self.emacs_mark.set(false);
+                    obj.emit_copy_clipboard();
+                    let buffer = obj.buffer();
+                    buffer.place_cursor(&buffer.iter_at_mark(&buffer.get_insert()));
+                }
+                (true, false, Key::y) => obj.emit_paste_clipboard(),
+                (true, false, Key::space) => {
+                    let buffer = obj.buffer();
+                    buffer.place_cursor(&buffer.iter_at_mark(&buffer.get_insert()));
+                    self.emacs_mark.set(true);
+                }
+                (true, false, Key::g) => {
+                    self.emacs_mark.set(false);
+                    let buffer = obj.buffer();
+                    buffer.place_cursor(&buffer.iter_at_mark(&buffer.get_insert()));
+                }
+                (true, false, Key::slash) => obj.buffer().undo(),
+                (true, false, Key::s) | (true, false, Key::r) => {
+                    let _ = obj.activate_action("editor.show-search", None);
+                }
+                (true, false, Key::x) => self.emacs_prefix.set(true),
+                _ => return glib::Propagation::Proceed,
+            }
+            glib::Propagation::Stop
+        }
 
+        /// Dim the whole text again, then brighten the focus
+        fn refresh_typewriter_dimming(&self) {
+            let buffer = TextViewExt::buffer(self.obj().upcast_ref::<TextView>());
             let (start, end) = buffer.bounds();
-            buffer.apply_tag_by_name(TYPEWRITER_DIM_TAG, &start, &end);
+            if self.obj().typewriter_mode() {
+                buffer.apply_tag_by_name(TYPEWRITER_DIM_TAG, &start, &end);
+            } else {
+                buffer.remove_tag_by_name(TYPEWRITER_DIM_TAG, &start, &end);
+            }
+            if let Some((start_mark, end_mark)) = self.focus_marks.take() {
+                buffer.delete_mark(&start_mark);
+                buffer.delete_mark(&end_mark);
+            }
+            self.update_typewriter_dimming();
+        }
+
+        /// Move the focus to the cursor. Only the old and the new focus are retagged.
+        fn update_typewriter_dimming(&self) {
+            if !self.obj().typewriter_mode() {
+                return;
+            }
+            let buffer = TextViewExt::buffer(self.obj().upcast_ref::<TextView>());
+            let iter = buffer.iter_at_mark(&buffer.get_insert());
+            let scope =
+                FocusScope::try_from(self.focus_scope.borrow().as_str()).unwrap_or_default();
+            let (start, end) = match buffer.downcast_ref::<MarkdownBuffer>() {
+                Some(buffer) => buffer.focus_range(&iter, scope),
+                None => line_bounds(&buffer, iter.line()).unwrap_or((iter, iter)),
+            };
 
-            if let Some(line_start) = buffer.iter_at_line(line) {
-                let mut line_end = line_start;
-                line_end.forward_to_line_end();
-                buffer.remove_tag_by_name(TYPEWRITER_DIM_TAG, &line_start, &line_end);
+            let mut focus_marks = self.focus_marks.borrow_mut();
+            if let Some((start_mark, end_mark)) = focus_marks.as_ref() {
+                let old_start = buffer.iter_at_mark(start_mark);
+                let old_end = buffer.iter_at_mark(end_mark);
+                if old_start != start || old_end != end {
+                    buffer.apply_tag_by_name(TYPEWRITER_DIM_TAG, &old_start, &old_end);
+                }
+                buffer.move_mark(start_mark, &start);
+                buffer.move_mark(end_mark, &end);
+            } else {
+                *focus_marks = Some((
+                    buffer.create_mark(None, &start, true),
+                    buffer.create_mark(None, &end, false),
+                ));
             }
+            buffer.remove_tag_by_name(TYPEWRITER_DIM_TAG, &start, &end);
         }
 
         fn setup_typewriter_dimming(&self) {
@@ -145,8 +464,8 @@ mod imp {
                 .build();
             tag_table.add(&dim_tag);
 
-            let (start, end) = buffer.bounds();
-            buffer.apply_tag(&dim_tag, &start, &end);
+            // The marks belonged to the previous buffer
+            self.focus_marks.take();
 
             buffer.connect_cursor_position_notify(clone!(
                 #[weak(rename_to = imp)]
@@ -155,10 +474,47 @@ mod imp {
                     imp.update_typewriter_dimming();
                 }
             ));
-            self.update_typewriter_dimming();
+            // Inserted text has no tags, so it's dimmed unless it's in the focus.
+            // This runs after the text is inserted, when the iter is at its end.
+            buffer.connect_local(
+                "insert-text",
+                true,
+                clone!(
+                    #[weak(rename_to = imp)]
+                    self,
+                    #[upgrade_or_default]
+                    move |args| {
+                        let buffer = args[0].get::<gtk::TextBuffer>().ok()?;
+                        let end = args[1].get::<TextIter>().ok()?;
+                        let text = args[2].get::<String>().ok()?;
+                        if imp.obj().typewriter_mode() {
+                            let mut start = end;
+                            start.backward_chars(text.chars().count() as i32);
+                            buffer.apply_tag_by_name(TYPEWRITER_DIM_TAG, &start, &end);
+                            imp.update_typewriter_dimming();
+                        }
+                        None
+                    }
+                ),
+            );
+            self.refresh_typewriter_dimming();
         }
     }
 
+    fn is_modifier(key: Key) -> bool {
+        matches!(
+            key,
+            Key::Control_L
+                | Key::Control_R
+                | Key::Shift_L
+                | Key::Shift_R
+                | Key::Alt_L
+                | Key::Alt_R
+                | Key::Meta_L
+                | Key::Meta_R
+        )
+    }
+
     fn line_bounds(buf: &gtk::TextBuffer, line: i32) -> Option<(TextIter, TextIter)> {
         let start = buf.iter_at_line(line)?;
         let mut end = buf.iter_at_line(line + 1).unwrap_or_else(|| buf.end_iter());
@@ -4,26 +4,41 @@ mod imp {
     use std::sync::OnceLock;
 
     use adw::subclass::prelude::*;
+    use gettextrs::gettext;
     use gtk::Button;
     use gtk::CompositeTemplate;
+    use gtk::DropDown;
     use gtk::Entry;
+    use gtk::FileLauncher;
+    use gtk::StringList;
     use gtk::TemplateChild;
+    use gtk::gio;
     use gtk::glib;
     use gtk::glib::clone;
     use gtk::glib::property::PropertySet;
     use gtk::glib::subclass::*;
     use gtk::prelude::*;
 
+    use crate::data::Template;
+    use crate::util::file_actions;
+
     #[derive(CompositeTemplate, Default)]
     #[template(resource = "/org/scratchmark/Scratchmark/ui/library/document_create_popover.ui")]
     pub struct DocumentCreatePopover {
         #[template_child]
         name_entry: TemplateChild<Entry>,
         #[template_child]
+        template_dropdown: TemplateChild<DropDown>,
+        #[template_child]
+        templates_folder_button: TemplateChild<Button>,
+        #[template_child]
         commit_button: TemplateChild<Button>,
 
         pub(super) parent_path: OnceLock<PathBuf>,
+        pub(super) project_path: OnceLock<PathBuf>,
         pub(super) filename: RefCell<Option<PathBuf>>,
+        /// Templates in the dropdown, after the blank entry
+        pub(super) templates: RefCell<Vec<Template>>,
     }
 
     #[glib::object_subclass]
@@ -67,6 +82,24 @@ mod imp {
                 }
             ));
 
+            self.templates_folder_button.connect_clicked(|_| {
+                let file = gio::File::for_path(file_actions::path_user_templates());
+                FileLauncher::new(Some(&file)).launch(
+                    None::<&gtk::Window>,
+                    None::<&gio::Cancellable>,
+                    |_| {},
+                );
+            });
+
+            // Templates may have been added since the last time
+            self.obj().connect_show(clone!(
+                #[weak (rename_to = imp)]
+                self,
+                move |_| {
+                    imp.refresh_templates();
+                }
+            ));
+
             self.parent_constructed();
         }
 
@@ -114,6 +147,32 @@ mod imp {
             }
         }
 
+        fn refresh_templates(&self) {
+            let selected_name = self.obj().selected_template().map(|template| template.name);
+            let templates = Template::available(self.project_path.get().unwrap());
+
+            let model = StringList::new(&[gettext("Blank Document").as_str()]);
+            for template in &templates {
+                model.append(&template.name);
+            }
+            let selected = templates
+                .iter()
+                .position(|template| Some(&template.name) == selected_name.as_ref())
+                .map(|i| i + 1)
+                .unwrap_or(0);
+            self.templates.replace(templates);
+            self.template_dropdown.set_model(Some(&model));
+            self.template_dropdown.set_selected(selected as u32);
+        }
+
+        pub(super) fn selected_template(&self) -> Option<Template> {
+            let selected = self.template_dropdown.selected() as usize;
+            self.templates
+                .borrow()
+                .get(selected.checked_sub(1)?)
+                .cloned()
+        }
+
         fn commit(&self) {
             let Some(filename) = self.filename.borrow().clone() else {
                 return;
@@ -130,6 +189,8 @@ use adw::subclass::prelude::*;
 use gtk::glib;
 use gtk::glib::Object;
 
+use crate::data::Template;
+
 glib::wrapper! {
     pub struct DocumentCreatePopover(ObjectSubclass<imp::DocumentCreatePopover>)
         @extends gtk::Popover, gtk::Widget,
@@ -137,9 +198,16 @@ glib::wrapper! {
 }
 
 impl DocumentCreatePopover {
-    pub fn new(parent_path: PathBuf) -> Self {
+    /// Templates are looked up in the project at `project_path`, and in the user's folder
+    pub fn new(parent_path: PathBuf, project_path: PathBuf) -> Self {
         let obj: DocumentCreatePopover = Object::builder().build();
         obj.imp().parent_path.set(parent_path).unwrap();
+        obj.imp().project_path.set(project_path).unwrap();
         obj
     }
+
+    /// The template picked for the new document. None for a blank document.
+    pub fn selected_template(&self) -> Option<Template> {
+        self.imp().selected_template()
+    }
 }
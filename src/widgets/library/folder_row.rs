@@ -169,7 +169,8 @@ mod imp {
 
         pub(super) fn setup_document_create_menu(&self) {
             let obj = self.obj();
-            let popover = DocumentCreatePopover::new(self.folder().path());
+            let popover =
+                DocumentCreatePopover::new(self.folder().path(), self.folder().project_path());
             popover.set_parent(&*obj);
             popover.connect_closure(
                 "committed",
@@ -177,8 +178,14 @@ mod imp {
                 closure_local!(
                     #[weak(rename_to = imp)]
                     self,
-                    move |_: DocumentCreatePopover, name: PathBuf| {
-                        if let Err(e) = imp.folder().create_document(name) {
+                    move |popover: DocumentCreatePopover, name: PathBuf| {
+                        let result = match popover.selected_template() {
+                            Some(template) => {
+                                imp.folder().create_document_from_template(name, &template)
+                            }
+                            None => imp.folder().create_document(name),
+                        };
+                        if let Err(e) = result {
                             imp.folder().notify(&e.to_string())
                         }
                     }
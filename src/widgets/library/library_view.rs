@@ -597,6 +597,27 @@ impl LibraryView {
         }
     }
 
+    /// Root of the project that contains the path
+    pub fn project_path_of(&self, path: &Path) -> Option<PathBuf> {
+        self.projects()
+            .values()
+            .map(|project_view| project_view.project().path())
+            .find(|project_path| path.starts_with(project_path))
+    }
+
+    /// All documents in the project that contains the path, sorted by path
+    pub fn project_documents(&self, path: &Path) -> Vec<PathBuf> {
+        for project_view in self.projects().values() {
+            let project = project_view.project();
+            if path.starts_with(project.path()) {
+                let mut documents: Vec<PathBuf> = project.documents().keys().cloned().collect();
+                documents.sort();
+                return documents;
+            }
+        }
+        Vec::new()
+    }
+
     pub fn add_project(&self, path: PathBuf) {
         self.imp().add_project(path);
     }
@@ -0,0 +1,87 @@
+mod imp {
+    use adw::AlertDialog;
+    use adw::subclass::prelude::*;
+    use gtk::CompositeTemplate;
+    use gtk::ListBox;
+    use gtk::glib;
+
+    #[derive(CompositeTemplate, Default)]
+    #[template(resource = "/org/scratchmark/Scratchmark/ui/library/link_update_dialog.ui")]
+    pub struct LinkUpdateDialog {
+        #[template_child]
+        pub(super) list_box: TemplateChild<ListBox>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for LinkUpdateDialog {
+        const NAME: &'static str = "LinkUpdateDialog";
+        type Type = super::LinkUpdateDialog;
+        type ParentType = AlertDialog;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for LinkUpdateDialog {
+        fn constructed(&self) {
+            self.parent_constructed();
+        }
+    }
+
+    impl WidgetImpl for LinkUpdateDialog {}
+    impl AdwDialogImpl for LinkUpdateDialog {}
+    impl AdwAlertDialogImpl for LinkUpdateDialog {}
+}
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use adw::AlertDialog;
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use gtk::glib;
+use gtk::glib::Object;
+
+use crate::data::LinkEdit;
+
+glib::wrapper! {
+    pub struct LinkUpdateDialog(ObjectSubclass<imp::LinkUpdateDialog>)
+        @extends adw::AlertDialog, adw::Dialog, gtk::Widget,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget, gtk::ShortcutManager;
+}
+
+impl LinkUpdateDialog {
+    /// Preview the edits of each document. Paths are shown relative to `base`.
+    pub fn new(base: &Path, changes: &[(PathBuf, Vec<LinkEdit>)]) -> Self {
+        let obj: LinkUpdateDialog = Object::builder().build();
+        let num_links: usize = changes.iter().map(|(_, edits)| edits.len()).sum();
+        let body = obj.body();
+        obj.set_body(
+            &body
+                .replace("{{links}}", &num_links.to_string())
+                .replace("{{documents}}", &changes.len().to_string()),
+        );
+
+        for (path, edits) in changes {
+            let display_path = path.strip_prefix(base).unwrap_or(path);
+            for edit in edits {
+                let row = adw::ActionRow::builder()
+                    .title(format!("{} → {}", edit.old, edit.new))
+                    .subtitle(format!("{}:{}", display_path.display(), edit.line + 1))
+                    .use_markup(false)
+                    .build();
+                obj.imp().list_box.append(&row);
+            }
+        }
+        obj
+    }
+
+    pub fn present(&self, parent: Option<&impl glib::object::IsA<gtk::Widget>>) {
+        self.clone().upcast::<AlertDialog>().present(parent);
+    }
+}
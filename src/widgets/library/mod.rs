@@ -5,8 +5,11 @@ mod folder_create_popover;
 mod folder_row;
 mod item_rename_popover;
 mod library_view;
+mod link_update_dialog;
 mod project_err_placeholder;
 mod project_list_box;
 mod project_view;
 
+pub use document_row::DocumentRow;
 pub use library_view::LibraryView;
+pub use link_update_dialog::LinkUpdateDialog;
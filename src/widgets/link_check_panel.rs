@@ -0,0 +1,141 @@
+mod imp {
+    use std::path::PathBuf;
+    use std::sync::OnceLock;
+
+    use adw::subclass::prelude::*;
+    use gtk::Button;
+    use gtk::CompositeTemplate;
+    use gtk::Label;
+    use gtk::ListBox;
+    use gtk::glib;
+    use gtk::glib::clone;
+    use gtk::glib::subclass::Signal;
+    use gtk::prelude::*;
+
+    #[derive(CompositeTemplate, Default)]
+    #[template(resource = "/org/scratchmark/Scratchmark/ui/link_check_panel.ui")]
+    pub struct LinkCheckPanel {
+        #[template_child]
+        pub(super) title_label: TemplateChild<Label>,
+        #[template_child]
+        close_button: TemplateChild<Button>,
+        #[template_child]
+        pub(super) list_box: TemplateChild<ListBox>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for LinkCheckPanel {
+        const NAME: &'static str = "LinkCheckPanel";
+        type Type = super::LinkCheckPanel;
+        type ParentType = adw::Bin;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for LinkCheckPanel {
+        fn constructed(&self) {
+            self.parent_constructed();
+            let obj = self.obj();
+
+            self.close_button.connect_clicked(clone!(
+                #[weak]
+                obj,
+                move |_| obj.set_visible(false)
+            ));
+        }
+
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| {
+                vec![
+                    Signal::builder("open-link")
+                        .param_types([PathBuf::static_type(), i32::static_type()])
+                        .build(),
+                ]
+            })
+        }
+    }
+
+    impl WidgetImpl for LinkCheckPanel {}
+    impl BinImpl for LinkCheckPanel {}
+}
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use gettextrs::gettext;
+use gtk::glib;
+use gtk::glib::Object;
+use gtk::glib::clone;
+
+use crate::data::BrokenLink;
+use crate::data::LinkProblem;
+
+glib::wrapper! {
+    pub struct LinkCheckPanel(ObjectSubclass<imp::LinkCheckPanel>)
+        @extends adw::Bin, gtk::Widget,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget;
+}
+
+impl Default for LinkCheckPanel {
+    fn default() -> Self {
+        Object::builder().build()
+    }
+}
+
+impl LinkCheckPanel {
+    /// Show the broken links of each document. `base` is the folder paths are shown relative to.
+    pub fn set_results(&self, base: &Path, results: Vec<(PathBuf, Vec<BrokenLink>)>) {
+        let imp = self.imp();
+        imp.list_box.remove_all();
+
+        let mut count = 0;
+        for (path, broken_links) in results {
+            let display_path = path.strip_prefix(base).unwrap_or(&path).to_owned();
+            for broken in broken_links {
+                count += 1;
+                let problem = match broken.problem {
+                    LinkProblem::MissingFile => gettext("File not found"),
+                    LinkProblem::MissingAnchor => gettext("Heading not found"),
+                };
+                let row = adw::ActionRow::builder()
+                    .title(&broken.link.destination)
+                    .subtitle(format!(
+                        "{}:{} · {problem}",
+                        display_path.display(),
+                        broken.link.line + 1
+                    ))
+                    .use_markup(false)
+                    .activatable(true)
+                    .build();
+                let line = broken.link.line as i32;
+                row.connect_activated(clone!(
+                    #[weak(rename_to = obj)]
+                    self,
+                    #[strong]
+                    path,
+                    move |_| {
+                        obj.emit_by_name::<()>("open-link", &[&path, &line]);
+                    }
+                ));
+                imp.list_box.append(&row);
+            }
+        }
+
+        let title = if count == 0 {
+            gettext("Broken Links")
+        } else {
+            gettext("Broken Links ({count})").replace("{count}", &count.to_string())
+        };
+        imp.title_label.set_label(&title);
+        self.set_visible(true);
+    }
+}
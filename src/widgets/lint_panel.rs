@@ -0,0 +1,157 @@
+mod imp {
+    use std::sync::OnceLock;
+
+    use adw::subclass::prelude::*;
+    use gtk::Button;
+    use gtk::CompositeTemplate;
+    use gtk::Label;
+    use gtk::ListBox;
+    use gtk::glib;
+    use gtk::glib::clone;
+    use gtk::glib::subclass::Signal;
+    use gtk::prelude::*;
+
+    #[derive(CompositeTemplate, Default)]
+    #[template(resource = "/org/scratchmark/Scratchmark/ui/lint_panel.ui")]
+    pub struct LintPanel {
+        #[template_child]
+        pub(super) title_label: TemplateChild<Label>,
+        #[template_child]
+        pub(super) rules_box: TemplateChild<gtk::Box>,
+        #[template_child]
+        close_button: TemplateChild<Button>,
+        #[template_child]
+        pub(super) list_box: TemplateChild<ListBox>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for LintPanel {
+        const NAME: &'static str = "LintPanel";
+        type Type = super::LintPanel;
+        type ParentType = adw::Bin;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for LintPanel {
+        fn constructed(&self) {
+            self.parent_constructed();
+            let obj = self.obj();
+
+            self.close_button.connect_clicked(clone!(
+                #[weak]
+                obj,
+                move |_| obj.set_visible(false)
+            ));
+        }
+
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| {
+                vec![
+                    Signal::builder("jump-to-line")
+                        .param_types([i32::static_type()])
+                        .build(),
+                    // Rule id and whether it's enabled now
+                    Signal::builder("rule-changed")
+                        .param_types([String::static_type(), bool::static_type()])
+                        .build(),
+                ]
+            })
+        }
+    }
+
+    impl WidgetImpl for LintPanel {}
+    impl BinImpl for LintPanel {}
+}
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use gettextrs::gettext;
+use gtk::glib;
+use gtk::glib::Object;
+use gtk::glib::clone;
+
+use crate::data::LintConfig;
+use crate::data::LintDiagnostic;
+use crate::data::LintRule;
+
+glib::wrapper! {
+    pub struct LintPanel(ObjectSubclass<imp::LintPanel>)
+        @extends adw::Bin, gtk::Widget,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget;
+}
+
+impl Default for LintPanel {
+    fn default() -> Self {
+        Object::builder().build()
+    }
+}
+
+impl LintPanel {
+    /// Show which rules the project enables
+    pub fn set_config(&self, config: &LintConfig) {
+        let rules_box = &self.imp().rules_box;
+        while let Some(child) = rules_box.first_child() {
+            rules_box.remove(&child);
+        }
+        for rule in LintRule::ALL {
+            let check = gtk::CheckButton::builder()
+                .label(format!("{} · {}", rule.id(), rule.description()))
+                .active(config.is_enabled(rule))
+                .build();
+            check.connect_toggled(clone!(
+                #[weak(rename_to = obj)]
+                self,
+                move |check| {
+                    obj.emit_by_name::<()>(
+                        "rule-changed",
+                        &[&rule.id().to_owned(), &check.is_active()],
+                    );
+                }
+            ));
+            rules_box.append(&check);
+        }
+    }
+
+    pub fn set_diagnostics(&self, diagnostics: &[LintDiagnostic]) {
+        let imp = self.imp();
+        imp.list_box.remove_all();
+
+        for diagnostic in diagnostics {
+            let row = adw::ActionRow::builder()
+                .title(diagnostic.rule.description())
+                .subtitle(
+                    gettext("Line {line} · {id} {name}")
+                        .replace("{line}", &(diagnostic.line + 1).to_string())
+                        .replace("{id}", diagnostic.rule.id())
+                        .replace("{name}", diagnostic.rule.name()),
+                )
+                .use_markup(false)
+                .activatable(true)
+                .build();
+            let line = diagnostic.line as i32;
+            row.connect_activated(clone!(
+                #[weak(rename_to = obj)]
+                self,
+                move |_| {
+                    obj.emit_by_name::<()>("jump-to-line", &[&line]);
+                }
+            ));
+            imp.list_box.append(&row);
+        }
+
+        let title = if diagnostics.is_empty() {
+            gettext("Lint Problems")
+        } else {
+            gettext("Lint Problems ({count})").replace("{count}", &diagnostics.len().to_string())
+        };
+        imp.title_label.set_label(&title);
+    }
+}
@@ -3,6 +3,8 @@ mod imp {
 
     use adw::subclass::prelude::*;
     use gtk::CompositeTemplate;
+    use gtk::Popover;
+    use gtk::SpinButton;
     use gtk::gio::SimpleAction;
     use gtk::gio::SimpleActionGroup;
     use gtk::glib;
@@ -14,6 +16,13 @@ mod imp {
     #[derive(CompositeTemplate, Default)]
     #[template(resource = "/org/scratchmark/Scratchmark/ui/markdown_format_bar.ui")]
     pub struct MarkdownFormatBar {
+        #[template_child]
+        table_popover: TemplateChild<Popover>,
+        #[template_child]
+        table_rows_spin: TemplateChild<SpinButton>,
+        #[template_child]
+        table_columns_spin: TemplateChild<SpinButton>,
+
         actions: SimpleActionGroup,
         pub(super) editor: RefCell<Option<EditorView>>,
     }
@@ -156,6 +165,59 @@ mod imp {
             ));
             self.actions.add_action(&action);
 
+            let action = SimpleAction::new("table", None);
+            action.connect_activate(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move |_action, _parameter| {
+                    imp.table_popover.popdown();
+                    if let Some(editor) = imp.editor.borrow().as_ref() {
+                        let size = (
+                            imp.table_rows_spin.value_as_int(),
+                            imp.table_columns_spin.value_as_int(),
+                        );
+                        editor
+                            .activate_action("editor.format-table", Some(&size.to_variant()))
+                            .unwrap();
+                    }
+                }
+            ));
+            self.actions.add_action(&action);
+
+            for name in [
+                "table-insert-row",
+                "table-remove-row",
+                "table-insert-column",
+                "table-remove-column",
+            ] {
+                let action = SimpleAction::new(name, None);
+                let editor_action = format!("editor.{name}");
+                action.connect_activate(clone!(
+                    #[weak(rename_to = imp)]
+                    self,
+                    move |_action, _parameter| {
+                        if let Some(editor) = imp.editor.borrow().as_ref() {
+                            editor.activate_action(&editor_action, None).unwrap();
+                        }
+                    }
+                ));
+                self.actions.add_action(&action);
+            }
+
+            let action = SimpleAction::new("table-align", Some(glib::VariantTy::STRING));
+            action.connect_activate(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move |_action, alignment| {
+                    if let Some(editor) = imp.editor.borrow().as_ref() {
+                        editor
+                            .activate_action("editor.table-align", alignment)
+                            .unwrap();
+                    }
+                }
+            ));
+            self.actions.add_action(&action);
+
             self.update_enabled();
         }
 
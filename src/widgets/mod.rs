@@ -1,15 +1,23 @@
 mod editor;
 mod editor_placeholder;
 mod library;
+mod link_check_panel;
+mod lint_panel;
 mod markdown_format_bar;
 mod preferences;
+mod sprint_dialog;
 mod window;
 mod window_title;
 
 pub use editor::EditorView;
 pub use editor_placeholder::EditorPlaceholder;
+pub use library::DocumentRow;
 pub use library::LibraryView;
+pub use library::LinkUpdateDialog;
+pub use link_check_panel::LinkCheckPanel;
+pub use lint_panel::LintPanel;
 pub use markdown_format_bar::MarkdownFormatBar;
 pub use preferences::*;
+pub use sprint_dialog::SprintDialog;
 pub use window::Window;
 pub use window_title::WindowTitle;
@@ -2,29 +2,54 @@ mod imp {
     use std::cell::OnceCell;
     use std::cell::RefCell;
     use std::collections::HashMap;
+    use std::path::Path;
     use std::sync::OnceLock;
 
+    use crate::data::FocusScope;
+    use crate::data::KeybindingMode;
     use crate::settings::EDITOR_FONT_SIZES;
     use crate::util;
+    use crate::util::style_scheme;
     use crate::widgets::PreferencesFileExtItem;
     use crate::widgets::preferences::file_ext_add_popover::FileExtAddPopover;
     use adw::ActionRow;
+    use adw::AlertDialog;
+    use adw::ComboRow;
+    use adw::EntryRow;
+    use adw::PreferencesGroup;
+    use adw::ResponseAppearance;
     use adw::SpinRow;
     use adw::SwitchRow;
+    use adw::Toast;
     use adw::prelude::*;
     use adw::subclass::prelude::*;
+    use gettextrs::gettext;
     use gtk::Button;
     use gtk::CompositeTemplate;
+    use gtk::EventControllerKey;
+    use gtk::FileDialog;
+    use gtk::FileFilter;
+    use gtk::FileLauncher;
     use gtk::FlowBox;
     use gtk::FontDialog;
     use gtk::MenuButton;
     use gtk::PositionType;
+    use gtk::PropagationPhase;
     use gtk::Scale;
+    use gtk::ShortcutLabel;
+    use gtk::StringList;
+    use gtk::gdk::Key;
+    use gtk::gio;
     use gtk::gio::Cancellable;
     use gtk::gio::Settings;
     use gtk::glib;
+    use gtk::glib::GString;
+    use gtk::glib::SignalHandlerId;
     use gtk::glib::clone;
     use gtk::glib::closure_local;
+    use gtk::glib::translate::IntoGlib;
+    use sourceview5::StyleScheme;
+    use sourceview5::StyleSchemeManager;
 
     #[derive(CompositeTemplate, Default)]
     #[template(resource = "/org/scratchmark/Scratchmark/ui/preferences/dialog.ui")]
@@ -40,15 +65,44 @@ mod imp {
         #[template_child]
         editor_font_scale_reset: TemplateChild<Button>,
         #[template_child]
+        editor_style_scheme_combo: TemplateChild<ComboRow>,
+        #[template_child]
+        editor_style_scheme_import_button: TemplateChild<ActionRow>,
+        style_scheme_ids: RefCell<Vec<String>>,
+        style_scheme_handler: OnceCell<SignalHandlerId>,
+        #[template_child]
         editor_minimap_toggle: TemplateChild<SwitchRow>,
         #[template_child]
         editor_tabs_as_spaces_toggle: TemplateChild<SwitchRow>,
         #[template_child]
+        editor_keybindings_combo: TemplateChild<ComboRow>,
+        #[template_child]
         editor_limit_width_toggle: TemplateChild<SwitchRow>,
         #[template_child]
         editor_max_width_spin: TemplateChild<SpinRow>,
         #[template_child]
+        editor_focus_scope_combo: TemplateChild<ComboRow>,
+        #[template_child]
         editor_spellcheck_toggle: TemplateChild<SwitchRow>,
+        #[template_child]
+        editor_smart_typography_toggle: TemplateChild<SwitchRow>,
+        #[template_child]
+        editor_style_check_toggle: TemplateChild<SwitchRow>,
+        #[template_child]
+        editor_style_words_button: TemplateChild<ActionRow>,
+        #[template_child]
+        editor_image_previews_toggle: TemplateChild<SwitchRow>,
+        #[template_child]
+        editor_assets_folder_entry: TemplateChild<EntryRow>,
+
+        #[template_child]
+        format_bullet_combo: TemplateChild<ComboRow>,
+        #[template_child]
+        format_emphasis_combo: TemplateChild<ComboRow>,
+        #[template_child]
+        format_rewrap_toggle: TemplateChild<SwitchRow>,
+        #[template_child]
+        editor_wrap_column_spin: TemplateChild<SpinRow>,
 
         #[template_child]
         library_ignore_hidden_files_toggle: TemplateChild<SwitchRow>,
@@ -60,6 +114,20 @@ mod imp {
         library_add_ext_menubutton: TemplateChild<MenuButton>,
         library_ext_items: RefCell<HashMap<String, PreferencesFileExtItem>>,
         library_file_ext_add_popover: OnceLock<FileExtAddPopover>,
+
+        #[template_child]
+        journal_project_combo: TemplateChild<ComboRow>,
+        #[template_child]
+        journal_template_entry: TemplateChild<EntryRow>,
+
+        #[template_child]
+        shortcuts_general_group: TemplateChild<PreferencesGroup>,
+        #[template_child]
+        shortcuts_editor_group: TemplateChild<PreferencesGroup>,
+        #[template_child]
+        shortcuts_reset_button: TemplateChild<Button>,
+        shortcut_labels: RefCell<Vec<(&'static str, ShortcutLabel)>>,
+        settings_handlers: RefCell<Vec<SignalHandlerId>>,
     }
 
     #[glib::object_subclass]
@@ -118,6 +186,8 @@ mod imp {
                 move |_| imp.reset_font_size()
             ));
 
+            self.bind_style_scheme_setting();
+
             let editor_minimap_toggle: &SwitchRow = &self.editor_minimap_toggle;
             settings
                 .bind("editor-show-minimap", editor_minimap_toggle, "active")
@@ -130,6 +200,7 @@ mod imp {
                     "active",
                 )
                 .build();
+            self.bind_keybindings_setting(&settings);
             let editor_limit_width_toggle: &SwitchRow = &self.editor_limit_width_toggle;
             settings
                 .bind("editor-limit-width", editor_limit_width_toggle, "active")
@@ -148,10 +219,45 @@ mod imp {
                 .bind("editor-limit-width", editor_max_width_spin, "sensitive")
                 .get()
                 .build();
+            self.bind_focus_scope_setting(&settings);
             let editor_spellcheck_toggle: &SwitchRow = &self.editor_spellcheck_toggle;
             settings
                 .bind("editor-use-spellcheck", editor_spellcheck_toggle, "active")
                 .build();
+            let editor_smart_typography_toggle: &SwitchRow = &self.editor_smart_typography_toggle;
+            settings
+                .bind(
+                    "editor-smart-typography",
+                    editor_smart_typography_toggle,
+                    "active",
+                )
+                .build();
+            let editor_style_check_toggle: &SwitchRow = &self.editor_style_check_toggle;
+            settings
+                .bind("editor-style-check", editor_style_check_toggle, "active")
+                .build();
+            self.editor_style_words_button.connect_activated(|row| {
+                let file = gio::File::for_path(util::file_actions::path_style_words());
+                FileLauncher::new(Some(&file)).launch(
+                    row.root().and_downcast_ref::<gtk::Window>(),
+                    None::<&Cancellable>,
+                    |_| {},
+                );
+            });
+            let editor_image_previews_toggle: &SwitchRow = &self.editor_image_previews_toggle;
+            settings
+                .bind(
+                    "editor-show-image-previews",
+                    editor_image_previews_toggle,
+                    "active",
+                )
+                .build();
+            let editor_assets_folder_entry: &EntryRow = &self.editor_assets_folder_entry;
+            settings
+                .bind("editor-assets-folder", editor_assets_folder_entry, "text")
+                .build();
+
+            self.bind_format_settings(&settings);
 
             let library_ignore_hidden_files_toggle: &SwitchRow =
                 &self.library_ignore_hidden_files_toggle;
@@ -172,6 +278,9 @@ mod imp {
                 )
                 .build();
 
+            self.bind_journal_settings(&settings);
+            self.bind_shortcut_settings(&settings);
+
             self.library_extensions_flowbox.set_sort_func(|a, b| {
                 let a = a
                     .child()
@@ -232,6 +341,505 @@ mod imp {
             });
         }
 
+        fn bind_shortcut_settings(&self, settings: &Settings) {
+            for (action, _) in crate::accels::ACCELS {
+                let Some(title) = crate::accels::title(action) else {
+                    continue;
+                };
+                let label = ShortcutLabel::builder()
+                    .disabled_text(gettext("Disabled"))
+                    .valign(gtk::Align::Center)
+                    .build();
+                let row = ActionRow::builder().title(title).activatable(true).build();
+                row.add_suffix(&label);
+                row.connect_activated(clone!(
+                    #[weak(rename_to = imp)]
+                    self,
+                    move |_| imp.prompt_shortcut(action)
+                ));
+                if action.starts_with("editor.") {
+                    self.shortcuts_editor_group.add(&row);
+                } else {
+                    self.shortcuts_general_group.add(&row);
+                }
+                self.shortcut_labels.borrow_mut().push((action, label));
+            }
+
+            self.shortcuts_reset_button.connect_clicked(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move |_| imp.settings.get().unwrap().reset("custom-accels")
+            ));
+
+            for key in ["custom-accels", "editor-keybindings"] {
+                let handler = settings.connect_changed(
+                    Some(key),
+                    clone!(
+                        #[weak(rename_to = imp)]
+                        self,
+                        move |_, _| imp.refresh_shortcuts()
+                    ),
+                );
+                self.settings_handlers.borrow_mut().push(handler);
+            }
+            self.obj().connect_destroy(|obj| {
+                let imp = obj.imp();
+                let settings = imp.settings.get().unwrap();
+                for handler in imp.settings_handlers.take() {
+                    settings.disconnect(handler);
+                }
+            });
+            self.refresh_shortcuts();
+        }
+
+        fn refresh_shortcuts(&self) {
+            let settings = self.settings.get().unwrap();
+            let mode = crate::accels::keybinding_mode(settings);
+            let custom = crate::accels::custom_accels(settings);
+            for (action, label) in self.shortcut_labels.borrow().iter() {
+                let accels = crate::accels::accels_for(action, mode, &custom);
+                label.set_accelerator(&accels.join(" "));
+            }
+        }
+
+        /// Ask for a new shortcut of the action
+        fn prompt_shortcut(&self, action: &'static str) {
+            let title = crate::accels::title(action).unwrap_or_default();
+            let dialog = AlertDialog::new(
+                Some(&gettext("Set Shortcut")),
+                Some(&gettext("Press the new shortcut for “{action}”").replace("{action}", &title)),
+            );
+            dialog.add_response("cancel", &gettext("_Cancel"));
+            dialog.add_response("reset", &gettext("_Reset"));
+            dialog.add_response("disable", &gettext("_Disable"));
+            dialog.set_response_appearance("disable", ResponseAppearance::Destructive);
+            dialog.set_close_response("cancel");
+            dialog.connect_response(
+                None,
+                clone!(
+                    #[weak(rename_to = imp)]
+                    self,
+                    move |_, response| match response {
+                        "reset" => imp.store_shortcut(action, None),
+                        "disable" => imp.store_shortcut(action, Some("")),
+                        _ => (),
+                    }
+                ),
+            );
+
+            let key_controller = EventControllerKey::new();
+            key_controller.set_propagation_phase(PropagationPhase::Capture);
+            key_controller.connect_key_pressed(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                #[weak]
+                dialog,
+                #[upgrade_or]
+                glib::Propagation::Proceed,
+                move |_, key, _, state| {
+                    let modifiers = state & gtk::accelerator_get_default_mod_mask();
+                    // Plain keys would stop typing, except for function keys
+                    let function_key =
+                        (Key::F1.into_glib()..=Key::F35.into_glib()).contains(&key.into_glib());
+                    if (modifiers.is_empty() && !function_key)
+                        || !gtk::accelerator_valid(key, modifiers)
+                    {
+                        return glib::Propagation::Proceed;
+                    }
+                    let accel = gtk::accelerator_name(key.to_lower(), modifiers);
+                    dialog.close();
+                    imp.set_shortcut(action, &accel);
+                    glib::Propagation::Stop
+                }
+            ));
+            dialog.add_controller(key_controller);
+
+            // The app's shortcuts would be triggered before the dialog sees the keys
+            if let Some(app) = gio::Application::default().and_downcast::<gtk::Application>() {
+                crate::accels::clear(&app);
+                let settings = self.settings.get().unwrap().clone();
+                dialog.connect_closed(move |_| crate::accels::apply(&app, &settings));
+            }
+            dialog.present(Some(&*self.obj()));
+        }
+
+        /// Use the shortcut for the action, after asking whether to take it from another one
+        fn set_shortcut(&self, action: &'static str, accel: &str) {
+            let settings = self.settings.get().unwrap();
+            let Some(other) = crate::accels::conflict(settings, action, accel) else {
+                self.store_shortcut(action, Some(accel));
+                return;
+            };
+
+            let shortcut = gtk::accelerator_parse(accel)
+                .map(|(key, modifiers)| gtk::accelerator_get_label(key, modifiers).to_string())
+                .unwrap_or_else(|| accel.to_owned());
+            let other_title = crate::accels::title(other).unwrap_or_else(|| other.to_owned());
+            let dialog = AlertDialog::new(
+                Some(&gettext("Shortcut Already Used")),
+                Some(
+                    &gettext("{shortcut} is used by “{action}”. Replacing it turns it off there.")
+                        .replace("{shortcut}", &shortcut)
+                        .replace("{action}", &other_title),
+                ),
+            );
+            dialog.add_response("cancel", &gettext("_Cancel"));
+            dialog.add_response("replace", &gettext("_Replace"));
+            dialog.set_response_appearance("replace", ResponseAppearance::Destructive);
+            dialog.set_close_response("cancel");
+            let accel = accel.to_owned();
+            dialog.connect_response(
+                Some("replace"),
+                clone!(
+                    #[weak(rename_to = imp)]
+                    self,
+                    move |_, _| {
+                        let settings = imp.settings.get().unwrap();
+                        let mode = crate::accels::keybinding_mode(settings);
+                        let mut custom = crate::accels::custom_accels(settings);
+                        // The other action keeps the rest of its shortcuts
+                        let parsed = gtk::accelerator_parse(&accel);
+                        let remaining: Vec<String> =
+                            crate::accels::accels_for(other, mode, &custom)
+                                .into_iter()
+                                .filter(|other_accel| gtk::accelerator_parse(other_accel) != parsed)
+                                .collect();
+                        custom.insert(other.to_owned(), remaining.join(" "));
+                        custom.insert(action.to_owned(), accel.clone());
+                        settings
+                            .set_value("custom-accels", &custom.to_variant())
+                            .unwrap();
+                    }
+                ),
+            );
+            dialog.present(Some(&*self.obj()));
+        }
+
+        /// Save the user's shortcut of the action. None goes back to the default.
+        fn store_shortcut(&self, action: &str, accel: Option<&str>) {
+            let settings = self.settings.get().unwrap();
+            let mut custom = crate::accels::custom_accels(settings);
+            match accel {
+                Some(accel) => custom.insert(action.to_owned(), accel.to_owned()),
+                None => custom.remove(action),
+            };
+            settings
+                .set_value("custom-accels", &custom.to_variant())
+                .unwrap();
+        }
+
+        fn bind_style_scheme_setting(&self) {
+            let handler = self
+                .editor_style_scheme_combo
+                .connect_selected_notify(clone!(
+                    #[weak(rename_to = imp)]
+                    self,
+                    move |combo| {
+                        let ids = imp.style_scheme_ids.borrow();
+                        if let Some(id) = ids.get(combo.selected() as usize) {
+                            imp.settings
+                                .get()
+                                .unwrap()
+                                .set_string("editor-style-scheme", id)
+                                .unwrap();
+                        }
+                    }
+                ));
+            self.style_scheme_handler.set(handler).unwrap();
+            self.refresh_style_schemes();
+
+            self.editor_style_scheme_import_button
+                .connect_activated(clone!(
+                    #[weak(rename_to = imp)]
+                    self,
+                    move |_| imp.show_style_scheme_import_dialog()
+                ));
+        }
+
+        /// List the installed schemes. Dark variants are picked with their light scheme.
+        fn refresh_style_schemes(&self) {
+            let manager = StyleSchemeManager::default();
+            let exists = |id: &str| manager.scheme(id).is_some();
+            let mut schemes: Vec<StyleScheme> = manager
+                .scheme_ids()
+                .iter()
+                .filter(|id| !style_scheme::is_variant(id, exists))
+                .filter_map(|id| manager.scheme(id))
+                .collect();
+            schemes.sort_by_key(|scheme| scheme.name().to_lowercase());
+
+            let names: Vec<GString> = schemes.iter().map(|scheme| scheme.name()).collect();
+            let names: Vec<&str> = names.iter().map(GString::as_str).collect();
+            let ids: Vec<String> = schemes.iter().map(|scheme| scheme.id().into()).collect();
+
+            let current = self.settings.get().unwrap().string("editor-style-scheme");
+            let current = style_scheme::scheme_variant(&current, false, None, exists);
+            let selected = ids.iter().position(|id| *id == current);
+
+            let combo: &ComboRow = &self.editor_style_scheme_combo;
+            let handler = self.style_scheme_handler.get().unwrap();
+            combo.block_signal(handler);
+            self.style_scheme_ids.replace(ids);
+            combo.set_model(Some(&StringList::new(&names)));
+            combo.set_selected(selected.map_or(gtk::INVALID_LIST_POSITION, |i| i as u32));
+            combo.unblock_signal(handler);
+        }
+
+        fn show_style_scheme_import_dialog(&self) {
+            let filter = FileFilter::new();
+            filter.set_name(Some(&gettext("Style Schemes")));
+            filter.add_suffix("xml");
+            let filters = gio::ListStore::new::<FileFilter>();
+            filters.append(&filter);
+            let dialog = FileDialog::builder()
+                .title(gettext("Import Color Scheme"))
+                .filters(&filters)
+                .build();
+            dialog.open(
+                self.obj().root().and_downcast_ref::<gtk::Window>(),
+                None::<&Cancellable>,
+                clone!(
+                    #[weak(rename_to = imp)]
+                    self,
+                    move |result| {
+                        if let Ok(file) = result
+                            && let Some(path) = file.path()
+                        {
+                            imp.import_style_scheme(&path);
+                        }
+                    }
+                ),
+            );
+        }
+
+        /// Copy a scheme file to the user's schemes and use it
+        fn import_style_scheme(&self, path: &Path) {
+            let obj = self.obj();
+            let Some(file_name) = path.file_name() else {
+                return;
+            };
+            let fail = |message: String| obj.add_toast(Toast::new(&message));
+
+            // Load it on its own first, so an invalid file never replaces an installed scheme
+            let staged = util::file_actions::path_style_scheme_import().join(file_name);
+            if let Err(e) = std::fs::copy(path, &staged) {
+                println!("Couldn't import color scheme: {e}");
+                fail(gettext("Couldn't import color scheme"));
+                return;
+            }
+            let staging = StyleSchemeManager::new();
+            staging.set_search_path(&[staged.parent().unwrap().to_string_lossy().as_ref()]);
+            let scheme_id = staging
+                .scheme_ids()
+                .iter()
+                .filter_map(|id| staging.scheme(id))
+                .find(|scheme| scheme.filename().is_some_and(|f| Path::new(&f) == staged))
+                .map(|scheme| scheme.id());
+            let Some(scheme_id) = scheme_id else {
+                let _ = std::fs::remove_file(&staged);
+                fail(gettext("Not a valid color scheme"));
+                return;
+            };
+
+            let manager = StyleSchemeManager::default();
+            let target = util::file_actions::path_user_style_schemes().join(file_name);
+            if manager.scheme(&scheme_id).is_some() || target.exists() {
+                let _ = std::fs::remove_file(&staged);
+                fail(gettext(
+                    "A color scheme with the same name is already installed",
+                ));
+                return;
+            }
+            let installed = std::fs::copy(&staged, &target);
+            let _ = std::fs::remove_file(&staged);
+            if let Err(e) = installed {
+                println!("Couldn't import color scheme: {e}");
+                fail(gettext("Couldn't import color scheme"));
+                return;
+            }
+
+            manager.force_rescan();
+            self.settings
+                .get()
+                .unwrap()
+                .set_string("editor-style-scheme", &scheme_id)
+                .unwrap();
+            self.refresh_style_schemes();
+        }
+
+        fn bind_keybindings_setting(&self, settings: &Settings) {
+            let model = StringList::new(&[
+                gettext("Default").as_str(),
+                gettext("Vim").as_str(),
+                gettext("Emacs").as_str(),
+            ]);
+            let combo: &ComboRow = &self.editor_keybindings_combo;
+            combo.set_model(Some(&model));
+            let current = KeybindingMode::try_from(settings.string("editor-keybindings").as_str())
+                .unwrap_or_default();
+            let selected = KeybindingMode::ALL
+                .iter()
+                .position(|mode| *mode == current)
+                .unwrap_or(0);
+            combo.set_selected(selected as u32);
+            combo.connect_selected_notify(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move |combo| {
+                    let mode = KeybindingMode::ALL
+                        .get(combo.selected() as usize)
+                        .copied()
+                        .unwrap_or_default();
+                    imp.settings
+                        .get()
+                        .unwrap()
+                        .set_string("editor-keybindings", &mode.to_string())
+                        .unwrap();
+                }
+            ));
+        }
+
+        fn bind_focus_scope_setting(&self, settings: &Settings) {
+            let model = StringList::new(&[
+                gettext("Line").as_str(),
+                gettext("Sentence").as_str(),
+                gettext("Paragraph").as_str(),
+            ]);
+            let combo: &ComboRow = &self.editor_focus_scope_combo;
+            combo.set_model(Some(&model));
+            let current = FocusScope::try_from(settings.string("editor-focus-scope").as_str())
+                .unwrap_or_default();
+            let selected = FocusScope::ALL
+                .iter()
+                .position(|scope| *scope == current)
+                .unwrap_or(0);
+            combo.set_selected(selected as u32);
+            combo.connect_selected_notify(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move |combo| {
+                    let scope = FocusScope::ALL
+                        .get(combo.selected() as usize)
+                        .copied()
+                        .unwrap_or_default();
+                    imp.settings
+                        .get()
+                        .unwrap()
+                        .set_string("editor-focus-scope", &scope.to_string())
+                        .unwrap();
+                }
+            ));
+        }
+
+        fn bind_format_settings(&self, settings: &Settings) {
+            const BULLETS: &[&str] = &["-", "*", "+"];
+            const EMPHASES: &[&str] = &["*", "_"];
+            let bullet_model = StringList::new(&[
+                gettext("Hyphen (-)").as_str(),
+                gettext("Asterisk (*)").as_str(),
+                gettext("Plus (+)").as_str(),
+            ]);
+            let emphasis_model = StringList::new(&[
+                gettext("Asterisks (*text*)").as_str(),
+                gettext("Underscores (_text_)").as_str(),
+            ]);
+            for (combo, model, key, values) in [
+                (
+                    &self.format_bullet_combo,
+                    bullet_model,
+                    "format-bullet",
+                    BULLETS,
+                ),
+                (
+                    &self.format_emphasis_combo,
+                    emphasis_model,
+                    "format-emphasis",
+                    EMPHASES,
+                ),
+            ] {
+                combo.set_model(Some(&model));
+                let current = settings.string(key);
+                let selected = values
+                    .iter()
+                    .position(|v| *v == current.as_str())
+                    .unwrap_or(0);
+                combo.set_selected(selected as u32);
+                combo.connect_selected_notify(clone!(
+                    #[weak(rename_to = imp)]
+                    self,
+                    move |combo| {
+                        let value = values.get(combo.selected() as usize).unwrap_or(&values[0]);
+                        imp.settings.get().unwrap().set_string(key, value).unwrap();
+                    }
+                ));
+            }
+
+            let format_rewrap_toggle: &SwitchRow = &self.format_rewrap_toggle;
+            settings
+                .bind("format-rewrap", format_rewrap_toggle, "active")
+                .build();
+            let editor_wrap_column_spin: &SpinRow = &self.editor_wrap_column_spin;
+            editor_wrap_column_spin
+                .adjustment()
+                .set_upper(crate::settings::WRAP_COLUMN_MAX as f64);
+            editor_wrap_column_spin
+                .adjustment()
+                .set_lower(crate::settings::WRAP_COLUMN_MIN as f64);
+            settings
+                .bind("editor-wrap-column", editor_wrap_column_spin, "value")
+                .build();
+        }
+
+        fn bind_journal_settings(&self, settings: &Settings) {
+            // The drafts library comes first, then the open projects
+            let mut project_paths: Vec<String> = settings
+                .strv("library-project-paths")
+                .iter()
+                .map(|path| path.to_string())
+                .collect();
+            let current = settings.string("journal-project-path").to_string();
+            if !current.is_empty() && !project_paths.contains(&current) {
+                project_paths.push(current.clone());
+            }
+
+            let model = StringList::new(&[gettext("Drafts").as_str()]);
+            for path in &project_paths {
+                let name = Path::new(path)
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| path.clone());
+                model.append(&name);
+            }
+            self.journal_project_combo.set_model(Some(&model));
+            let selected = project_paths
+                .iter()
+                .position(|path| *path == current)
+                .map(|i| i + 1)
+                .unwrap_or(0);
+            self.journal_project_combo.set_selected(selected as u32);
+
+            self.journal_project_combo.connect_selected_notify(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move |combo| {
+                    let path = match combo.selected() as usize {
+                        0 => "",
+                        i => project_paths.get(i - 1).map(String::as_str).unwrap_or(""),
+                    };
+                    imp.settings
+                        .get()
+                        .unwrap()
+                        .set_string("journal-project-path", path)
+                        .unwrap();
+                }
+            ));
+
+            let journal_template_entry: &EntryRow = &self.journal_template_entry;
+            settings
+                .bind("journal-template", journal_template_entry, "text")
+                .build();
+        }
+
         fn has_file_ext_already(&self, ext: &str) -> bool {
             let ext = util::process_file_ext_text(ext);
             ext == "md" || self.library_ext_items.borrow().contains_key(&ext)
@@ -0,0 +1,112 @@
+mod imp {
+    use adw::AlertDialog;
+    use adw::SpinRow;
+    use adw::SwitchRow;
+    use adw::subclass::prelude::*;
+    use gtk::CompositeTemplate;
+    use gtk::Label;
+    use gtk::ListBox;
+    use gtk::glib;
+
+    #[derive(CompositeTemplate, Default)]
+    #[template(resource = "/org/scratchmark/Scratchmark/ui/sprint_dialog.ui")]
+    pub struct SprintDialog {
+        #[template_child]
+        pub(super) duration_spin: TemplateChild<SpinRow>,
+        #[template_child]
+        pub(super) word_target_spin: TemplateChild<SpinRow>,
+        #[template_child]
+        pub(super) focus_mode_toggle: TemplateChild<SwitchRow>,
+        #[template_child]
+        pub(super) history_label: TemplateChild<Label>,
+        #[template_child]
+        pub(super) history_list_box: TemplateChild<ListBox>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for SprintDialog {
+        const NAME: &'static str = "SprintDialog";
+        type Type = super::SprintDialog;
+        type ParentType = AlertDialog;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for SprintDialog {}
+    impl WidgetImpl for SprintDialog {}
+    impl AdwDialogImpl for SprintDialog {}
+    impl AdwAlertDialogImpl for SprintDialog {}
+}
+
+use adw::AlertDialog;
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use gettextrs::gettext;
+use gtk::gio::Settings;
+use gtk::glib;
+use gtk::glib::Object;
+
+use crate::data::SprintRecord;
+
+/// Number of past sprints shown in the dialog
+const HISTORY_ROWS: usize = 5;
+
+glib::wrapper! {
+    pub struct SprintDialog(ObjectSubclass<imp::SprintDialog>)
+        @extends adw::AlertDialog, adw::Dialog, gtk::Widget,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget, gtk::ShortcutManager;
+}
+
+impl SprintDialog {
+    /// The sprint options are bound to the settings. `history` is oldest first.
+    pub fn new(settings: &Settings, history: &[SprintRecord]) -> Self {
+        let obj: SprintDialog = Object::builder().build();
+        let imp = obj.imp();
+
+        let duration_spin: &adw::SpinRow = &imp.duration_spin;
+        settings
+            .bind("sprint-duration", duration_spin, "value")
+            .build();
+        let word_target_spin: &adw::SpinRow = &imp.word_target_spin;
+        settings
+            .bind("sprint-word-target", word_target_spin, "value")
+            .build();
+        let focus_mode_toggle: &adw::SwitchRow = &imp.focus_mode_toggle;
+        settings
+            .bind("sprint-focus-mode", focus_mode_toggle, "active")
+            .build();
+
+        for record in history.iter().rev().take(HISTORY_ROWS) {
+            let started = glib::DateTime::from_unix_local(record.started)
+                .and_then(|started| started.format("%x %R"))
+                .map(|started| started.to_string())
+                .unwrap_or_default();
+            let mut summary = gettext("{words} words in {minutes} min · {wpm} wpm")
+                .replace("{words}", &record.words.to_string())
+                .replace("{minutes}", &(record.elapsed / 60).to_string())
+                .replace("{wpm}", &format!("{:.0}", record.words_per_minute()));
+            if record.target_reached() {
+                summary.push_str(" · ✓");
+            }
+            let row = adw::ActionRow::builder()
+                .title(summary)
+                .subtitle(started)
+                .use_markup(false)
+                .build();
+            imp.history_list_box.append(&row);
+        }
+        imp.history_label.set_visible(!history.is_empty());
+        imp.history_list_box.set_visible(!history.is_empty());
+        obj
+    }
+
+    pub fn present(&self, parent: Option<&impl glib::object::IsA<gtk::Widget>>) {
+        self.clone().upcast::<AlertDialog>().present(parent);
+    }
+}
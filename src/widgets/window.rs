@@ -2,7 +2,11 @@ mod imp {
     use std::cell::Cell;
     use std::cell::OnceCell;
     use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::path::Path;
     use std::path::PathBuf;
+    use std::time::Duration;
+    use std::time::Instant;
 
     use adw::AboutDialog;
     use adw::ApplicationWindow;
@@ -22,7 +26,10 @@ mod imp {
     use gtk::CompositeTemplate;
     use gtk::EventControllerMotion;
     use gtk::Revealer;
+    use gtk::ShortcutsGroup;
+    use gtk::ShortcutsShortcut;
     use gtk::ToggleButton;
+    use gtk::gio;
     use gtk::gio::Settings;
     use gtk::gio::SimpleAction;
     use gtk::gio::SimpleActionGroup;
@@ -36,16 +43,51 @@ mod imp {
     use crate::config;
     use crate::data::Document;
     use crate::data::Folder;
+    use crate::data::JournalDate;
+    use crate::data::LinkEdit;
+    use crate::data::LintConfig;
+    use crate::data::LintRule;
+    use crate::data::MovedItem;
+    use crate::data::SprintRecord;
+    use crate::data::Template;
+    use crate::data::TemplateValues;
+    use crate::data::append_sprint_history;
+    use crate::data::apply_link_edits;
+    use crate::data::broken_links;
+    use crate::data::fill_template;
+    use crate::data::journal_note_dates;
+    use crate::data::link_edits;
+    use crate::data::load_sprint_history;
+    use crate::data::project_name;
     use crate::error::ScratchmarkError;
     use crate::util::file_actions;
 
     use crate::widgets::EditorPlaceholder;
     use crate::widgets::EditorView;
     use crate::widgets::LibraryView;
+    use crate::widgets::LinkCheckPanel;
+    use crate::widgets::LinkUpdateDialog;
+    use crate::widgets::LintPanel;
     use crate::widgets::MarkdownFormatBar;
     use crate::widgets::PreferencesDialog;
+    use crate::widgets::SprintDialog;
     use crate::widgets::WindowTitle;
 
+    /// A running writing sprint
+    struct Sprint {
+        started: glib::DateTime,
+        start: Instant,
+        duration: Duration,
+        word_target: u32,
+        /// Words written in documents that were closed during the sprint
+        words_banked: i32,
+        /// The open editor, and its word count when the sprint first saw it
+        baseline: Option<(EditorView, i32)>,
+        /// Focus mode was turned on for the sprint, so turn it off afterwards
+        restore_focus_mode: bool,
+        timer: glib::SourceId,
+    }
+
     #[derive(CompositeTemplate, Default, Properties)]
     #[properties(wrapper_type = super::Window)]
     #[template(resource = "/org/scratchmark/Scratchmark/ui/window.ui")]
@@ -90,9 +132,16 @@ mod imp {
         format_bar_toggle: TemplateChild<ToggleButton>,
         #[template_child]
         editor_sidebar_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        link_check_panel: TemplateChild<LinkCheckPanel>,
+        #[template_child]
+        lint_panel: TemplateChild<LintPanel>,
 
         library_view: LibraryView,
         editor: RefCell<Option<EditorView>>,
+        sprint: RefCell<Option<Sprint>>,
+        /// Counts link checks, so results of a project check that was run again are dropped
+        link_check_generation: Cell<u32>,
 
         motion_controller: EventControllerMotion,
 
@@ -110,6 +159,8 @@ mod imp {
 
         fn class_init(klass: &mut Self::Class) {
             MarkdownFormatBar::ensure_type();
+            LinkCheckPanel::ensure_type();
+            LintPanel::ensure_type();
             WindowTitle::ensure_type();
 
             klass.bind_template();
@@ -134,6 +185,21 @@ mod imp {
             let settings = Settings::new(APP_ID);
             self.settings.set(settings.clone()).unwrap();
             crate::settings::sanity_filter(&settings);
+            for key in ["editor-keybindings", "custom-accels"] {
+                settings.connect_changed(
+                    Some(key),
+                    clone!(
+                        #[weak(rename_to = imp)]
+                        self,
+                        move |settings, _| {
+                            if let Some(app) = imp.obj().application() {
+                                crate::accels::apply(&app, settings);
+                            }
+                            imp.setup_help_overlay();
+                        }
+                    ),
+                );
+            }
             #[cfg(not(feature = "generatescreenshots"))]
             {
                 let library_view: &LibraryView = self.library_view.as_ref();
@@ -304,9 +370,7 @@ mod imp {
 
             self.editor_sidebar_toggle.set_sensitive(false);
 
-            let builder = Builder::from_resource("/org/scratchmark/Scratchmark/ui/shortcuts.ui");
-            let shortcuts = builder.object("help_overlay").unwrap();
-            obj.set_help_overlay(Some(&shortcuts));
+            self.setup_help_overlay();
 
             let top_split = self.top_split.get();
 
@@ -367,7 +431,15 @@ mod imp {
                             imp.editor.borrow().as_ref().unwrap().stop_file_monitor();
                         }
 
-                        if let Err(e) = imp.library_view.move_item(old_path, new_path.clone()) {
+                        let moved = MovedItem {
+                            old_path: old_path.clone(),
+                            new_path: new_path.clone(),
+                            is_folder: true,
+                        };
+                        let link_changes = imp.collect_link_edits(&moved);
+
+                        let move_result = imp.library_view.move_item(old_path, new_path.clone());
+                        if let Err(e) = &move_result {
                             imp.toast(&e.to_string());
                         }
 
@@ -386,6 +458,9 @@ mod imp {
                         );
 
                         imp.update_window_title();
+                        if move_result.is_ok() {
+                            imp.prompt_link_update(moved, link_changes);
+                        }
                     }
                 ),
             );
@@ -409,7 +484,15 @@ mod imp {
                             imp.editor.borrow().as_ref().unwrap().stop_file_monitor();
                         }
 
-                        if let Err(e) = imp.library_view.move_item(old_path, new_path.clone()) {
+                        let moved = MovedItem {
+                            old_path: old_path.clone(),
+                            new_path: new_path.clone(),
+                            is_folder: false,
+                        };
+                        let link_changes = imp.collect_link_edits(&moved);
+
+                        let move_result = imp.library_view.move_item(old_path, new_path.clone());
+                        if let Err(e) = &move_result {
                             println!("{e}");
                             imp.toast(&gettext("Couldn't move file"));
                         }
@@ -426,6 +509,9 @@ mod imp {
                         );
 
                         imp.update_window_title();
+                        if move_result.is_ok() {
+                            imp.prompt_link_update(moved, link_changes);
+                        }
                     }
                 ),
             );
@@ -453,6 +539,78 @@ mod imp {
                 ),
             );
 
+            self.link_check_panel.connect_closure(
+                "open-link",
+                false,
+                closure_local!(
+                    #[weak(rename_to = imp)]
+                    self,
+                    move |_: LinkCheckPanel, path: PathBuf, line: i32| {
+                        let is_open = imp
+                            .editor
+                            .borrow()
+                            .as_ref()
+                            .is_some_and(|editor| editor.path() == path);
+                        if !is_open {
+                            imp.load_document(path);
+                        }
+                        if let Some(editor) = imp.editor.borrow().as_ref() {
+                            editor.jump_to_line(line);
+                        }
+                    }
+                ),
+            );
+
+            self.lint_panel.connect_closure(
+                "jump-to-line",
+                false,
+                closure_local!(
+                    #[weak(rename_to = imp)]
+                    self,
+                    move |_: LintPanel, line: i32| {
+                        if let Some(editor) = imp.editor.borrow().as_ref() {
+                            editor.jump_to_line(line);
+                        }
+                    }
+                ),
+            );
+
+            self.lint_panel.connect_closure(
+                "rule-changed",
+                false,
+                closure_local!(
+                    #[weak(rename_to = imp)]
+                    self,
+                    move |_: LintPanel, id: String, enabled: bool| {
+                        let Some(path) = imp.editor.borrow().as_ref().map(|e| e.path()) else {
+                            return;
+                        };
+                        let Some(rule) = LintRule::ALL.into_iter().find(|r| r.id() == id) else {
+                            return;
+                        };
+                        if let Err(e) =
+                            LintConfig::save_rule(&imp.lint_project_path(&path), rule, enabled)
+                        {
+                            imp.toast(&e.to_string());
+                        }
+                        imp.lint_document();
+                    }
+                ),
+            );
+
+            // Linting stops when the panel is closed
+            self.lint_panel.connect_visible_notify(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move |panel| {
+                    if !panel.is_visible()
+                        && let Some(editor) = imp.editor.borrow().as_ref()
+                    {
+                        editor.set_lint_config(None);
+                    }
+                }
+            ));
+
             self.library_view.connect_closure(
                 "toast",
                 false,
@@ -695,6 +853,110 @@ mod imp {
             ));
             obj.add_action(&action);
 
+            let action = SimpleAction::new("check-links", None);
+            action.connect_activate(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move |_, _| imp.check_links(false)
+            ));
+            obj.add_action(&action);
+
+            let action = SimpleAction::new("check-links-project", None);
+            action.connect_activate(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move |_, _| imp.check_links(true)
+            ));
+            obj.add_action(&action);
+
+            let action = SimpleAction::new("lint-document", None);
+            action.connect_activate(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move |_, _| imp.lint_document()
+            ));
+            obj.add_action(&action);
+
+            let action = SimpleAction::new("journal-today", None);
+            action.connect_activate(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move |_, _| {
+                    if let Some(today) = JournalDate::today() {
+                        imp.open_journal_note(today);
+                    }
+                }
+            ));
+            obj.add_action(&action);
+
+            let action = SimpleAction::new("journal-previous", None);
+            action.connect_activate(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move |_, _| {
+                    let Some(date) = imp.open_journal_date() else {
+                        return;
+                    };
+                    let dates = journal_note_dates(&imp.journal_root());
+                    match date.previous_of(&dates) {
+                        Some(date) => imp.load_document(date.note_path(&imp.journal_root())),
+                        None => imp.toast(&gettext("No earlier journal note")),
+                    }
+                }
+            ));
+            obj.add_action(&action);
+
+            let action = SimpleAction::new("journal-next", None);
+            action.connect_activate(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move |_, _| {
+                    let Some(date) = imp.open_journal_date() else {
+                        return;
+                    };
+                    let dates = journal_note_dates(&imp.journal_root());
+                    match date.next_of(&dates) {
+                        Some(date) => imp.load_document(date.note_path(&imp.journal_root())),
+                        None => imp.toast(&gettext("No later journal note")),
+                    }
+                }
+            ));
+            obj.add_action(&action);
+
+            let action = SimpleAction::new("sprint-start", None);
+            action.connect_activate(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move |_, _| {
+                    let history = load_sprint_history(&file_actions::path_sprint_history());
+                    let dialog = SprintDialog::new(imp.settings(), &history);
+                    dialog.connect_closure(
+                        "response",
+                        false,
+                        closure_local!(
+                            #[weak]
+                            imp,
+                            move |_: SprintDialog, response: String| {
+                                if response == "start" {
+                                    imp.start_sprint();
+                                }
+                            }
+                        ),
+                    );
+                    dialog.present(Some(&*imp.obj()));
+                }
+            ));
+            obj.add_action(&action);
+
+            let action = SimpleAction::new("sprint-stop", None);
+            action.set_enabled(false);
+            action.connect_activate(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move |_, _| imp.finish_sprint(false)
+            ));
+            obj.add_action(&action);
+
             let action = SimpleAction::new("toggle-sidebar", None);
             action.connect_activate(clone!(
                 #[weak]
@@ -777,9 +1039,10 @@ mod imp {
                 editor_actions.add_action(&action);
             }
 
-            fn forward_heading_action_to_editor(
+            fn forward_level_action_to_editor(
                 imp: &Window,
                 name: &str,
+                target: &'static str,
                 level: i32,
                 editor_actions: &SimpleActionGroup,
             ) {
@@ -791,7 +1054,7 @@ mod imp {
                     move |_, _| {
                         if let Some(editor) = imp.editor.borrow().as_ref() {
                             editor
-                                .activate_action("editor.format-heading", Some(&level.to_variant()))
+                                .activate_action(target, Some(&level.to_variant()))
                                 .expect(&name);
                         }
                     }
@@ -800,17 +1063,52 @@ mod imp {
             }
 
             let pi32 = Some(VariantTy::INT32);
+            let pii = Some(VariantTy::new("(ii)").unwrap());
+            let pstr = Some(VariantTy::STRING);
             forward_action_to_editor(self, "format-bold", None, &editor_actions);
             forward_action_to_editor(self, "format-italic", None, &editor_actions);
             forward_action_to_editor(self, "format-link", None, &editor_actions);
             forward_action_to_editor(self, "format-heading", pi32, &editor_actions);
-            forward_heading_action_to_editor(self, "format-h1", 1, &editor_actions);
-            forward_heading_action_to_editor(self, "format-h2", 2, &editor_actions);
-            forward_heading_action_to_editor(self, "format-h3", 3, &editor_actions);
-            forward_heading_action_to_editor(self, "format-h4", 4, &editor_actions);
-            forward_heading_action_to_editor(self, "format-h5", 5, &editor_actions);
-            forward_heading_action_to_editor(self, "format-h6", 6, &editor_actions);
+            for level in 1..=6 {
+                forward_level_action_to_editor(
+                    self,
+                    &format!("format-h{level}"),
+                    "editor.format-heading",
+                    level,
+                    &editor_actions,
+                );
+            }
             forward_action_to_editor(self, "format-code", None, &editor_actions);
+            forward_action_to_editor(self, "format-task", None, &editor_actions);
+            forward_action_to_editor(self, "format-footnote", None, &editor_actions);
+            forward_action_to_editor(self, "format-table", pii, &editor_actions);
+            forward_action_to_editor(self, "format-document", None, &editor_actions);
+            forward_action_to_editor(self, "reflow-paragraph", None, &editor_actions);
+            forward_action_to_editor(self, "unwrap-paragraph", None, &editor_actions);
+            forward_action_to_editor(self, "table-reformat", None, &editor_actions);
+            forward_action_to_editor(self, "table-insert-row", None, &editor_actions);
+            forward_action_to_editor(self, "table-remove-row", None, &editor_actions);
+            forward_action_to_editor(self, "table-insert-column", None, &editor_actions);
+            forward_action_to_editor(self, "table-remove-column", None, &editor_actions);
+            forward_action_to_editor(self, "table-align", pstr, &editor_actions);
+            forward_action_to_editor(self, "move-lines-up", None, &editor_actions);
+            forward_action_to_editor(self, "move-lines-down", None, &editor_actions);
+            forward_action_to_editor(self, "move-section-up", None, &editor_actions);
+            forward_action_to_editor(self, "move-section-down", None, &editor_actions);
+            forward_action_to_editor(self, "promote-section", None, &editor_actions);
+            forward_action_to_editor(self, "demote-section", None, &editor_actions);
+            forward_action_to_editor(self, "fold-toggle", None, &editor_actions);
+            forward_action_to_editor(self, "fold-to-level", pi32, &editor_actions);
+            forward_action_to_editor(self, "unfold-all", None, &editor_actions);
+            for level in 1..=6 {
+                forward_level_action_to_editor(
+                    self,
+                    &format!("fold-level-{level}"),
+                    "editor.fold-to-level",
+                    level,
+                    &editor_actions,
+                );
+            }
             forward_action_to_editor(self, "show-search", None, &editor_actions);
             forward_action_to_editor(self, "show-search-replace", None, &editor_actions);
             forward_action_to_editor(self, "hide-search", None, &editor_actions);
@@ -877,10 +1175,35 @@ mod imp {
             self.settings.get().expect("Settings uninitialized.")
         }
 
+        /// Rebuild the help overlay so it shows the current shortcuts.
+        /// Actions without a default shortcut get listed when the user gives them one.
+        fn setup_help_overlay(&self) {
+            let builder = Builder::from_resource("/org/scratchmark/Scratchmark/ui/shortcuts.ui");
+            let shortcuts = builder.object("help_overlay").unwrap();
+            let custom_group: ShortcutsGroup = builder.object("custom_group").unwrap();
+            let custom = crate::accels::custom_accels(self.settings());
+            for (action, defaults) in crate::accels::ACCELS {
+                if !defaults.is_empty() || custom.get(*action).is_none_or(String::is_empty) {
+                    continue;
+                }
+                let Some(title) = crate::accels::title(action) else {
+                    continue;
+                };
+                let shortcut = ShortcutsShortcut::builder()
+                    .title(title)
+                    .action_name(*action)
+                    .build();
+                custom_group.add_shortcut(&shortcut);
+                custom_group.set_visible(true);
+            }
+            self.obj().set_help_overlay(Some(&shortcuts));
+        }
+
         fn update_window_title(&self) {
             let binding = self.editor.borrow();
             let Some(editor) = binding.as_ref() else {
                 self.window_title.set_filename(None::<String>);
+                self.window_title.set_journal_navigation(false);
                 return;
             };
             let filename = editor
@@ -888,6 +1211,207 @@ mod imp {
                 .file_stem()
                 .map(|d| d.to_string_lossy().to_string());
             self.window_title.set_filename(filename);
+            self.window_title
+                .set_journal_navigation(self.open_journal_date().is_some());
+        }
+
+        fn start_sprint(&self) {
+            let obj = self.obj();
+            let settings = self.settings();
+            let restore_focus_mode = settings.boolean("sprint-focus-mode") && !obj.focus_mode();
+            if restore_focus_mode {
+                obj.change_action_state("focus-mode", &true.to_variant());
+            }
+
+            let timer = glib::timeout_add_seconds_local(
+                1,
+                clone!(
+                    #[weak(rename_to = imp)]
+                    self,
+                    #[upgrade_or]
+                    glib::ControlFlow::Break,
+                    move || imp.on_sprint_tick()
+                ),
+            );
+            self.sprint.replace(Some(Sprint {
+                started: glib::DateTime::now_local().unwrap(),
+                start: Instant::now(),
+                duration: Duration::from_secs(settings.uint("sprint-duration") as u64 * 60),
+                word_target: settings.uint("sprint-word-target"),
+                words_banked: 0,
+                baseline: None,
+                restore_focus_mode,
+                timer,
+            }));
+            self.update_sprint_status();
+        }
+
+        fn on_sprint_tick(&self) -> glib::ControlFlow {
+            let finished = match self.sprint.borrow().as_ref() {
+                Some(sprint) => sprint.start.elapsed() >= sprint.duration,
+                None => return glib::ControlFlow::Break,
+            };
+            if finished {
+                self.finish_sprint(true);
+                return glib::ControlFlow::Break;
+            }
+            self.update_sprint_status();
+            glib::ControlFlow::Continue
+        }
+
+        /// Words written since the sprint started, across all documents
+        fn sprint_words(&self) -> i32 {
+            let mut binding = self.sprint.borrow_mut();
+            let Some(sprint) = binding.as_mut() else {
+                return 0;
+            };
+            let Some(editor) = self.editor.borrow().clone() else {
+                return sprint.words_banked;
+            };
+            let words = editor.document_stats().num_words;
+            match &sprint.baseline {
+                Some((baseline_editor, baseline_words)) if *baseline_editor == editor => {
+                    sprint.words_banked + words - baseline_words
+                }
+                _ => {
+                    sprint.baseline = Some((editor, words));
+                    sprint.words_banked
+                }
+            }
+        }
+
+        /// Keep the words of the open document before it's closed
+        fn bank_sprint_words(&self) {
+            let words = self.sprint_words();
+            if let Some(sprint) = self.sprint.borrow_mut().as_mut() {
+                sprint.words_banked = words;
+                sprint.baseline = None;
+            }
+        }
+
+        fn update_sprint_status(&self) {
+            let words = self.sprint_words();
+            let binding = self.sprint.borrow();
+            let Some(sprint) = binding.as_ref() else {
+                self.window_title.set_sprint_status(None::<String>);
+                return;
+            };
+            let remaining = sprint
+                .duration
+                .saturating_sub(sprint.start.elapsed())
+                .as_secs();
+            let words = if sprint.word_target > 0 {
+                gettext("{words}/{target} words")
+                    .replace("{words}", &words.to_string())
+                    .replace("{target}", &sprint.word_target.to_string())
+            } else {
+                gettext("{words} words").replace("{words}", &words.to_string())
+            };
+            let status = format!("{}:{:02} · {words}", remaining / 60, remaining % 60);
+            self.window_title.set_sprint_status(Some(status));
+
+            let obj = self.obj();
+            obj.action_set_enabled("win.sprint-start", false);
+            obj.action_set_enabled("win.sprint-stop", true);
+        }
+
+        /// End the sprint, record it in the history and show how it went.
+        /// The timer removes itself when the sprint ends on time.
+        fn finish_sprint(&self, from_timer: bool) {
+            let words = self.sprint_words();
+            let Some(sprint) = self.sprint.take() else {
+                return;
+            };
+            if !from_timer {
+                sprint.timer.remove();
+            }
+            let obj = self.obj();
+            if sprint.restore_focus_mode {
+                obj.change_action_state("focus-mode", &false.to_variant());
+            }
+            self.window_title.set_sprint_status(None::<String>);
+            obj.action_set_enabled("win.sprint-start", true);
+            obj.action_set_enabled("win.sprint-stop", false);
+
+            let record = SprintRecord {
+                started: sprint.started.to_unix(),
+                duration: sprint.duration.as_secs() as u32,
+                elapsed: sprint.start.elapsed().min(sprint.duration).as_secs() as u32,
+                words,
+                word_target: sprint.word_target,
+            };
+            if let Err(e) = append_sprint_history(&file_actions::path_sprint_history(), &record) {
+                self.toast(&e.to_string());
+            }
+
+            let mut body =
+                gettext("You wrote {words} words in {minutes} minutes, {wpm} words per minute.")
+                    .replace("{words}", &record.words.to_string())
+                    .replace("{minutes}", &(record.elapsed / 60).to_string())
+                    .replace("{wpm}", &format!("{:.0}", record.words_per_minute()));
+            if record.target_reached() {
+                body = format!("{body}\n{}", gettext("You reached your target!"));
+            }
+            let dialog = adw::AlertDialog::new(Some(&gettext("Sprint Finished")), Some(&body));
+            dialog.add_response("close", &gettext("_Close"));
+            dialog.present(Some(&*obj));
+        }
+
+        /// Journal notes go into the configured project, or the drafts library
+        fn journal_root(&self) -> PathBuf {
+            let path = self.settings().string("journal-project-path");
+            if path.is_empty() {
+                file_actions::path_builtin_library()
+            } else {
+                PathBuf::from(path.as_str())
+            }
+        }
+
+        /// The day of the open document, if it's a journal note
+        fn open_journal_date(&self) -> Option<JournalDate> {
+            let path = self.editor.borrow().as_ref()?.path();
+            JournalDate::of_note(&path, &self.journal_root())
+        }
+
+        /// Open the note of the day, creating it first if needed
+        fn open_journal_note(&self, date: JournalDate) {
+            let root = self.journal_root();
+            let path = date.note_path(&root);
+            if !path.exists() {
+                if let Err(e) = self.create_journal_note(&root, &path, date) {
+                    self.toast(&e.to_string());
+                    return;
+                }
+                self.library_view.refresh_content();
+            }
+            self.load_document(path);
+        }
+
+        fn create_journal_note(
+            &self,
+            root: &Path,
+            path: &Path,
+            date: JournalDate,
+        ) -> Result<(), ScratchmarkError> {
+            if let Err(e) = std::fs::create_dir_all(path.parent().unwrap()) {
+                println!("{e}");
+                return Err(ScratchmarkError::FolderCreateFail);
+            }
+            let template_name = self.settings().string("journal-template");
+            let text = match Template::available(root)
+                .into_iter()
+                .find(|template| template.name == template_name.as_str())
+            {
+                Some(template) => template.read()?,
+                None => "# {{title}}\n\n".to_owned(),
+            };
+            let date = date.to_string();
+            let values = TemplateValues {
+                title: &date,
+                date: &date,
+                project: &project_name(root),
+            };
+            file_actions::create_document_with_contents(path, &fill_template(&text, &values))
         }
 
         fn update_toolbar_style(&self) {
@@ -1058,6 +1582,18 @@ mod imp {
                 ),
             );
 
+            editor.connect_closure(
+                "lint-changed",
+                false,
+                closure_local!(
+                    #[weak(rename_to = imp)]
+                    self,
+                    move |editor: EditorView| {
+                        imp.lint_panel.set_diagnostics(&editor.lint_diagnostics());
+                    }
+                ),
+            );
+
             let window_title: &WindowTitle = self.window_title.as_ref();
             editor
                 .bind_property("unsaved-changes", window_title, "unsaved-changes")
@@ -1088,6 +1624,50 @@ mod imp {
             settings
                 .bind("typewriter-mode", &editor, "typewriter_mode")
                 .build();
+            settings
+                .bind("editor-assets-folder", &editor, "assets_folder")
+                .build();
+            settings
+                .bind("editor-show-image-previews", &editor, "show_image_previews")
+                .build();
+            settings
+                .bind("editor-smart-typography", &editor, "smart_typography")
+                .build();
+            settings
+                .bind("editor-style-check", &editor, "style_check")
+                .build();
+            settings
+                .bind("format-bullet", &editor, "format_bullet")
+                .build();
+            settings
+                .bind("format-emphasis", &editor, "format_emphasis")
+                .build();
+            settings
+                .bind("format-rewrap", &editor, "format_rewrap")
+                .build();
+            settings
+                .bind("editor-wrap-column", &editor, "wrap_column")
+                .build();
+            settings
+                .bind("editor-focus-scope", &editor, "focus_scope")
+                .build();
+            settings
+                .bind("editor-keybindings", &editor, "keybindings")
+                .build();
+            settings
+                .bind("editor-style-scheme", &editor, "style_scheme")
+                .build();
+
+            self.restore_folds(&editor);
+            editor.connect_closure(
+                "folds-changed",
+                false,
+                closure_local!(
+                    #[weak(rename_to = imp)]
+                    self,
+                    move |editor: EditorView| imp.store_folds(&editor)
+                ),
+            );
 
             self.main_toolbar_view.set_content(Some(&editor));
             self.format_bar.bind_editor(Some(editor.clone()));
@@ -1096,6 +1676,197 @@ mod imp {
             self.set_editor_actions_enabled(true);
             self.update_window_title();
             self.update_toolbar_style();
+            if self.lint_panel.is_visible() {
+                self.lint_document();
+            }
+        }
+
+        /// Fold the sections that were folded when the document was last open
+        fn restore_folds(&self, editor: &EditorView) {
+            let folds: HashMap<String, Vec<String>> = self
+                .settings()
+                .value("editor-folded-headings")
+                .get()
+                .unwrap_or_default();
+            if let Some(headings) = folds.get(editor.path().to_string_lossy().as_ref()) {
+                editor.fold_headings(headings);
+            }
+        }
+
+        /// Remember the folded sections of the document. Documents that are gone are forgotten.
+        fn store_folds(&self, editor: &EditorView) {
+            let settings = self.settings();
+            let mut folds: HashMap<String, Vec<String>> = settings
+                .value("editor-folded-headings")
+                .get()
+                .unwrap_or_default();
+            folds.retain(|path, _| Path::new(path).exists());
+            let path = editor.path().to_string_lossy().into_owned();
+            let headings = editor.folded_headings();
+            if headings.is_empty() {
+                folds.remove(&path);
+            } else {
+                folds.insert(path, headings);
+            }
+            settings
+                .set_value("editor-folded-headings", &folds.to_variant())
+                .unwrap();
+        }
+
+        /// Lint the open document with the rules of its project.
+        /// It's linted again after changes, until the panel is closed.
+        fn lint_document(&self) {
+            let binding = self.editor.borrow();
+            let Some(editor) = binding.as_ref() else {
+                return;
+            };
+            let config = LintConfig::load(&self.lint_project_path(&editor.path()));
+            self.lint_panel.set_config(&config);
+            self.lint_panel.set_visible(true);
+            editor.set_lint_config(Some(config));
+        }
+
+        /// Lint rules come from the root of the project, or the folder of a loose document
+        fn lint_project_path(&self, document_path: &Path) -> PathBuf {
+            self.library_view
+                .project_path_of(document_path)
+                .or_else(|| document_path.parent().map(|p| p.to_path_buf()))
+                .unwrap_or_default()
+        }
+
+        /// Show broken links of the open document, or of every document in its project
+        fn check_links(&self, whole_project: bool) {
+            let binding = self.editor.borrow();
+            let Some(editor) = binding.as_ref() else {
+                return;
+            };
+            let open_path = editor.path();
+            let base = self
+                .library_view
+                .project_path_of(&open_path)
+                .or_else(|| open_path.parent().map(|p| p.to_path_buf()))
+                .unwrap_or_default();
+
+            let mut results = vec![(open_path.clone(), editor.broken_links())];
+            let generation = self.link_check_generation.get().wrapping_add(1);
+            self.link_check_generation.set(generation);
+            if !whole_project {
+                self.link_check_panel.set_results(&base, results);
+                return;
+            }
+
+            // Reading every document takes a while in big projects
+            let paths: Vec<PathBuf> = self
+                .library_view
+                .project_documents(&open_path)
+                .into_iter()
+                .filter(|path| *path != open_path)
+                .collect();
+            let task = gio::spawn_blocking(move || {
+                paths
+                    .into_iter()
+                    .filter_map(|path| {
+                        let text = std::fs::read_to_string(&path).ok()?;
+                        let broken = broken_links(&text, &path);
+                        (!broken.is_empty()).then_some((path, broken))
+                    })
+                    .collect::<Vec<_>>()
+            });
+            glib::spawn_future_local(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                async move {
+                    let Ok(project_results) = task.await else {
+                        return;
+                    };
+                    if imp.link_check_generation.get() != generation {
+                        return;
+                    }
+                    results.extend(project_results);
+                    imp.link_check_panel.set_results(&base, results);
+                }
+            ));
+        }
+
+        /// Link edits for every document in the project of the moved item. Call before moving,
+        /// while the documents are still at their old paths.
+        fn collect_link_edits(&self, moved: &MovedItem) -> Vec<(PathBuf, Vec<LinkEdit>)> {
+            let Some(project_root) = self.library_view.project_path_of(&moved.old_path) else {
+                return Vec::new();
+            };
+            let open_path = self.editor.borrow().as_ref().map(|editor| editor.path());
+
+            let mut changes = Vec::new();
+            for path in self.library_view.project_documents(&moved.old_path) {
+                let text = if open_path.as_ref() == Some(&path) {
+                    self.editor.borrow().as_ref().unwrap().text()
+                } else if let Ok(text) = std::fs::read_to_string(&path) {
+                    text
+                } else {
+                    continue;
+                };
+                let edits = link_edits(&text, &path, &project_root, moved);
+                if !edits.is_empty() {
+                    changes.push((path, edits));
+                }
+            }
+            changes
+        }
+
+        fn prompt_link_update(&self, moved: MovedItem, changes: Vec<(PathBuf, Vec<LinkEdit>)>) {
+            if changes.is_empty() {
+                return;
+            }
+            let base = self
+                .library_view
+                .project_path_of(&moved.new_path)
+                .unwrap_or_default();
+            let dialog = LinkUpdateDialog::new(&base, &changes);
+            dialog.connect_closure(
+                "response",
+                false,
+                closure_local!(
+                    #[weak(rename_to = imp)]
+                    self,
+                    move |_: LinkUpdateDialog, response: String| {
+                        if response == "update" {
+                            imp.apply_link_updates(&moved, &changes);
+                        }
+                    }
+                ),
+            );
+            dialog.present(Some(&*self.obj()));
+        }
+
+        /// Write the link edits. The open document is edited in the buffer, so it can be undone.
+        /// Documents can change while the dialog is open, so the edits are found again in the
+        /// current text of the documents that were previewed.
+        fn apply_link_updates(&self, moved: &MovedItem, changes: &[(PathBuf, Vec<LinkEdit>)]) {
+            let Some(project_root) = self.library_view.project_path_of(&moved.new_path) else {
+                return;
+            };
+            for (old_path, _) in changes {
+                let path = moved.map(old_path).unwrap_or_else(|| old_path.clone());
+                if let Some(editor) = self.editor.borrow().as_ref()
+                    && editor.path() == path
+                {
+                    let edits = link_edits(&editor.text(), old_path, &project_root, moved);
+                    editor.apply_link_edits(&edits);
+                    continue;
+                }
+                let result = std::fs::read_to_string(&path).and_then(|text| {
+                    let edits = link_edits(&text, old_path, &project_root, moved);
+                    if edits.is_empty() {
+                        return Ok(());
+                    }
+                    std::fs::write(&path, apply_link_edits(&text, &edits))
+                });
+                if let Err(e) = result {
+                    println!("{e}");
+                    self.toast(&ScratchmarkError::FileWriteFail.to_string());
+                }
+            }
+            self.library_view.refresh_content();
         }
 
         fn close_editor(&self) -> Result<(), ScratchmarkError> {
@@ -1107,7 +1878,12 @@ mod imp {
         }
 
         fn close_editor_without_saving(&self) {
+            self.bank_sprint_words();
+            if let Some(editor) = self.editor.borrow().as_ref() {
+                self.store_folds(editor);
+            }
             self.editor.replace(None);
+            self.lint_panel.set_diagnostics(&[]);
             self.main_toolbar_view
                 .set_content(Some(&EditorPlaceholder::default()));
             self.update_window_title();
@@ -1123,11 +1899,35 @@ mod imp {
             obj.action_set_enabled("win.file-save", enabled);
             obj.action_set_enabled("win.file-rename-selected", enabled);
             obj.action_set_enabled("win.file-close", enabled);
+            obj.action_set_enabled("win.check-links", enabled);
+            obj.action_set_enabled("win.check-links-project", enabled);
+            obj.action_set_enabled("win.lint-document", enabled);
             obj.action_set_enabled("editor.format-bold", enabled);
             obj.action_set_enabled("editor.format-italic", enabled);
             obj.action_set_enabled("editor.format-link", enabled);
             obj.action_set_enabled("editor.format-heading", enabled);
+            obj.action_set_enabled("editor.move-lines-up", enabled);
+            obj.action_set_enabled("editor.move-lines-down", enabled);
+            obj.action_set_enabled("editor.move-section-up", enabled);
+            obj.action_set_enabled("editor.move-section-down", enabled);
+            obj.action_set_enabled("editor.promote-section", enabled);
+            obj.action_set_enabled("editor.demote-section", enabled);
+            obj.action_set_enabled("editor.fold-toggle", enabled);
+            obj.action_set_enabled("editor.fold-to-level", enabled);
+            obj.action_set_enabled("editor.unfold-all", enabled);
             obj.action_set_enabled("editor.format-code", enabled);
+            obj.action_set_enabled("editor.format-task", enabled);
+            obj.action_set_enabled("editor.format-footnote", enabled);
+            obj.action_set_enabled("editor.format-table", enabled);
+            obj.action_set_enabled("editor.format-document", enabled);
+            obj.action_set_enabled("editor.reflow-paragraph", enabled);
+            obj.action_set_enabled("editor.unwrap-paragraph", enabled);
+            obj.action_set_enabled("editor.table-reformat", enabled);
+            obj.action_set_enabled("editor.table-insert-row", enabled);
+            obj.action_set_enabled("editor.table-remove-row", enabled);
+            obj.action_set_enabled("editor.table-insert-column", enabled);
+            obj.action_set_enabled("editor.table-remove-column", enabled);
+            obj.action_set_enabled("editor.table-align", enabled);
             obj.action_set_enabled("editor.show-search", enabled);
             obj.action_set_enabled("editor.show-search-replace", enabled);
             obj.action_set_enabled("editor.hide-search", enabled);
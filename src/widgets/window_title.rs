@@ -15,6 +15,10 @@ mod imp {
     pub struct WindowTitle {
         #[template_child]
         window_title: TemplateChild<adw::WindowTitle>,
+        #[template_child]
+        journal_previous_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        journal_next_button: TemplateChild<gtk::Button>,
 
         #[property(get, set, nullable)]
         filename: RefCell<Option<String>>,
@@ -22,6 +26,12 @@ mod imp {
         unsaved_changes: Cell<bool>,
         #[property(get, set)]
         focus_mode: Cell<bool>,
+        /// Show buttons for the previous and next journal note
+        #[property(get, set)]
+        journal_navigation: Cell<bool>,
+        /// Countdown of a running writing sprint, shown below the title
+        #[property(get, set, nullable)]
+        sprint_status: RefCell<Option<String>>,
     }
 
     #[glib::object_subclass]
@@ -57,6 +67,12 @@ mod imp {
 
     impl WindowTitle {
         fn update_window_title(&self) {
+            let journal_navigation = self.journal_navigation.get();
+            self.journal_previous_button.set_visible(journal_navigation);
+            self.journal_next_button.set_visible(journal_navigation);
+            self.window_title
+                .set_subtitle(self.sprint_status.borrow().as_deref().unwrap_or_default());
+
             let Some(filename) = self.filename.borrow().as_ref().cloned() else {
                 self.window_title.set_title("Scratchmark");
                 return;